@@ -0,0 +1,107 @@
+// Panda api docs live reload
+// Listens on the internal websocket channel for doc file changes, reloads the page
+// when the open document changes and shows parse errors as soon as they happen.
+(function () {
+  var RELOAD_URL = "/__api_docs/ws/";
+  var retry = 1000;
+  var banner = null;
+  var errorFile = null;
+
+  function currentPath() {
+    var hash = window.location.hash;
+    var i = hash.indexOf("?");
+    if (i < 0) {
+      return "";
+    }
+    var params = hash.substring(i + 1).split("&");
+    for (var n = 0; n < params.length; n++) {
+      var kv = params[n].split("=");
+      if (kv[0] === "path") {
+        return decodeURIComponent(kv[1] || "");
+      }
+    }
+    return "";
+  }
+
+  function isOpenDoc(filename) {
+    var path = currentPath();
+    if (!path) {
+      // home page shows README.md and the menus
+      return true;
+    }
+    return path === filename || path.indexOf(filename) === 0 || filename.indexOf("_data/") >= 0;
+  }
+
+  function showError(filename, error) {
+    if (!banner) {
+      banner = document.createElement("pre");
+      banner.style.cssText = "position:fixed;left:0;right:0;bottom:0;z-index:9999;margin:0;" +
+        "max-height:40%;overflow:auto;padding:12px 16px;background:#fef0f0;color:#f56c6c;" +
+        "border-top:2px solid #f56c6c;font-size:13px;white-space:pre-wrap;";
+      banner.onclick = hideError;
+      document.body.appendChild(banner);
+    }
+    errorFile = filename;
+    banner.textContent = filename + " parse error:\n" + error;
+  }
+
+  function hideError() {
+    if (banner) {
+      document.body.removeChild(banner);
+      banner = null;
+    }
+    errorFile = null;
+  }
+
+  // doc pages of a mounted project carry its prefix, ex: ?server_url=/svc-order
+  function serverUrl() {
+    var params = window.location.search.replace("?", "").split("&");
+    for (var n = 0; n < params.length; n++) {
+      var kv = params[n].split("=");
+      if (kv[0] === "server_url") {
+        return decodeURIComponent(kv[1] || "").replace(/\/$/, "");
+      }
+    }
+    return "";
+  }
+
+  function connect() {
+    var protocol = window.location.protocol === "https:" ? "wss://" : "ws://";
+    var url = serverUrl();
+    if (url.indexOf("http") === 0) {
+      url = url.replace(/^http/, "ws") + RELOAD_URL;
+    } else {
+      url = protocol + window.location.host + url + RELOAD_URL;
+    }
+    var ws = new WebSocket(url);
+    ws.onopen = function () {
+      retry = 1000;
+    };
+    ws.onmessage = function (e) {
+      var data;
+      try {
+        data = JSON.parse(e.data);
+      } catch (err) {
+        return;
+      }
+      if (data.event === "error") {
+        showError(data.filename, data.error);
+        return;
+      }
+      if (errorFile === data.filename) {
+        hideError();
+      }
+      if (isOpenDoc(data.filename)) {
+        window.location.reload();
+      }
+    };
+    ws.onclose = function () {
+      setTimeout(connect, retry);
+      retry = Math.min(retry * 2, 30000);
+    };
+  }
+
+  if (window.WebSocket) {
+    connect();
+  }
+})();
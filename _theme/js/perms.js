@@ -0,0 +1,48 @@
+// Panda api permission matrix link
+// Adds a link to the permission matrix page when _auth.json5 has groups.
+(function () {
+  if (window.PANDA_STATIC_DATA) {
+    // the exported static site has no server to build the matrix
+    return;
+  }
+
+  // doc pages of a mounted project carry its prefix, ex: ?server_url=/svc-order
+  function serverUrl() {
+    var params = window.location.search.replace("?", "").split("&");
+    for (var n = 0; n < params.length; n++) {
+      var kv = params[n].split("=");
+      if (kv[0] === "server_url") {
+        return decodeURIComponent(kv[1] || "").replace(/\/$/, "");
+      }
+    }
+    return "";
+  }
+
+  function addLink(url) {
+    var style = document.createElement("style");
+    style.textContent = ".perms-link{position:fixed;bottom:16px;right:24px;z-index:2000;padding:6px 12px;" +
+      "background:#fff;border:1px solid #dcdfe6;border-radius:4px;color:#409eff;font-size:13px;text-decoration:none}" +
+      ".perms-link:hover{border-color:#409eff}";
+    document.head.appendChild(style);
+    var link = document.createElement("a");
+    link.className = "perms-link";
+    link.href = url + "?format=html";
+    link.target = "_blank";
+    link.textContent = "Permissions";
+    document.body.appendChild(link);
+  }
+
+  var url = serverUrl() + "/__api_docs/perms/";
+  var xhr = new XMLHttpRequest();
+  xhr.open("GET", url + "?format=json");
+  xhr.onload = function () {
+    try {
+      if (JSON.parse(xhr.responseText).groups.length) {
+        addLink(url);
+      }
+    } catch (e) {
+      // no auth, no link
+    }
+  };
+  xhr.send();
+})();
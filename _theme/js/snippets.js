@@ -0,0 +1,157 @@
+// Panda api request snippets
+// Shows the curl, HTTPie, fetch, axios, Python requests and Rust reqwest snippets of the
+// open api and its test_data cases, using the server selected in the doc page.
+(function () {
+  var LANGS = [
+    ["curl", "curl"],
+    ["httpie", "HTTPie"],
+    ["fetch", "fetch"],
+    ["axios", "axios"],
+    ["python", "Python"],
+    ["reqwest", "Rust"]
+  ];
+  var state = { item: 0, lang: "curl" };
+  var panel = null;
+
+  function el(tag, className, text) {
+    var e = document.createElement(tag);
+    if (className) {
+      e.className = className;
+    }
+    if (text) {
+      e.textContent = text;
+    }
+    return e;
+  }
+
+  function addStyle() {
+    var style = el("style");
+    style.textContent = ".snippets{margin:24px 0;border:1px solid #ebeef5;border-radius:4px;background:#fff}" +
+      ".snippets__bar{display:flex;flex-wrap:wrap;align-items:center;padding:8px 12px;border-bottom:1px solid #ebeef5;font-size:13px}" +
+      ".snippets__bar select{margin-right:12px}" +
+      ".snippets__tab{margin-right:12px;color:#606266;cursor:pointer}" +
+      ".snippets__tab.is-active{color:#409eff}" +
+      ".snippets__copy{margin-left:auto;color:#409eff;cursor:pointer}" +
+      ".snippets pre{margin:0;padding:12px 16px;overflow:auto;font-size:13px;line-height:1.5;background:#fafafa}";
+    document.head.appendChild(style);
+  }
+
+  function findStore() {
+    var children = document.body.children;
+    for (var i = 0; i < children.length; i++) {
+      if (children[i].__vue__ && children[i].__vue__.$store) {
+        return children[i].__vue__.$store;
+      }
+    }
+    return null;
+  }
+
+  // snippets are generated for the server the page was loaded from, swap in the selected one
+  function code(snippets, item, server) {
+    var text = item[state.lang] || "";
+    if (server && server.url && snippets.base_url) {
+      text = text.split(snippets.base_url).join(server.url.replace(/\/$/, ""));
+    }
+    return text;
+  }
+
+  function copy(text) {
+    if (navigator.clipboard) {
+      navigator.clipboard.writeText(text);
+      return;
+    }
+    var area = el("textarea");
+    area.value = text;
+    document.body.appendChild(area);
+    area.select();
+    document.execCommand("copy");
+    document.body.removeChild(area);
+  }
+
+  function render(api, server) {
+    if (panel && panel.parentNode) {
+      panel.parentNode.removeChild(panel);
+    }
+    panel = null;
+    var container = document.querySelector(".api");
+    if (!api || !api.snippets || !api.snippets.items.length || !container) {
+      return;
+    }
+    var snippets = api.snippets;
+    if (state.item >= snippets.items.length) {
+      state.item = 0;
+    }
+    var item = snippets.items[state.item];
+
+    panel = el("div", "snippets");
+    var bar = el("div", "snippets__bar");
+    if (snippets.items.length > 1) {
+      var select = el("select");
+      for (var i = 0; i < snippets.items.length; i++) {
+        var option = el("option", "", snippets.items[i].method + (snippets.items[i].name ? " - " + snippets.items[i].name : ""));
+        option.value = i;
+        option.selected = i === state.item;
+        select.appendChild(option);
+      }
+      select.onchange = function () {
+        state.item = parseInt(select.value, 10);
+        render(api, server);
+      };
+      bar.appendChild(select);
+    }
+    LANGS.forEach(function (lang) {
+      var tab = el("span", "snippets__tab" + (lang[0] === state.lang ? " is-active" : ""), lang[1]);
+      tab.onclick = function () {
+        state.lang = lang[0];
+        render(api, server);
+      };
+      bar.appendChild(tab);
+    });
+    var text = code(snippets, item, server);
+    var copyButton = el("span", "snippets__copy", "copy");
+    copyButton.onclick = function () {
+      copy(text);
+      copyButton.textContent = "copied";
+    };
+    bar.appendChild(copyButton);
+    panel.appendChild(bar);
+    panel.appendChild(el("pre", "", text));
+    container.appendChild(panel);
+  }
+
+  function watch(store) {
+    var timer = null;
+    var update = function () {
+      // the api page is a lazy loaded chunk, wait until it is rendered
+      var retry = 20;
+      clearTimeout(timer);
+      var tryRender = function () {
+        if (document.querySelector(".api") || retry-- <= 0) {
+          render(store.state.api, store.state.server);
+        } else {
+          timer = setTimeout(tryRender, 100);
+        }
+      };
+      timer = setTimeout(tryRender, 0);
+    };
+    store.watch(function (s) {
+      return [s.api, s.server];
+    }, update);
+    window.addEventListener("hashchange", update);
+    update();
+  }
+
+  function start(retry) {
+    var store = findStore();
+    if (store) {
+      addStyle();
+      watch(store);
+    } else if (retry > 0) {
+      setTimeout(function () {
+        start(retry - 1);
+      }, 200);
+    }
+  }
+
+  start(50);
+})();
@@ -0,0 +1,178 @@
+// Panda api static site
+// Answers the /__api_docs/ requests of the doc pages from the data that `panda export html`
+// bakes into data.js, so the site works on any static host, and adds a search box over the
+// docs, apis and their fields.
+(function () {
+  var DATA = window.PANDA_STATIC_DATA || {};
+  var SEP = "\\\\";
+  var MAX_RESULTS = 20;
+
+  function lookup(key) {
+    if (key in DATA) {
+      return DATA[key];
+    }
+    try {
+      key = decodeURIComponent(key);
+    } catch (e) {
+      return undefined;
+    }
+    return DATA[key];
+  }
+
+  // the doc pages load their data with XMLHttpRequest, point those requests to blobs of the baked data
+  var open = XMLHttpRequest.prototype.open;
+  XMLHttpRequest.prototype.open = function (method, url) {
+    var args = Array.prototype.slice.call(arguments);
+    var i = typeof url === "string" ? url.indexOf("/__api_docs/") : -1;
+    if (i >= 0) {
+      var value = lookup(url.substring(i));
+      if (value === undefined) {
+        value = { code: -1, msg: "not found in the exported docs" };
+      }
+      var text = typeof value === "string" ? value : JSON.stringify(value);
+      args[0] = "GET";
+      args[1] = URL.createObjectURL(new Blob([text], { type: "application/json" }));
+    }
+    return open.apply(this, args);
+  };
+
+  function el(tag, className, text) {
+    var e = document.createElement(tag);
+    if (className) {
+      e.className = className;
+    }
+    if (text) {
+      e.textContent = text;
+    }
+    return e;
+  }
+
+  // field names and descriptions of a body, query or response model
+  function fieldText(model, words) {
+    if (Array.isArray(model)) {
+      model.forEach(function (item) {
+        fieldText(item, words);
+      });
+    } else if (model && typeof model === "object") {
+      Object.keys(model).forEach(function (key) {
+        if (key.charAt(0) !== "$") {
+          words.push(key);
+        }
+        fieldText(model[key], words);
+      });
+    } else if (typeof model === "string") {
+      words.push(model);
+    }
+    return words;
+  }
+
+  function buildIndex() {
+    var index = [];
+    var basic = DATA["/__api_docs/"];
+    if (!basic) {
+      return index;
+    }
+
+    function children(node) {
+      if (!node.children) {
+        return [];
+      }
+      return Array.isArray(node.children) ? node.children : Object.values(node.children);
+    }
+
+    function walk(node, path) {
+      var data = node.filename ? DATA["/__api_docs/api_data/?filename=" + node.filename] : null;
+      if (node.filetype === "md") {
+        index.push({ route: "doc", path: path, title: node.name, sub: node.filename,
+          text: (data && data.content) || "" });
+      } else if (node.filetype === "json5" && data && data.apis) {
+        index.push({ route: "api-info", path: path, title: node.name, sub: node.filename, text: node.desc || "" });
+        data.apis.forEach(function (api) {
+          var fields = fieldText([api.body, api.query, api.response], []);
+          index.push({ route: "api", path: path + SEP + api.name, title: api.name,
+            sub: (api.method || []).join(",") + " " + api.url, text: (api.desc || "") + " " + fields.join(" ") });
+        });
+        return;
+      }
+      children(node).forEach(function (child) {
+        walk(child, path + SEP + child.name);
+      });
+    }
+
+    basic.api_docs.forEach(function (doc) {
+      if (doc.filetype !== "auth" && doc.filetype !== "settings") {
+        walk(doc, doc.name);
+      }
+    });
+    return index;
+  }
+
+  function search(index, q) {
+    var words = q.toLowerCase().split(/\s+/).filter(Boolean);
+    var results = [];
+    index.forEach(function (entry) {
+      var title = entry.title.toLowerCase();
+      var sub = entry.sub.toLowerCase();
+      var text = entry.text.toLowerCase();
+      var score = 0;
+      for (var i = 0; i < words.length; i++) {
+        var w = words[i];
+        var s = (title.indexOf(w) >= 0 ? 5 : 0) + (sub.indexOf(w) >= 0 ? 3 : 0) + (text.indexOf(w) >= 0 ? 1 : 0);
+        if (!s) {
+          return;
+        }
+        score += s;
+      }
+      results.push({ entry: entry, score: score });
+    });
+    results.sort(function (a, b) {
+      return b.score - a.score;
+    });
+    return results.slice(0, MAX_RESULTS);
+  }
+
+  function addSearch() {
+    var style = el("style");
+    style.textContent = ".static-search{position:fixed;top:12px;right:24px;z-index:2000;width:320px;font-size:13px}" +
+      ".static-search input{box-sizing:border-box;width:100%;padding:6px 10px;border:1px solid #dcdfe6;border-radius:4px;outline:0}" +
+      ".static-search ul{margin:4px 0 0;padding:0;list-style:none;max-height:420px;overflow:auto;background:#fff;border:1px solid #ebeef5;border-radius:4px;box-shadow:0 2px 12px rgba(0,0,0,.1)}" +
+      ".static-search li{padding:8px 10px;cursor:pointer;border-bottom:1px solid #f2f6fc}" +
+      ".static-search li:hover{background:#f5f7fa}" +
+      ".static-search .sub{color:#909399;font-size:12px}";
+    document.head.appendChild(style);
+
+    var box = el("div", "static-search");
+    var input = el("input");
+    input.placeholder = "Search docs, apis and fields";
+    var list = el("ul");
+    list.style.display = "none";
+    box.appendChild(input);
+    box.appendChild(list);
+    document.body.appendChild(box);
+
+    var index = null;
+    input.oninput = function () {
+      index = index || buildIndex();
+      list.innerHTML = "";
+      var results = input.value.trim() ? search(index, input.value) : [];
+      list.style.display = results.length ? "block" : "none";
+      results.forEach(function (result) {
+        var entry = result.entry;
+        var li = el("li");
+        li.appendChild(el("div", "", entry.title));
+        li.appendChild(el("div", "sub", entry.sub));
+        li.onclick = function () {
+          window.location.hash = "#/" + entry.route + "?path=" + encodeURIComponent(entry.path);
+          list.style.display = "none";
+        };
+        list.appendChild(li);
+      });
+    };
+  }
+
+  if (document.readyState === "loading") {
+    document.addEventListener("DOMContentLoaded", addSearch);
+  } else {
+    addSearch();
+  }
+})();
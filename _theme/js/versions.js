@@ -0,0 +1,239 @@
+// Panda api versions
+// Adds a version selector when the docs have versioned apis: the doc requests get a
+// `version` parameter so only the docs and apis of that version are listed. Shows the
+// version, since and deprecation notes of the open api and its deprecated fields.
+(function () {
+  if (window.PANDA_STATIC_DATA) {
+    // the exported static site has no server to filter the docs
+    return;
+  }
+  var STORAGE_KEY = "panda-api-version";
+  var selected = localStorage.getItem(STORAGE_KEY) || "";
+  var versions = [];
+  var banner = null;
+
+  function el(tag, className, text) {
+    var e = document.createElement(tag);
+    if (className) {
+      e.className = className;
+    }
+    if (text) {
+      e.textContent = text;
+    }
+    return e;
+  }
+
+  function isDocRequest(url) {
+    return /\/__api_docs\/(api_data\/)?(\?|$)/.test(url);
+  }
+
+  // filter the doc requests by the selected version, and read the versions from the home data
+  var open = XMLHttpRequest.prototype.open;
+  XMLHttpRequest.prototype.open = function (method, url) {
+    var args = Array.prototype.slice.call(arguments);
+    if (typeof url === "string" && isDocRequest(url)) {
+      if (selected) {
+        args[1] = url + (url.indexOf("?") >= 0 ? "&" : "?") + "version=" + encodeURIComponent(selected);
+      }
+      if (url.indexOf("/api_data/") < 0) {
+        var xhr = this;
+        xhr.addEventListener("load", function () {
+          try {
+            versions = JSON.parse(xhr.responseText).versions || [];
+          } catch (e) {
+            versions = [];
+          }
+          addSelector();
+        });
+      }
+    }
+    return open.apply(this, args);
+  };
+
+  function addStyle() {
+    var style = el("style");
+    style.textContent = ".version-select{position:fixed;top:12px;right:24px;z-index:2000;font-size:13px}" +
+      ".lifecycle{margin:16px 0;padding:10px 14px;border-radius:4px;background:#f4f4f5;color:#606266;font-size:13px;line-height:1.6}" +
+      ".lifecycle.is-deprecated{background:#fdf6ec;color:#e6a23c}" +
+      ".lifecycle ul{margin:4px 0 0;padding-left:18px}";
+    document.head.appendChild(style);
+  }
+
+  function addSelector() {
+    if (!versions.length || document.querySelector(".version-select")) {
+      return;
+    }
+    var select = el("select", "version-select");
+    [""].concat(versions).forEach(function (version) {
+      var option = el("option", "", version || "All versions");
+      option.value = version;
+      option.selected = version === selected;
+      select.appendChild(option);
+    });
+    select.onchange = function () {
+      localStorage.setItem(STORAGE_KEY, select.value);
+      window.location.reload();
+    };
+    document.body.appendChild(select);
+  }
+
+  function deprecationText(deprecated) {
+    var text = [deprecated.desc || "Deprecated"];
+    if (deprecated.replacement) {
+      text.push("Use " + deprecated.replacement + " instead.");
+    }
+    if (deprecated.sunset) {
+      text.push("Removed after " + deprecated.sunset + ".");
+    }
+    return text.join(" ");
+  }
+
+  function parseDeprecated(value) {
+    if (value === true) {
+      return {};
+    }
+    if (typeof value === "string") {
+      return { desc: value };
+    }
+    return value && typeof value === "object" ? value : null;
+  }
+
+  // a deprecated value is a bool, a note or {desc, replacement, date, sunset},
+  // anything else is a field that happens to be named deprecated
+  function isLifecycle(value) {
+    if (typeof value === "boolean" || typeof value === "string") {
+      return true;
+    }
+    if (!value || typeof value !== "object" || Array.isArray(value)) {
+      return false;
+    }
+    var keys = Object.keys(value);
+    return keys.length > 0 && keys.every(function (key) {
+      return ["desc", "replacement", "date", "sunset"].indexOf(key) >= 0;
+    });
+  }
+
+  // deprecated fields of a body, query or response model, as path: note
+  function deprecatedFields(model, path, result) {
+    if (Array.isArray(model)) {
+      model.forEach(function (item) {
+        deprecatedFields(item, path, result);
+      });
+    } else if (model && typeof model === "object") {
+      Object.keys(model).forEach(function (key) {
+        var attr = model[key];
+        if (key.charAt(0) === "$" || (key === "deprecated" && isLifecycle(attr)) || !attr || typeof attr !== "object") {
+          return;
+        }
+        var fieldPath = path ? path + "." + key : key;
+        var deprecated = parseDeprecated(attr.$deprecated !== undefined ? attr.$deprecated
+          : isLifecycle(attr.deprecated) ? attr.deprecated : undefined);
+        if (deprecated) {
+          result.push(fieldPath + ": " + deprecationText(deprecated));
+        }
+        deprecatedFields(attr, fieldPath, result);
+      });
+    }
+    return result;
+  }
+
+  function render(api) {
+    if (banner && banner.parentNode) {
+      banner.parentNode.removeChild(banner);
+    }
+    banner = null;
+    var container = document.querySelector(".api");
+    if (!api || !container) {
+      return;
+    }
+    var lines = [];
+    var versionInfo = [];
+    if (api.version) {
+      versionInfo.push("Version " + api.version);
+    }
+    if (api.since) {
+      versionInfo.push("since " + api.since);
+    }
+    if (versionInfo.length) {
+      lines.push(versionInfo.join(", "));
+    }
+    if (api.deprecated) {
+      lines.push(deprecationText(api.deprecated));
+    }
+    var fields = [];
+    ["url_param", "query", "body", "response"].forEach(function (part) {
+      deprecatedFields(api[part], part, fields);
+    });
+    if (!lines.length && !fields.length) {
+      return;
+    }
+
+    banner = el("div", "lifecycle" + (api.deprecated || fields.length ? " is-deprecated" : ""));
+    lines.forEach(function (line) {
+      banner.appendChild(el("div", "", line));
+    });
+    if (fields.length) {
+      banner.appendChild(el("div", "", "Deprecated fields:"));
+      var list = el("ul");
+      fields.forEach(function (field) {
+        list.appendChild(el("li", "", field));
+      });
+      banner.appendChild(list);
+    }
+    container.insertBefore(banner, container.firstChild);
+  }
+
+  function findStore() {
+    var children = document.body.children;
+    for (var i = 0; i < children.length; i++) {
+      if (children[i].__vue__ && children[i].__vue__.$store) {
+        return children[i].__vue__.$store;
+      }
+    }
+    return null;
+  }
+
+  function watch(store) {
+    var timer = null;
+    var update = function () {
+      // the api page is a lazy loaded chunk, wait until it is rendered
+      var retry = 20;
+      clearTimeout(timer);
+      var tryRender = function () {
+        if (document.querySelector(".api") || retry-- <= 0) {
+          render(store.state.api);
+        } else {
+          timer = setTimeout(tryRender, 100);
+        }
+      };
+      timer = setTimeout(tryRender, 0);
+    };
+    store.watch(function (s) {
+      return s.api;
+    }, update);
+    window.addEventListener("hashchange", update);
+    update();
+  }
+
+  function start(retry) {
+    var store = findStore();
+    if (store) {
+      watch(store);
+    } else if (retry > 0) {
+      setTimeout(function () {
+        start(retry - 1);
+      }, 200);
+    }
+  }
+
+  function init() {
+    addStyle();
+    start(50);
+  }
+
+  if (document.readyState === "loading") {
+    document.addEventListener("DOMContentLoaded", init);
+  } else {
+    init();
+  }
+})();
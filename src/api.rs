@@ -1,15 +1,15 @@
 use actix::Addr;
 use actix_files;
 use actix_multipart::Multipart;
-use actix_web::dev::ResourceDef;
-use actix_web::{http, web, Error, HttpRequest, HttpResponse};
+use actix_rt::time::delay_for;
+use actix_web::dev::{self, RequestHead};
+use actix_web::{http, web, Error, HttpMessage, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::io::prelude::*;
-use std::path::Path;
-use std::sync::Mutex;
-use std::thread;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime};
 
 use futures::StreamExt;
@@ -19,155 +19,296 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
 use crate::db;
+use crate::graphql;
+use crate::jwt;
 use crate::mock;
+use crate::project::Project;
+use crate::router;
+use crate::schema;
 use crate::server;
-use crate::websocket::WsChatSession;
+use crate::snippets;
+use crate::stream;
+use crate::version;
+use crate::websocket::{self, WsMockSession};
 use crate::{float, int, timestamp};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ApiDocDataRequest {
     filename: String,
+    /// 只返回这个版本的接口
+    version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiDocBasicRequest {
+    /// 只返回有这个版本接口的文档菜单
+    version: Option<String>,
 }
 
 /// 根据接口文件路径获取接口文档详情
 pub async fn get_api_doc_data(
+    req: HttpRequest,
     req_get: web::Query<ApiDocDataRequest>,
-    data: web::Data<Mutex<db::Database>>,
+    data: web::Data<db::DataStore>,
+    project: web::Data<Project>,
 ) -> HttpResponse {
-    let data = data.lock().unwrap();
-    let api_docs = &data.api_docs;
+    let data = data.load();
+    // 接口的示例代码默认使用当前访问的服务器地址
+    let conn = req.connection_info();
+    let base_url = format!("{}://{}{}", conn.scheme(), conn.host(), project.prefix);
+    let version = req_get.version.as_deref().filter(|v| !v.is_empty());
+    match get_api_doc_value(&data, &project.root, &req_get.filename, &base_url, version) {
+        Value::Null => HttpResponse::Ok().body(""),
+        value => HttpResponse::Ok().json(value),
+    }
+}
 
-    if req_get.filename == "_auth.json5" {
-        if let Some(auth_doc) = &data.auth_doc {
-            return HttpResponse::Ok().json(auth_doc);
-        } else {
-            return HttpResponse::Ok().body("");
-        }
-    } else if req_get.filename == "_settings.json5" {
-        if let Some(settings) = &data.settings {
-            return HttpResponse::Ok().json(settings);
-        } else {
-            return HttpResponse::Ok().body("");
-        }
+/// 文档文件的详情数据, 没有auth或者settings文件时返回Null
+pub fn get_api_doc_value(
+    data: &db::Database,
+    root: &Path,
+    doc_file: &str,
+    base_url: &str,
+    version: Option<&str>,
+) -> Value {
+    if doc_file == "_auth.json5" {
+        return match &data.auth_doc {
+            Some(auth_doc) => json!(auth_doc),
+            None => Value::Null,
+        };
+    } else if doc_file == jwt::JWT_DOC_FILE {
+        let apis: Vec<Value> = jwt::get_jwt_apis(data)
+            .iter()
+            .map(|api| {
+                let mut api_value = json!(api);
+                api_value["snippets"] = snippets::get_api_snippets(api, base_url, &data.auth_doc);
+                api_value
+            })
+            .collect();
+        if apis.is_empty() {
+            return Value::Null;
+        }
+        return json!({
+            "name": "Login",
+            "desc": "The login and refresh token apis of the jwt auth in _auth.json5",
+            "order": 0,
+            "filename": jwt::JWT_DOC_FILE,
+            "apis": apis});
+    } else if doc_file == "_settings.json5" {
+        return match &data.settings {
+            Some(settings) => settings.clone(),
+            None => Value::Null,
+        };
     }
 
-    if req_get.filename.ends_with(".md") {
-        if Path::new(&req_get.filename).exists() {
-            let (mut order, mut menu_title) =
-                db::get_order_and_title_from_filename(&req_get.filename, "md");
+    if doc_file.ends_with(".md") {
+        if root.join(doc_file).exists() {
+            let (mut order, mut menu_title) = db::get_order_and_title_from_filename(doc_file, "md");
             let mut desc = "".to_string();
             let mut md_content = "".to_string();
             let mut filename = "".to_string();
             db::load_md_doc_config(
-                &req_get.filename,
+                root,
+                doc_file,
                 &mut order,
                 &mut menu_title,
                 &mut desc,
                 &mut md_content,
                 &mut filename,
             );
-            return HttpResponse::Ok().json(json!({
+            return json!({
                     "order": order,
                     "name": menu_title,
-                    "content": md_content}));
+                    "content": md_content});
         }
-    } else if req_get.filename.ends_with(".json5") {
-        for (_, doc) in api_docs {
-            if doc.filename == req_get.filename {
+    } else if doc_file.ends_with(".json5") {
+        for doc in data.api_docs.values() {
+            if doc.filename == doc_file {
+                // 每个接口带上请求的示例代码
                 let mut apis = Vec::new();
                 for api in &doc.apis {
-                    let api = api.lock().unwrap();
-                    apis.push(api.clone());
+                    if let Some(version) = version {
+                        if !version::is_api_in_version(api, version) {
+                            continue;
+                        }
+                    }
+                    let mut api_value = json!(api.as_ref());
+                    api_value["snippets"] =
+                        snippets::get_api_snippets(api, base_url, &data.auth_doc);
+                    apis.push(api_value);
                 }
-                return HttpResponse::Ok().json(json!({
+                return json!({
                     "name": doc.name,
                     "desc": doc.desc,
                     "order": doc.order,
                     "filename": doc.filename,
-                    "apis": apis}));
+                    "version": doc.version,
+                    "since": doc.since,
+                    "deprecated": doc.deprecated,
+                    "apis": apis});
             }
         }
     }
 
-    HttpResponse::Ok().json(json!({
+    json!({
       "code": -1,
       "msg": "没有该接口文档文件"
-    }))
+    })
 }
 
 /// 获取项目接口的基本信息
 /// 返回项目名称，介绍，项目接口简要列表
 /// 前端需要自己根据 api_doc 的order进行排序
-pub async fn get_api_doc_basic(db_data: web::Data<Mutex<db::Database>>) -> HttpResponse {
-    let data = db_data.lock().unwrap();
+pub async fn get_api_doc_basic(
+    req_get: web::Query<ApiDocBasicRequest>,
+    db_data: web::Data<db::DataStore>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let version = req_get.version.as_deref().filter(|v| !v.is_empty());
+    HttpResponse::Ok().json(get_api_doc_basic_value(&data, version))
+}
+
+/// 文档首页的数据, 静态导出时也使用
+pub fn get_api_doc_basic_value(data: &db::Database, version: Option<&str>) -> Value {
     let basic_data = &data.basic_data;
 
     let mut docs = Vec::new();
     if let Some(auth_doc) = &data.auth_doc {
         docs.push(json!({"name":auth_doc.name, "filetype":"auth", "desc":auth_doc.desc, "order":0, "filename":"_auth.json5", "children":{}}));
     }
+    if !jwt::get_jwt_apis(data).is_empty() {
+        docs.push(json!({"name":"Login", "filetype":"json5", "desc":"", "order":0, "filename":jwt::JWT_DOC_FILE, "children":{}}));
+    }
     if let Some(_) = &data.settings {
         docs.push(json!({"name":"Settings", "filetype":"settings", "desc":"", "order":0, "filename":"_settings.json5", "children":{}}));
     }
 
-    for (_, doc) in &data.menus {
+    let menus = match version {
+        Some(version) => version::filter_menus(&data.menus, &data.api_docs, version),
+        None => data.menus.clone(),
+    };
+    for doc in menus.values() {
         docs.push(json!({ "name": doc.name, "filetype":doc.filetype, "desc": doc.desc, "order": doc.order, "filename": doc.filename, "children":doc.children }));
     }
 
-    HttpResponse::Ok().json(json!({
+    json!({
       "project_name": &basic_data.project_name,
       "project_desc": &basic_data.project_desc,
       "read_me": &basic_data.read_me,
-      "api_docs": docs
-    }))
+      "api_docs": docs,
+      "versions": version::get_versions(&data.api_docs),
+      "route_conflicts": &data.router.conflicts,
+      "route_errors": &data.router.errors
+    })
 }
 
 /// api docs 在线浏览文档
 /// 前端相关静态皮肤文件展示服务
-pub async fn theme_view(req: HttpRequest) -> Result<actix_files::NamedFile, Error> {
-    let req_path = req.path();
+pub async fn theme_view(
+    req: HttpRequest,
+    project: web::Data<Project>,
+) -> Result<actix_files::NamedFile, Error> {
+    let req_path = project.request_path(req.path());
 
-    // for api documents homepage
-    let home_dir = dirs::home_dir().unwrap();
-    let theme_home_dir = format!(
-        "{}/.panda_api/theme",
-        home_dir.to_str().unwrap().trim_end_matches("/")
-    );
     let theme_file;
     if req_path == "/" {
         theme_file = "/index.html";
     } else {
         theme_file = req_path.trim_start_matches("/__api_docs/theme");
     }
+    open_theme_file(&project.root, theme_file)
+}
+
+/// 按前缀挂载的项目首页
+/// 文档页面通过server_url参数确定接口数据的地址，没有带上的时候跳转到带参数的地址
+pub async fn project_home_view(
+    req: HttpRequest,
+    project: web::Data<Project>,
+) -> Result<HttpResponse, Error> {
+    if !project.prefix.is_empty() && !req.query_string().contains("server_url=") {
+        let port = req.app_config().local_addr().port();
+        return Ok(HttpResponse::Found()
+            .header(http::header::LOCATION, project.doc_url(port))
+            .finish());
+    }
+    open_theme_file(&project.root, "/index.html")?.into_response(&req)
+}
+
+/// 多项目时的首页，列出所有的项目
+pub async fn projects_view() -> Result<actix_files::NamedFile, Error> {
+    let current_dir = std::env::current_dir()?;
+    open_theme_file(&current_dir, "/projects.html")
+}
+
+/// 获取所有项目的基本信息
+pub async fn get_projects(
+    projects: web::Data<Vec<(Project, web::Data<db::DataStore>)>>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let port = req.app_config().local_addr().port();
+    let mut result = Vec::new();
+    for (project, data) in projects.iter() {
+        let data = data.load();
+        let basic_data = &data.basic_data;
+        result.push(json!({
+            "name": project.name,
+            "project_name": basic_data.project_name,
+            "project_desc": basic_data.project_desc,
+            "url": project.doc_url(port),
+        }));
+    }
+    HttpResponse::Ok().json(result)
+}
 
-    // 优先加载本地目录皮肤，如果本地目录皮肤不存在，加载安装目录皮肤
-    let theme_filepath = format!("_theme{}", theme_file);
-    if Path::new(&theme_filepath).exists() {
-        return Ok(actix_files::NamedFile::open(theme_filepath)?);
+/// 优先加载项目目录皮肤，如果项目目录皮肤不存在，加载安装目录皮肤
+pub fn open_theme_file(root: &Path, theme_file: &str) -> Result<actix_files::NamedFile, Error> {
+    Ok(actix_files::NamedFile::open(get_theme_file_path(
+        root, theme_file,
+    ))?)
+}
+
+/// 皮肤文件的路径, 项目目录没有的时候使用安装目录的皮肤
+pub fn get_theme_file_path(root: &Path, theme_file: &str) -> PathBuf {
+    let theme_filepath = root.join(format!("_theme{}", theme_file));
+    if theme_filepath.exists() {
+        return theme_filepath;
     }
 
     // 加载安装目录的皮肤
-    let theme_filepath = theme_home_dir + theme_file;
-    Ok(actix_files::NamedFile::open(Path::new(&theme_filepath))?)
+    let home_dir = dirs::home_dir().unwrap();
+    let theme_home_dir = format!(
+        "{}/.panda_api/theme",
+        home_dir.to_str().unwrap().trim_end_matches("/")
+    );
+    PathBuf::from(theme_home_dir + theme_file)
 }
 
 /// 获取用户自己存放的静态文件
 /// 多用于写markdown的时候存放的图片
-pub async fn static_file_view(req: HttpRequest) -> Result<actix_files::NamedFile, Error> {
-    let req_path = req.path().trim_start_matches("/");
-    return Ok(actix_files::NamedFile::open(Path::new(req_path))?);
+pub async fn static_file_view(
+    req: HttpRequest,
+    project: web::Data<Project>,
+) -> Result<actix_files::NamedFile, Error> {
+    let req_path = project.request_path(req.path()).trim_start_matches("/");
+    Ok(actix_files::NamedFile::open(project.root.join(req_path))?)
 }
 
 /// 查看上传的 图片或文件
-pub async fn upload_file_view(req: HttpRequest) -> Result<actix_files::NamedFile, Error> {
-    let req_path = req.path();
+pub async fn upload_file_view(
+    req: HttpRequest,
+    project: web::Data<Project>,
+) -> Result<actix_files::NamedFile, Error> {
+    let req_path = project.request_path(req.path());
     let file_path = "_data".to_string() + req_path;
-    return Ok(actix_files::NamedFile::open(Path::new(&file_path))?);
+    Ok(actix_files::NamedFile::open(project.root.join(&file_path))?)
 }
 
 /// 获取_data目录中的数据, models数据 或者其它加载数据
-pub async fn get_api_doc_schema_data(req_get: web::Query<ApiDocDataRequest>) -> HttpResponse {
-    let path = Path::new(&req_get.filename);
+pub async fn get_api_doc_schema_data(
+    req_get: web::Query<ApiDocDataRequest>,
+    project: web::Data<Project>,
+) -> HttpResponse {
+    let path = project.root.join(&req_get.filename);
     let read_me = match fs::read_to_string(path) {
         Ok(x) => x,
         Err(_) => "no data file".to_string(),
@@ -183,17 +324,48 @@ pub struct FormData {
     username: String,
 }
 
+/// 处理websocket连接
+/// 请求地址需要对应一个method为WEBSOCKET的接口，连接后按接口文档回复客户端的消息
 pub async fn websocket_handle(
     req: HttpRequest,
     stream: web::Payload,
+    db_data: web::Data<db::DataStore>,
     srv: web::Data<Addr<server::ChatServer>>,
+    project: web::Data<Project>,
 ) -> Result<HttpResponse, Error> {
+    let req_path = project.request_path(req.path());
+    let data = db_data.load();
+    let api = match find_websocket_api(&data, req_path) {
+        Some(api) => api,
+        None => {
+            return Ok(HttpResponse::Ok().json(json!({
+                "code": - 1,
+                "msg": format ! ("this api address {} no websocket api match", req_path)
+            })));
+        }
+    };
+    if api.auth {
+        let request_query = web::Query::<Value>::from_query(req.query_string())
+            .map(|q| q.into_inner())
+            .unwrap_or(Value::Null);
+        if let Some(auth_error) = auth_validator(
+            &req,
+            &api.url,
+            req_path,
+            &Value::Null,
+            &request_query,
+            &data.auth_doc,
+        ) {
+            return Ok(auth_error);
+        }
+    }
+
     ws::start(
-        WsChatSession {
+        WsMockSession {
             id: 0,
             hb: Instant::now(),
-            room: "Main".to_owned(),
-            name: None,
+            path: req_path.to_string(),
+            data: db_data.clone(),
             addr: srv.get_ref().clone(),
         },
         &req,
@@ -201,6 +373,74 @@ pub async fn websocket_handle(
     )
 }
 
+/// 找到请求地址对应的websocket接口
+pub fn find_websocket_api(data: &db::Database, req_path: &str) -> Option<Arc<db::ApiData>> {
+    let api_data_list = data.find_api_data(req_path)?;
+    for api in api_data_list {
+        if api.method.contains(&"WEBSOCKET".to_string()) {
+            return Some(api.clone());
+        }
+    }
+    None
+}
+
+/// 根据websocket接口文档生成对客户端消息的回复
+/// 和http接口一样，先用消息匹配test_data中的body，都不匹配的时候按response生成mock数据
+/// 返回回复内容和延迟回复的毫秒数
+pub fn get_websocket_response(api: &db::ApiData, message: &Value) -> (Value, u64) {
+    if let Some(test_data) = api.test_data.as_array() {
+        for test_case_data in test_data {
+            let v = match test_case_data.get("body") {
+                Some(v) => v,
+                None => &Value::Null,
+            };
+            let is_match = match message {
+                Value::Object(_) | Value::Array(_) | Value::Null => is_value_equal(message, v),
+                // 文本消息直接比较
+                _ => message == v,
+            };
+            if !is_match {
+                continue;
+            }
+
+            let case_response = match test_case_data.get("response") {
+                Some(v) => v,
+                None => &Value::Null,
+            };
+            let response =
+                parse_test_case_response(case_response, "", &api.response, message, &Value::Null);
+            let delay = match test_case_data.get("delay") {
+                Some(v) => v.as_u64().unwrap_or(0),
+                None => 0,
+            };
+            return (response, delay);
+        }
+    }
+
+    // 没有匹配到测试数据的消息按接口的body检查
+    if let Err(e) = schema::validate_model(&api.body, message) {
+        return (
+            json!({"code": -1, "msg": format!("message error: {}", e)}),
+            0,
+        );
+    }
+
+    if is_empty_response(&api.response) {
+        return (
+            json!({
+                "code": - 1,
+                "msg": format ! ("this websocket api {} have no response or test_data defined", api.url)
+            }),
+            0,
+        );
+    }
+
+    match create_mock_value(&api.response, "", &api.response, message, &Value::Null) {
+        Some(v) => (v, 0),
+        None => (Value::Null, 0),
+    }
+}
+
 /// 处理post、put、delete 请求
 ///
 pub async fn action_handle(
@@ -208,7 +448,8 @@ pub async fn action_handle(
     request_body: Option<web::Json<Value>>,
     request_query: Option<web::Query<Value>>,
     request_form_data: Option<Multipart>,
-    db_data: web::Data<Mutex<db::Database>>,
+    db_data: web::Data<db::DataStore>,
+    project: web::Data<Project>,
 ) -> HttpResponse {
     let body_mode = get_request_body_mode(&req);
     let req_method = req.method().as_str();
@@ -218,7 +459,7 @@ pub async fn action_handle(
     }
 
     let request_body = if &body_mode == "form-data" {
-        get_request_form_data(request_form_data).await
+        get_request_form_data(request_form_data, &project).await
     } else {
         match request_body {
             Some(x) => x.into_inner(),
@@ -231,53 +472,82 @@ pub async fn action_handle(
         None => Value::Null,
     };
 
-    find_response_data(&req, body_mode, request_body, request_query, db_data)
+    let req_path = project.request_path(req.path());
+    find_response_data(
+        &req,
+        req_path,
+        body_mode,
+        request_body,
+        request_query,
+        db_data,
+    )
+    .await
 }
 
 /// 找到对应url 对应请求的数据
-///
-fn find_response_data(
+/// 使用当前数据快照，在请求处理过程中数据重新加载也不会影响到这个请求
+async fn find_response_data(
     req: &HttpRequest,
+    req_path: &str,
     body_mode: String,
     request_body: Value,
     request_query: Value,
-    db_data: web::Data<Mutex<db::Database>>,
+    db_data: web::Data<db::DataStore>,
 ) -> HttpResponse {
-    let db_data = db_data.lock().unwrap();
-    let db_api_data = &db_data.api_data;
-    let req_path = req.path();
+    let db_data = db_data.load_full();
     let req_method = req.method().as_str();
     let req_headers = req.headers();
 
-    let api_data_list = match db_api_data.get(req_path) {
-        Some(v) => Some(v),
-        None => {
-            let mut r = None;
-            for (api_url, api_data_list) in db_api_data {
-                let res = ResourceDef::new(api_url);
-                if res.is_match(req_path) {
-                    r = Some(api_data_list);
-                    break;
-                }
-            }
-            r
-        }
-    };
+    if let Some(response) = jwt_action_handle(req_method, req_path, &request_body, &db_data) {
+        return response;
+    }
+
+    // 同一个url有多个版本的接口时, 按请求头或者url前面的版本号选择
+    let (req_version, versioned_path) = version::get_request_version(
+        &db_data,
+        req_headers
+            .get(version::VERSION_HEADER)
+            .and_then(|v| v.to_str().ok()),
+        req_path,
+    );
+    let req_path = versioned_path.as_str();
+    let api_data_list = db_data
+        .find_api_data(req_path)
+        .map(|list| version::select_api_versions(list, req_version.as_deref()));
 
     if let Some(api_data_list) = api_data_list {
-        'a: for a_api_data in api_data_list {
-            let a_api_data = a_api_data.lock().unwrap();
+        if api_data_list.is_empty() {
+            return HttpResponse::Ok().json(json!({
+                "code": -1,
+                "msg": format!("this api address {} has no version {}", req_path, req_version.unwrap_or_default())
+            }));
+        }
+        'a: for a_api_data in &api_data_list {
+            let is_graphql = a_api_data.method.contains(&"GRAPHQL".to_string());
             if a_api_data.method.contains(&req_method.to_string())
                 || a_api_data.method.contains(&"*".to_string())
+                || (is_graphql && (req_method == "POST" || req_method == "GET"))
             {
                 if a_api_data.auth {
                     // 权限检查
-                    if let Some(auth_valid_errors) =
-                        auth_validator(&req, &a_api_data.url, &db_data.auth_doc)
-                    {
-                        return HttpResponse::Ok().json(auth_valid_errors);
+                    if let Some(auth_error) = auth_validator(
+                        req,
+                        &a_api_data.url,
+                        req_path,
+                        &request_body,
+                        &request_query,
+                        &db_data.auth_doc,
+                    ) {
+                        return version::add_deprecation_headers(auth_error, a_api_data);
                     }
                 }
+                // response中可以使用jwt token的claims
+                let claims = if a_api_data.auth {
+                    get_request_claims(req, &db_data.auth_doc)
+                } else {
+                    Value::Null
+                };
+                let template_body = add_claims_to_body(&request_body, &claims);
 
                 // 首先判断在api接口中是否对request_headers有要求，如果有要求，那么就按照api接口定义匹配
                 // 像url, 级别的接口匹配
@@ -306,6 +576,18 @@ fn find_response_data(
                     }
                 }
 
+                if is_graphql {
+                    let (response, delay) =
+                        graphql::get_graphql_response(a_api_data, &request_body, &request_query);
+                    if delay > 0 {
+                        delay_for(Duration::from_millis(delay)).await;
+                    }
+                    return version::add_deprecation_headers(
+                        HttpResponse::Ok().json(response),
+                        a_api_data,
+                    );
+                }
+
                 let mut status_code = 200;
                 let mut content_type = "application/json";
                 if !a_api_data.response_headers.is_null() {
@@ -388,32 +670,64 @@ fn find_response_data(
                             case_response,
                             "",
                             &a_api_data.response,
-                            &request_body,
+                            &template_body,
                             &request_query,
                         );
                         if let Some(v) = test_case_data.get("delay") {
                             if let Some(t) = v.as_u64() {
-                                thread::sleep(Duration::from_millis(t));
+                                // 异步等待，不阻塞worker处理其它请求
+                                delay_for(Duration::from_millis(t)).await;
                             }
                         }
 
+                        // test_data中的stream设置优先于接口的stream设置
+                        let stream_value = match test_case_data.get("stream") {
+                            Some(v) => v,
+                            None => &a_api_data.stream,
+                        };
+                        if let Some(config) = stream::StreamConfig::from_value(stream_value) {
+                            // 测试数据中的response作为每条事件默认的数据
+                            let response = stream::stream_response(
+                                config,
+                                status_code,
+                                get_last_event_id(req),
+                                case_response,
+                                &a_api_data.response,
+                                &template_body,
+                                &request_query,
+                            );
+                            return version::add_deprecation_headers(response, a_api_data);
+                        }
+
                         let serialized = serde_json::to_string(&response).unwrap();
-                        return HttpResponse::build(status_code)
-                            .content_type(content_type)
-                            .body(serialized);
+                        return version::add_deprecation_headers(
+                            HttpResponse::build(status_code)
+                                .content_type(content_type)
+                                .body(serialized),
+                            a_api_data,
+                        );
                     }
                 }
 
-                if a_api_data.response.is_null()
-                    || (a_api_data.response.is_object()
-                        && a_api_data.response.as_object().unwrap().is_empty())
-                    || (a_api_data.response.is_array()
-                        && a_api_data.response.as_array().unwrap().is_empty())
-                {
-                    return HttpResponse::Ok().json(json!({
+                if let Some(config) = stream::StreamConfig::from_value(&a_api_data.stream) {
+                    let response = stream::stream_response(
+                        config,
+                        status_code,
+                        get_last_event_id(req),
+                        &Value::Null,
+                        &a_api_data.response,
+                        &template_body,
+                        &request_query,
+                    );
+                    return version::add_deprecation_headers(response, a_api_data);
+                }
+
+                if is_empty_response(&a_api_data.response) {
+                    let response = HttpResponse::Ok().json(json!({
                         "code": - 1,
                         "msg": format ! ("this api address {} with method {} have no response or test_data defined", req_path, req_method)
                     }));
+                    return version::add_deprecation_headers(response, a_api_data);
                 }
 
                 let mut serialized = "".to_string();
@@ -421,14 +735,17 @@ fn find_response_data(
                     &a_api_data.response,
                     "",
                     &a_api_data.response,
-                    &request_body,
+                    &template_body,
                     &request_query,
                 ) {
                     serialized = serde_json::to_string(&response).unwrap();
                 }
-                return HttpResponse::build(status_code)
-                    .content_type(content_type)
-                    .body(serialized);
+                return version::add_deprecation_headers(
+                    HttpResponse::build(status_code)
+                        .content_type(content_type)
+                        .body(serialized),
+                    a_api_data,
+                );
             }
         }
         return HttpResponse::Ok().json(json!({
@@ -443,8 +760,116 @@ fn find_response_data(
     }))
 }
 
+/// 根据websocket接口push中定义的response生成推送的消息
+pub fn get_websocket_push_message(push: &Value) -> Value {
+    let response = match push.get("response") {
+        Some(v) => v,
+        None => return Value::Null,
+    };
+    match create_mock_value(response, "", response, &Value::Null, &Value::Null) {
+        Some(v) => v,
+        None => Value::Null,
+    }
+}
+
+/// 找到websocket接口中指定名称的push
+fn find_websocket_push<'a>(api: &'a db::ApiData, name: &str) -> Option<&'a Value> {
+    api.push
+        .as_array()?
+        .iter()
+        .find(|push| push.get("name").and_then(|v| v.as_str()) == Some(name))
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WebsocketPushRequest {
+    /// websocket连接的地址或者接口的url, 不填就是所有连接
+    url: Option<String>,
+    /// 接口push中定义的消息名称, 按它的response生成mock消息
+    name: Option<String>,
+    /// 直接推送的消息内容
+    message: Option<Value>,
+}
+
+/// 一个连接地址要推送的消息, 不需要推送时返回None
+fn get_room_push_message(
+    push_request: &WebsocketPushRequest,
+    room: &str,
+    api: Option<&db::ApiData>,
+) -> Option<Value> {
+    if let Some(url) = &push_request.url {
+        // 可以是连接的地址，也可以是接口文档中带参数的url
+        let is_api_url = api.map(|api| &api.url == url).unwrap_or(false);
+        if room != url && !is_api_url {
+            return None;
+        }
+    }
+    match (&push_request.message, &push_request.name, api) {
+        (Some(message), _, _) => Some(message.clone()),
+        (None, Some(name), Some(api)) => {
+            find_websocket_push(api, name).map(get_websocket_push_message)
+        }
+        _ => None,
+    }
+}
+
+/// 给websocket连接推送消息，方便测试的时候手动触发服务器事件
+/// 可以直接推送message，或者按接口push中name对应的定义生成mock消息
+pub async fn websocket_push_handle(
+    push_request: web::Json<WebsocketPushRequest>,
+    db_data: web::Data<db::DataStore>,
+    srv: web::Data<Addr<server::ChatServer>>,
+) -> HttpResponse {
+    if push_request.message.is_none() && push_request.name.is_none() {
+        return HttpResponse::Ok().json(json!({
+            "code": -1,
+            "msg": "message or name is required"
+        }));
+    }
+
+    let rooms = match srv.send(server::ListRooms).await {
+        Ok(rooms) => rooms,
+        Err(e) => {
+            log::error!("websocket push error {:?}", e);
+            Vec::new()
+        }
+    };
+
+    let data = db_data.load();
+    let mut sessions = 0;
+    for room in rooms {
+        let api = find_websocket_api(&data, &room);
+        let message = match get_room_push_message(&push_request, &room, api.as_deref()) {
+            Some(message) => message,
+            None => continue,
+        };
+        let msg = websocket::message_text(message);
+        if let Ok(n) = srv.send(server::Broadcast { room, msg }).await {
+            sessions += n;
+        }
+    }
+
+    HttpResponse::Ok().json(json!({
+        "code": 1,
+        "sessions": sessions
+    }))
+}
+
+/// sse客户端断线重连时带上的最后一条事件id
+fn get_last_event_id(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("Last-Event-ID")
+        .and_then(|v| v.to_str().ok())
+}
+
+/// 接口没有定义response
+fn is_empty_response(response: &Value) -> bool {
+    response.is_null()
+        || (response.is_object() && response.as_object().unwrap().is_empty())
+        || (response.is_array() && response.as_array().unwrap().is_empty())
+}
+
 /// 处理test_case response中的部分$mock字段
-fn parse_test_case_response(
+pub fn parse_test_case_response(
     test_case_response: &Value,
     field_path: &str,
     response_model: &Value,
@@ -596,7 +1021,7 @@ fn is_request_method_match_test_case(request_method: &str, test_case_method: &Va
 /// 判断两个serde value的值是否相等
 /// 只要value2中要求的每个字段，value1中都有，就表示相等, 也就是说value1的字段可能会比value2多
 /// 改为两个value1，value2中的字段必须完全相等
-fn is_value_equal(value1: &Value, value2: &Value) -> bool {
+pub fn is_value_equal(value1: &Value, value2: &Value) -> bool {
     if value1.is_null() && value2.is_null() {
         return true;
     }
@@ -668,7 +1093,7 @@ fn is_value_equal(value1: &Value, value2: &Value) -> bool {
 }
 
 /// 从请求中获取form_data里面的数据以及文件上传
-async fn get_request_form_data(request_form_data: Option<Multipart>) -> Value {
+async fn get_request_form_data(request_form_data: Option<Multipart>, project: &Project) -> Value {
     let mut form_data: Map<String, Value> = Map::new();
     if let Some(mut payload) = request_form_data {
         // 如果是文件上传
@@ -694,15 +1119,16 @@ async fn get_request_form_data(request_form_data: Option<Multipart>) -> Value {
                     filename = f;
                 }
 
-                match std::fs::create_dir_all("./_data/_upload") {
+                let upload_dir = project.root.join("_data/_upload");
+                match std::fs::create_dir_all(&upload_dir) {
                     Ok(_) => (),
                     Err(e) => {
                         println!("create folder failed _data/_upload {:?}", e);
                     }
                 }
 
-                let filepath = format!("./_data/_upload/{}", filename);
-                let filepath2 = &format!("./_data/_upload/{}", filename);
+                let filepath = upload_dir.join(filename);
+                let filepath2 = &format!("_data/_upload/{}", filename);
 
                 if let Ok(mut f) = web::block(|| std::fs::File::create(filepath)).await {
                     while let Some(chunk) = field.next().await {
@@ -716,7 +1142,7 @@ async fn get_request_form_data(request_form_data: Option<Multipart>) -> Value {
                             // 保存上传文件对应的访问url地址，好让response中调取
                             form_data.insert(
                                 format!("$___{}:url", field_name),
-                                Value::String(format!("/_upload/{}", filename)),
+                                Value::String(format!("{}/_upload/{}", project.prefix, filename)),
                             );
                         } else {
                             println!("create file error {}", filepath2);
@@ -767,7 +1193,8 @@ fn get_request_body_mode(req: &HttpRequest) -> String {
 }
 
 /// 判断是否是websocket连接请求
-fn is_websocket_connect(req: &HttpRequest) -> bool {
+/// 是否是websocket连接请求, 用于路由的guard
+pub fn is_websocket_connect(req: &RequestHead) -> bool {
     let mut has_version = false;
     let mut has_key = false;
     if let Some(_) = req.headers().get("sec-websocket-version") {
@@ -782,79 +1209,343 @@ fn is_websocket_connect(req: &HttpRequest) -> bool {
     false
 }
 
-fn get_token_from_request(req: &HttpRequest) -> String {
-    let headers = req.headers();
-    let mut token = "";
-    if let Some(x) = headers.get("authorization") {
-        if let Ok(x) = x.to_str() {
-            token = x.trim_start_matches("Bearer ").trim();
+/// 按auth_place的设置依次从header、query或者cookie中获取token
+pub fn get_token_from_request(req: &HttpRequest, token_places: &[db::TokenPlace]) -> String {
+    for token_place in token_places {
+        let value = match token_place.place.as_str() {
+            "query" => web::Query::<HashMap<String, String>>::from_query(req.query_string())
+                .ok()
+                .and_then(|q| q.get(&token_place.name).cloned()),
+            "cookie" => req.cookie(&token_place.name).map(|c| c.value().to_string()),
+            _ => req
+                .headers()
+                .get(token_place.name.as_str())
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string()),
+        };
+        if let Some(value) = value {
+            let token = value.trim_start_matches(&token_place.prefix).trim();
+            if !token.is_empty() {
+                return token.to_string();
+            }
         }
     }
-    token.to_string()
+    "".to_string()
 }
 
 /// 判断是否有某个url的权限
 fn is_has_perm(url: &str, method: &str, perms: &HashMap<String, HashSet<String>>) -> bool {
-    if let Some(methods) = perms.get("*") {
-        // 如果有所有网址权限，再判断方法上的权限是否满足
-        if methods.contains(method) || methods.contains("*") || methods.len() == 0 {
-            return true;
-        }
-    }
+    find_perm_rule(url, method, perms).is_some()
+}
 
-    match perms.get(url) {
-        Some(methods) => {
-            // 如果有所有网址权限，再判断方法上的权限是否满足
-            if methods.contains(method) || methods.contains("*") || methods.len() == 0 {
-                return true;
+/// 找到匹配这个url和请求方法的权限规则, 返回规则的url, "*" 表示所有网址
+pub fn find_perm_rule<'a>(
+    url: &str,
+    method: &str,
+    perms: &'a HashMap<String, HashSet<String>>,
+) -> Option<&'a str> {
+    // 先判断所有网址的权限，再判断这个url的权限
+    for key in &["*", url] {
+        if let Some((rule_url, methods)) = perms.get_key_value(*key) {
+            if methods.contains(method) || methods.contains("*") || methods.is_empty() {
+                return Some(rule_url);
             }
         }
-        None => return false,
     }
-
-    false
+    None
 }
 
 /// 判断用户是否有当前接口访问权限，如果有权限返回None，如果没有权限 返回报错信息
-fn auth_validator<'a>(
+/// 没有token或者token无效返回401, 没有权限返回403, 状态码和返回内容可以在_auth.json5中设置
+fn auth_validator(
     req: &HttpRequest,
     api_url: &str,
-    auth_doc: &'a Option<db::AuthDoc>,
-) -> Option<&'a Value> {
-    let token = get_token_from_request(req);
-
+    req_path: &str,
+    request_body: &Value,
+    request_query: &Value,
+    auth_doc: &Option<db::AuthDoc>,
+) -> Option<HttpResponse> {
     if let Some(auth_data) = auth_doc {
-        let no_perm_response = &auth_data.no_perm_response;
+        let token = get_token_from_request(req, &auth_data.token_places);
+        let invalid_token = || {
+            auth_error_response(
+                auth_data,
+                auth_data.invalid_token_status_code,
+                &auth_data.invalid_token_response,
+                Some(("invalid_token", "the token is invalid")),
+            )
+        };
 
         // 判断token是否符合生成规则
         if &token == "" {
-            return Some(no_perm_response);
+            return Some(auth_error_response(
+                auth_data,
+                auth_data.no_auth_status_code,
+                &auth_data.no_auth_response,
+                None,
+            ));
         }
 
         let req_method = req.method().as_str();
+        let url_param = get_url_params(api_url, req_path);
+        let context = |claims, user| PermContext {
+            url_param: &url_param,
+            body: request_body,
+            query: request_query,
+            claims,
+            user,
+        };
 
-        for group in &auth_data.groups {
-            let mut group_no_perm_response = &group.no_perm_response;
-            if group_no_perm_response.is_null() {
-                group_no_perm_response = no_perm_response;
+        if let Some(jwt_config) = &auth_data.jwt {
+            // jwt按claim中的group名称找到对应的权限组
+            let claims = match jwt::decode(jwt_config, &token) {
+                Ok(claims) => claims,
+                Err(jwt::JwtError::Expired) => {
+                    return Some(auth_error_response(
+                        auth_data,
+                        auth_data.invalid_token_status_code,
+                        &jwt_config.expired_response,
+                        Some(("invalid_token", "the token has expired")),
+                    ))
+                }
+                Err(jwt::JwtError::Invalid(_)) => return Some(invalid_token()),
+            };
+            if claims.get("token_use").is_some() {
+                // refresh_token不能用来访问接口
+                return Some(invalid_token());
+            }
+            let group_name = claims.get(&jwt_config.group_claim).and_then(|v| v.as_str());
+            for group in &auth_data.groups {
+                if Some(group.name.as_str()) == group_name {
+                    let username = claims.get("sub").and_then(|v| v.as_str()).unwrap_or("");
+                    let user = group.users.get(username).unwrap_or(&Value::Null);
+                    let context = context(&claims, user);
+                    return group_perm_validator(auth_data, group, api_url, req_method, &context);
+                }
             }
-            for (t, _) in &group.users {
+            return Some(invalid_token());
+        }
+
+        for group in &auth_data.groups {
+            for (t, user) in &group.users {
                 if t == &token {
-                    // 判断请求是否在权限范围内
-                    if is_has_perm(api_url, req_method, &group.no_perms) {
-                        return Some(group_no_perm_response);
-                    } else if is_has_perm(api_url, req_method, &group.has_perms) {
-                        return None;
-                    }
-                    return Some(group_no_perm_response);
+                    let claims = user.get("claims").unwrap_or(&Value::Null);
+                    let context = context(claims, user);
+                    return group_perm_validator(auth_data, group, api_url, req_method, &context);
                 }
             }
         }
-        return Some(no_perm_response);
+        return Some(invalid_token());
     }
     None
 }
 
+/// 判断请求是否在权限组的权限范围内
+fn group_perm_validator(
+    auth_doc: &db::AuthDoc,
+    group: &db::AuthData,
+    api_url: &str,
+    req_method: &str,
+    context: &PermContext,
+) -> Option<HttpResponse> {
+    // no_perms优先, 带条件的规则在条件满足时生效
+    let is_match_rules = |rules: &Vec<db::ConditionalPerm>| {
+        rules.iter().any(|rule| {
+            is_has_perm(api_url, req_method, &rule.perms) && context.is_match(&rule.when)
+        })
+    };
+    if !is_has_perm(api_url, req_method, &group.no_perms)
+        && !is_match_rules(&group.no_perm_rules)
+        && (is_has_perm(api_url, req_method, &group.has_perms)
+            || is_match_rules(&group.has_perm_rules))
+    {
+        return None;
+    }
+    let mut no_perm_response = &group.no_perm_response;
+    if no_perm_response.is_null() {
+        no_perm_response = &auth_doc.no_perm_response;
+    }
+    Some(auth_error_response(
+        auth_doc,
+        group.no_perm_status_code,
+        no_perm_response,
+        Some(("insufficient_scope", "no perm to visit")),
+    ))
+}
+
+/// 权限规则when条件中可以使用的请求数据
+struct PermContext<'a> {
+    url_param: &'a Value,
+    body: &'a Value,
+    query: &'a Value,
+    claims: &'a Value,
+    user: &'a Value,
+}
+
+impl<'a> PermContext<'a> {
+    /// 取出条件中的值, $url_param, $body, $query, $claims, $user 开头的从请求数据中取值, 其它的就是值本身
+    fn get_value(&self, value: &'a Value) -> Option<&'a Value> {
+        let s = match value.as_str() {
+            Some(s) if s.starts_with('$') => s,
+            _ => return Some(value),
+        };
+        let sources = [
+            ("$url_param", self.url_param),
+            ("$body", self.body),
+            ("$query", self.query),
+            ("$claims", self.claims),
+            ("$user", self.user),
+        ];
+        for (prefix, data) in sources.iter() {
+            if let Some(pointer) = s.strip_prefix(prefix) {
+                if pointer.is_empty() || pointer.starts_with('/') {
+                    return data.pointer(pointer);
+                }
+            }
+        }
+        Some(value)
+    }
+
+    /// when中的条件是否都满足, 取不到值的条件不满足
+    fn is_match(&self, when: &Map<String, Value>) -> bool {
+        when.iter().all(|(k, v)| {
+            let k = Value::String(k.to_string());
+            match (self.get_value(&k), self.get_value(v)) {
+                (Some(a), Some(b)) if !a.is_null() && !b.is_null() => {
+                    // url参数和query都是字符串, 按字符串比较
+                    let to_string = |v: &Value| match v {
+                        Value::String(s) => s.to_string(),
+                        v => v.to_string(),
+                    };
+                    to_string(a) == to_string(b)
+                }
+                _ => false,
+            }
+        })
+    }
+}
+
+/// 按接口url取出请求地址中的参数, 如 /users/{id}/ 和 /users/3/ 得到 {"id":"3"}
+fn get_url_params(api_url: &str, req_path: &str) -> Value {
+    let mut result = Map::new();
+    if router::is_dynamic_url(api_url) {
+        let mut path = dev::Path::new(req_path);
+        if dev::ResourceDef::new(api_url).match_path(&mut path) {
+            for (name, value) in path.iter() {
+                result.insert(name.to_string(), json!(value));
+            }
+        }
+    }
+    Value::Object(result)
+}
+
+/// 权限检查失败的返回, 401和403带上WWW-Authenticate
+fn auth_error_response(
+    auth_doc: &db::AuthDoc,
+    status_code: u16,
+    body: &Value,
+    error: Option<(&str, &str)>,
+) -> HttpResponse {
+    let status_code = http::StatusCode::from_u16(status_code).unwrap_or(http::StatusCode::OK);
+    let mut response = HttpResponse::build(status_code);
+    if status_code == http::StatusCode::UNAUTHORIZED || status_code == http::StatusCode::FORBIDDEN {
+        response.header(
+            http::header::WWW_AUTHENTICATE,
+            www_authenticate(auth_doc, error),
+        );
+    }
+    response.json(body)
+}
+
+/// 生成WWW-Authenticate, scheme按token在header中的前缀, 默认Bearer
+fn www_authenticate(auth_doc: &db::AuthDoc, error: Option<(&str, &str)>) -> String {
+    let scheme = auth_doc
+        .token_places
+        .iter()
+        .find(|p| p.place == "header" && !p.prefix.trim().is_empty())
+        .map(|p| p.prefix.trim())
+        .unwrap_or("Bearer");
+    let mut challenge = format!("{} realm=\"{}\"", scheme, auth_doc.name.replace('"', "'"));
+    if let Some((error, description)) = error {
+        challenge.push_str(&format!(
+            ", error=\"{}\", error_description=\"{}\"",
+            error, description
+        ));
+    }
+    challenge
+}
+
+/// jwt的登录接口和刷新token接口
+fn jwt_action_handle(
+    req_method: &str,
+    req_path: &str,
+    request_body: &Value,
+    db_data: &db::Database,
+) -> Option<HttpResponse> {
+    if req_method != "POST" {
+        return None;
+    }
+    // 只处理jwt模式下没有在接口文档中定义的登录和刷新token接口
+    if !jwt::get_jwt_apis(db_data)
+        .iter()
+        .any(|api| api.url == req_path)
+    {
+        return None;
+    }
+    let auth_doc = db_data.auth_doc.as_ref()?;
+    let jwt_config = auth_doc.jwt.as_ref()?;
+    let result = if req_path == jwt_config.login_url {
+        jwt::login(auth_doc, jwt_config, request_body)
+    } else {
+        jwt::refresh(jwt_config, request_body)
+    };
+    // 登录失败和refresh_token无效时返回invalid_token的状态码, 前端据此判断需要重新登录
+    match result {
+        Ok(body) => Some(HttpResponse::Ok().json(body)),
+        Err(jwt::TokenError::Invalid(body)) => Some(auth_error_response(
+            auth_doc,
+            auth_doc.invalid_token_status_code,
+            &body,
+            None,
+        )),
+        Err(jwt::TokenError::Sign(e)) => {
+            log::error!("jwt sign error: {}", e);
+            Some(HttpResponse::InternalServerError().json(json!({
+                "code": -1,
+                "msg": format!("jwt sign error: {}", e)
+            })))
+        }
+    }
+}
+
+/// 请求中jwt token的claims, 用于response中的 $value:"$claims/sub"
+fn get_request_claims(req: &HttpRequest, auth_doc: &Option<db::AuthDoc>) -> Value {
+    let auth_doc = match auth_doc {
+        Some(a) => a,
+        None => return Value::Null,
+    };
+    let jwt_config = match &auth_doc.jwt {
+        Some(c) => c,
+        None => return Value::Null,
+    };
+    let token = get_token_from_request(req, &auth_doc.token_places);
+    jwt::decode(jwt_config, &token).unwrap_or(Value::Null)
+}
+
+/// 把claims放到请求数据中, 生成response数据的时候可以取值
+fn add_claims_to_body(request_body: &Value, claims: &Value) -> Value {
+    if claims.is_null() {
+        return request_body.clone();
+    }
+    let mut body = match request_body {
+        Value::Object(v) => v.clone(),
+        Value::Null => Map::new(),
+        _ => return request_body.clone(),
+    };
+    body.insert("$___claims".to_string(), claims.clone());
+    Value::Object(body)
+}
+
 /// 获取string类型的mock value
 fn get_string_mock_value(field_type: &str, field_attr: &Value) -> Value {
     let mut min_length = 0;
@@ -1248,6 +1939,11 @@ fn create_mock_value_by_field(
             let pointer = value2.trim_start_matches("$query");
             let v = request_query.pointer(pointer)?;
             return Some(v.clone());
+        } else if value2.starts_with("$claims") {
+            // jwt token中的claims
+            let pointer = format!("/$___claims{}", value2.trim_start_matches("$claims"));
+            let v = request_body.pointer(&pointer)?;
+            return Some(v.clone());
         }
 
         return None;
@@ -1639,3 +2335,214 @@ fn is_marked_delete_field(field_value: &Value) -> bool {
     }
     return false;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    /// 在临时目录中写一个_auth.json5并加载
+    fn load_test_auth(name: &str, auth: &str) -> Option<db::AuthDoc> {
+        let root = std::env::temp_dir().join(format!("panda-auth-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("_auth.json5"), auth).unwrap();
+        let auth_doc = db::load_auth_data(&root, &HashMap::new());
+        let _ = fs::remove_dir_all(&root);
+        assert!(auth_doc.is_some());
+        auth_doc
+    }
+
+    /// 带着token请求api_url, 返回权限检查的状态码, WWW-Authenticate和返回内容
+    fn check_auth(
+        auth_doc: &Option<db::AuthDoc>,
+        api_url: &str,
+        token: Option<&str>,
+    ) -> Option<(u16, String, Value)> {
+        let mut req = TestRequest::get().uri(api_url);
+        if let Some(token) = token {
+            req = req.header("Authorization", format!("Bearer {}", token));
+        }
+        let req = req.to_http_request();
+        let resp = auth_validator(&req, api_url, api_url, &Value::Null, &Value::Null, auth_doc)?;
+        let www_authenticate = resp
+            .headers()
+            .get(http::header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let body = match resp.body().as_ref() {
+            Some(dev::Body::Bytes(b)) => serde_json::from_slice(b).unwrap(),
+            _ => Value::Null,
+        };
+        Some((resp.status().as_u16(), www_authenticate, body))
+    }
+
+    #[test]
+    fn auth_validator_test() {
+        let auth_doc = load_test_auth(
+            "token",
+            r#"{
+                name: "Test auth",
+                no_perm_response: {code: -1, msg: "no perm"},
+                groups: [
+                    {name: "user", users: [{token: "u1"}], has_perms: [["/orders/", "GET"]]},
+                    {name: "guest", users: [{token: "g1"}], has_perms: [],
+                     no_perm_status_code: 404, no_perm_response: {code: -2}}
+                ]
+            }"#,
+        );
+        let no_perm = json!({"code": -1, "msg": "no perm"});
+        assert_eq!(None, check_auth(&auth_doc, "/orders/", Some("u1")));
+
+        // 没有设置no_auth_response和invalid_token_response时返回no_perm_response
+        let realm = r#"Bearer realm="Test auth""#;
+        assert_eq!(
+            Some((401, realm.to_string(), no_perm.clone())),
+            check_auth(&auth_doc, "/orders/", None)
+        );
+        let invalid = format!(
+            r#"{}, error="invalid_token", error_description="the token is invalid""#,
+            realm
+        );
+        assert_eq!(
+            Some((401, invalid, no_perm.clone())),
+            check_auth(&auth_doc, "/orders/", Some("bad"))
+        );
+        let insufficient = format!(
+            r#"{}, error="insufficient_scope", error_description="no perm to visit""#,
+            realm
+        );
+        assert_eq!(
+            Some((403, insufficient, no_perm)),
+            check_auth(&auth_doc, "/users/", Some("u1"))
+        );
+
+        // 权限组自己的状态码和返回, 不是401和403时没有WWW-Authenticate
+        assert_eq!(
+            Some((404, "".to_string(), json!({"code": -2}))),
+            check_auth(&auth_doc, "/orders/", Some("g1"))
+        );
+    }
+
+    #[test]
+    fn auth_validator_jwt_test() {
+        let auth_doc = load_test_auth(
+            "jwt",
+            r#"{
+                name: "Jwt auth",
+                auth_type: "jwt",
+                jwt: {secret: "s3cret", expired_response: {code: -3}},
+                no_auth_response: {code: -4},
+                no_auth_status_code: 400,
+                invalid_token_response: {code: -5},
+                groups: [{name: "user", users: [{username: "alice"}], has_perms: ["/orders/"]}]
+            }"#,
+        );
+        let jwt_config = auth_doc.as_ref().unwrap().jwt.as_ref().unwrap();
+        let claims = json!({"sub": "alice", "group": "user"});
+        let claims = claims.as_object().unwrap();
+        let token = jwt::sign_claims(jwt_config, claims, None, 60).unwrap();
+        assert_eq!(None, check_auth(&auth_doc, "/orders/", Some(&token)));
+
+        assert_eq!(
+            Some((400, "".to_string(), json!({"code": -4}))),
+            check_auth(&auth_doc, "/orders/", None)
+        );
+        let (status, _, body) = check_auth(&auth_doc, "/orders/", Some("a.b.c")).unwrap();
+        assert_eq!((401, json!({"code": -5})), (status, body));
+
+        let expired = jwt::sign_claims(jwt_config, claims, None, -60).unwrap();
+        assert_eq!(
+            Some((
+                401,
+                r#"Bearer realm="Jwt auth", error="invalid_token", error_description="the token has expired""#.to_string(),
+                json!({"code": -3})
+            )),
+            check_auth(&auth_doc, "/orders/", Some(&expired))
+        );
+
+        // refresh_token不能访问接口
+        let refresh = jwt::sign_claims(jwt_config, claims, Some("refresh"), 60).unwrap();
+        let (status, _, body) = check_auth(&auth_doc, "/orders/", Some(&refresh)).unwrap();
+        assert_eq!((401, json!({"code": -5})), (status, body));
+    }
+
+    fn websocket_api() -> db::ApiData {
+        db::ApiData {
+            url: "/ws/order/{id}".to_string(),
+            method: vec!["WEBSOCKET".to_string()],
+            body: json!({"action": {"name": "action", "enum": ["ping", "query"]}}),
+            response: json!({"status": {"name": "order status", "enum": ["paid", "sent"]}}),
+            test_data: json!([
+                {"body": {"action": "ping"}, "response": {"action": "pong"}},
+                {"body": "hello", "response": "world", "delay": 500}
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_websocket_response_test() {
+        let api = websocket_api();
+        let (response, delay) = get_websocket_response(&api, &json!({"action": "ping"}));
+        assert_eq!((json!({"action": "pong"}), 0), (response, delay));
+        let (response, delay) = get_websocket_response(&api, &json!("hello"));
+        assert_eq!((json!("world"), 500), (response, delay));
+
+        // 没有匹配的测试数据时按response生成
+        let (response, _) = get_websocket_response(&api, &json!({"action": "query"}));
+        assert!(["paid", "sent"].contains(&response["status"].as_str().unwrap()));
+
+        let (response, _) = get_websocket_response(&api, &json!({"action": "close"}));
+        assert_eq!(-1, response["code"]);
+        assert_eq!(
+            "message error: /action should be one of [\"ping\",\"query\"]",
+            response["msg"]
+        );
+        let (response, _) = get_websocket_response(&api, &json!("bye"));
+        assert_eq!("message error: / should be object", response["msg"]);
+    }
+
+    #[test]
+    fn get_room_push_message_test() {
+        let mut api = websocket_api();
+        api.push = json!([{"name": "order closed", "manual": true, "response": {"status": {"value": "closed"}}}]);
+        let request =
+            |url: Option<&str>, name: Option<&str>, message: Option<Value>| WebsocketPushRequest {
+                url: url.map(|v| v.to_string()),
+                name: name.map(|v| v.to_string()),
+                message,
+            };
+        let closed = Some(json!({"status": "closed"}));
+
+        // 按接口的url或者连接的地址选择连接
+        let push = request(Some("/ws/order/{id}"), Some("order closed"), None);
+        assert_eq!(
+            closed,
+            get_room_push_message(&push, "/ws/order/3", Some(&api))
+        );
+        let push = request(Some("/ws/order/3"), Some("order closed"), None);
+        assert_eq!(
+            closed,
+            get_room_push_message(&push, "/ws/order/3", Some(&api))
+        );
+        assert_eq!(
+            None,
+            get_room_push_message(&push, "/ws/order/4", Some(&api))
+        );
+
+        // message优先于name, 没有url时推送给所有连接
+        let push = request(None, Some("order closed"), Some(json!("hi")));
+        assert_eq!(
+            Some(json!("hi")),
+            get_room_push_message(&push, "/chat", None)
+        );
+        let push = request(None, Some("missing"), None);
+        assert_eq!(
+            None,
+            get_room_push_message(&push, "/ws/order/3", Some(&api))
+        );
+        let push = request(None, Some("order closed"), None);
+        assert_eq!(None, get_room_push_message(&push, "/chat", None));
+    }
+}
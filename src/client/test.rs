@@ -6,14 +6,14 @@ use std::collections::HashMap;
 
 use crate::db;
 use actix_web::web;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 use crate::Test;
 
 /// 执行测试后端接口
-pub async fn run_test(conf: Test, db_data: web::Data<Mutex<db::Database>>) {
+pub async fn run_test(conf: Test, db_data: web::Data<db::DataStore>) {
     log::info!("start run test job {:?}", conf);
-    let db_data = db_data.lock().unwrap();
+    let db_data = db_data.load();
     let db_api_data = &db_data.api_data;
     let db_api_docs = &db_data.api_docs;
 
@@ -72,9 +72,7 @@ pub async fn run_test(conf: Test, db_data: web::Data<Mutex<db::Database>>) {
     }
 }
 
-async fn do_a_api_test(api: &Arc<Mutex<db::ApiData>>, server_url: &str) {
-    let api = api.lock().unwrap();
-
+async fn do_a_api_test(api: &Arc<db::ApiData>, server_url: &str) {
     let api_url = format!("{}{}", server_url, &api.url);
     if let Some(test_data) = api.test_data.as_array() {
         for a_data in test_data {
@@ -0,0 +1,73 @@
+pub mod model;
+pub mod rust;
+pub mod ts;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::db;
+use crate::Gen;
+
+/// 生成的文件
+pub struct GenFile {
+    pub filename: String,
+    pub content: String,
+    /// 文件已经存在时不覆盖, 用于需要手动修改的代码
+    pub keep_existing: bool,
+}
+
+impl GenFile {
+    pub fn new(filename: &str, content: String) -> GenFile {
+        GenFile {
+            filename: filename.to_string(),
+            content,
+            keep_existing: false,
+        }
+    }
+}
+
+/// 按接口文档生成代码, 写到输出目录中, 默认是 _gen/语言
+pub fn run_gen(conf: &Gen, root: &Path) -> Result<(), String> {
+    let data = db::Database::load(root);
+    let model = model::build_code_model(&data);
+    let (lang, files) = match conf.lang.as_str() {
+        "ts" | "typescript" => ("ts", ts::generate(&model)),
+        "rust" | "rs" => match conf.framework.as_str() {
+            "actix" | "axum" => ("rust", rust::generate(&model, &conf.framework)),
+            framework => {
+                return Err(format!(
+                    "unsupported framework {}, available: actix, axum",
+                    framework
+                ))
+            }
+        },
+        lang => {
+            return Err(format!(
+                "unsupported language {}, available: ts, rust",
+                lang
+            ))
+        }
+    };
+
+    let out = match &conf.out {
+        Some(out) => PathBuf::from(out),
+        None => root.join("_gen").join(lang),
+    };
+    let out = out.as_path();
+    fs::create_dir_all(out).map_err(|e| format!("create {} error: {}", out.display(), e))?;
+    for file in files {
+        let filepath = out.join(&file.filename);
+        if file.keep_existing && filepath.exists() {
+            println!("skip {}, it already exists", filepath.display());
+            continue;
+        }
+        // 内容没有变化时不重写文件, 避免触发重新编译
+        if fs::read_to_string(&filepath).ok().as_deref() == Some(file.content.as_str()) {
+            continue;
+        }
+        fs::write(&filepath, file.content)
+            .map_err(|e| format!("write {} error: {}", filepath.display(), e))?;
+        println!("generated {}", filepath.display());
+    }
+    Ok(())
+}
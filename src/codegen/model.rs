@@ -0,0 +1,584 @@
+//! 从接口文档生成客户端代码用的类型模型, 各个语言按照这个模型输出代码
+//! 类型按接口和字段路径命名, 输出的顺序只和文档内容有关, 重新生成时diff是稳定的
+
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+use crate::db;
+
+/// 字段的类型
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr {
+    String,
+    Int,
+    Float,
+    Bool,
+    Date,
+    DateTime,
+    /// 秒数的时间戳
+    Timestamp,
+    Uuid,
+    Any,
+    /// enum 或者 $enum 中的值
+    Enum(Vec<Value>),
+    Array(Box<TypeExpr>),
+    /// 数组中定义了多种元素
+    Union(Vec<TypeExpr>),
+    /// map的key都是字符串, 这里是value的类型
+    Map(Box<TypeExpr>),
+    /// 生成的命名类型
+    Named(String),
+    /// 递归引用的命名类型
+    Rec(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub ty: TypeExpr,
+    pub required: bool,
+    pub desc: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum TypeKind {
+    Object(Vec<Field>),
+    Alias(TypeExpr),
+}
+
+/// 生成的命名类型
+#[derive(Debug, Clone)]
+pub struct TypeDef {
+    pub name: String,
+    pub desc: String,
+    pub kind: TypeKind,
+}
+
+/// 接口请求中的一部分: url参数, query, body
+#[derive(Debug, Clone)]
+pub struct RequestPart {
+    pub type_name: String,
+    /// 是否有必填的字段
+    pub required: bool,
+}
+
+/// 一个接口的一个请求方法, 生成一个请求函数
+#[derive(Debug, Clone)]
+pub struct ApiFn {
+    pub name: String,
+    pub method: String,
+    pub url: String,
+    pub title: String,
+    pub desc: String,
+    pub body_mode: String,
+    /// url中的参数名, 按出现的顺序
+    pub url_params: Vec<String>,
+    pub url_param: Option<RequestPart>,
+    pub query: Option<RequestPart>,
+    pub body: Option<RequestPart>,
+    pub response: Option<String>,
+}
+
+/// 一个接口文档文件中的接口
+#[derive(Debug, Clone)]
+pub struct ApiGroup {
+    pub name: String,
+    pub doc: String,
+    pub title: String,
+    pub apis: Vec<ApiFn>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CodeModel {
+    pub types: Vec<TypeDef>,
+    pub groups: Vec<ApiGroup>,
+}
+
+/// 可以生成请求函数的方法, websocket和graphql接口不生成
+const HTTP_METHODS: [&str; 7] = ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
+/// 按接口文档生成类型模型
+pub fn build_code_model(data: &db::Database) -> CodeModel {
+    let mut builder = Builder::default();
+    let mut groups: Vec<ApiGroup> = Vec::new();
+    let mut group_names = HashSet::new();
+
+    let mut api_docs: Vec<&db::ApiDoc> = data.api_docs.values().collect();
+    api_docs.sort_by(|a, b| a.order.cmp(&b.order).then(a.filename.cmp(&b.filename)));
+
+    for api_doc in api_docs {
+        let doc_name = api_doc.filename.trim_end_matches(".json5");
+        let mut group = ApiGroup {
+            name: unique_name(&mut group_names, &camel_case(doc_name)),
+            doc: api_doc.filename.clone(),
+            title: api_doc.name.clone(),
+            apis: Vec::new(),
+        };
+        let mut fn_names = HashSet::new();
+        for api in &api_doc.apis {
+            let methods: Vec<&String> = api
+                .method
+                .iter()
+                .filter(|m| HTTP_METHODS.contains(&m.as_str()))
+                .collect();
+            if methods.is_empty() {
+                continue;
+            }
+            let url_params = get_url_param_names(&api.url);
+            let base = builder.unique_type_name(&pascal_case(&format!(
+                "{} {}",
+                methods[0].to_lowercase(),
+                url_words(&api.url)
+            )));
+            let url_param = builder.url_param_part(&base, api, &url_params);
+            let query = builder.request_part(&format!("{}Query", base), &api.query);
+            let body = builder.request_part(&format!("{}Body", base), &api.body);
+            let response = builder.part_type(&format!("{}Response", base), &api.response);
+
+            for method in methods {
+                let name = camel_case(&format!(
+                    "{} {}",
+                    method.to_lowercase(),
+                    url_words(&api.url)
+                ));
+                group.apis.push(ApiFn {
+                    name: unique_name(&mut fn_names, &name),
+                    method: method.to_string(),
+                    url: api.url.clone(),
+                    title: api.name.clone(),
+                    desc: api.desc.clone(),
+                    body_mode: api.body_mode.clone(),
+                    url_params: url_params.clone(),
+                    url_param: url_param.clone(),
+                    query: query.clone(),
+                    body: body.clone(),
+                    response: response.clone(),
+                });
+            }
+        }
+        if !group.apis.is_empty() {
+            groups.push(group);
+        }
+    }
+
+    CodeModel {
+        types: builder.types,
+        groups,
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    types: Vec<TypeDef>,
+    /// 已经使用的类型名称
+    names: HashSet<String>,
+    /// 已经生成的类型
+    emitted: HashSet<String>,
+    /// 正在生成的递归类型, 避免无限递归
+    in_progress: HashSet<String>,
+}
+
+impl Builder {
+    fn unique_type_name(&mut self, name: &str) -> String {
+        unique_name(&mut self.names, name)
+    }
+
+    fn add_type(&mut self, name: &str, desc: String, kind: TypeKind) {
+        if !self.emitted.insert(name.to_string()) {
+            return;
+        }
+        self.names.insert(name.to_string());
+        self.types.push(TypeDef {
+            name: name.to_string(),
+            desc,
+            kind,
+        });
+    }
+
+    /// url参数的类型, url中的参数都是必填的, 文档url_param中没有定义的参数按字符串处理
+    fn url_param_part(
+        &mut self,
+        base: &str,
+        api: &db::ApiData,
+        url_params: &[String],
+    ) -> Option<RequestPart> {
+        if url_params.is_empty() {
+            return None;
+        }
+        let name = format!("{}UrlParam", base);
+        let fields = url_params
+            .iter()
+            .map(|param| {
+                let attr = api.url_param.get(param).unwrap_or(&Value::Null);
+                Field {
+                    name: param.to_string(),
+                    ty: match attr {
+                        Value::Null => TypeExpr::String,
+                        _ => {
+                            let field_name = format!("{}{}", name, pascal_case(param));
+                            self.type_expr(attr, &field_name, attr, &field_name)
+                        }
+                    },
+                    required: true,
+                    desc: field_desc(attr),
+                }
+            })
+            .collect();
+        self.add_type(&name, String::new(), TypeKind::Object(fields));
+        Some(RequestPart {
+            type_name: name,
+            required: true,
+        })
+    }
+
+    /// query和body的类型, 没有定义字段时返回None
+    fn request_part(&mut self, name: &str, model: &Value) -> Option<RequestPart> {
+        let type_name = self.part_type(name, model)?;
+        let required = match self.types.iter().find(|t| t.name == type_name) {
+            Some(TypeDef {
+                kind: TypeKind::Object(fields),
+                ..
+            }) => fields.iter().any(|f| f.required),
+            _ => true,
+        };
+        Some(RequestPart {
+            type_name,
+            required,
+        })
+    }
+
+    /// query, body或者response的类型, 对象生成interface, 其它的生成类型别名
+    fn part_type(&mut self, name: &str, model: &Value) -> Option<String> {
+        if is_empty_model(model) {
+            return None;
+        }
+        let ty = self.type_expr(model, name, model, name);
+        if ty != TypeExpr::Named(name.to_string()) {
+            self.add_type(name, String::new(), TypeKind::Alias(ty));
+        }
+        Some(name.to_string())
+    }
+
+    /// 字段的类型, name是对象生成的类型名称, root是递归$ref的根节点, root_name是根节点的类型名称
+    fn type_expr(&mut self, attr: &Value, name: &str, root: &Value, root_name: &str) -> TypeExpr {
+        if let Value::Array(items) = attr {
+            let items: Vec<TypeExpr> = items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| {
+                    !item.is_null() && item.get("$__continue_array_value").is_none()
+                })
+                .map(|(i, item)| {
+                    let item_type_name = format!("{}{}", name, item_name(i));
+                    self.type_expr(item, &item_type_name, root, root_name)
+                })
+                .collect();
+            return TypeExpr::Array(Box::new(match items.len() {
+                0 => TypeExpr::Any,
+                1 => items[0].clone(),
+                _ => TypeExpr::Union(items),
+            }));
+        }
+        if attr.is_null() {
+            return TypeExpr::Any;
+        }
+        if attr.get("enum").is_some() || attr.get("$enum").is_some() {
+            return scalar_type("", attr);
+        }
+
+        match db::get_field_type(attr).as_str() {
+            "object" => {
+                let fields = self.object_fields(attr, name, root, root_name);
+                self.add_type(name, field_desc(attr), TypeKind::Object(fields));
+                TypeExpr::Named(name.to_string())
+            }
+            "map" => match attr.get("$value") {
+                Some(value) => {
+                    let value_name = format!("{}Value", name);
+                    TypeExpr::Map(Box::new(self.type_expr(
+                        value,
+                        &value_name,
+                        root,
+                        root_name,
+                    )))
+                }
+                None => TypeExpr::Map(Box::new(TypeExpr::Any)),
+            },
+            "rec" => {
+                // 递归的类型指向$ref节点生成的类型, 对象节点的类型在生成这个节点时已经命名
+                // 数组节点生成一个类型别名
+                let pointer = attr.get("$ref").and_then(|v| v.as_str()).unwrap_or("/");
+                let pointer = if pointer == "/" { "" } else { pointer };
+                let target_name = pointer_type_name(root_name, pointer);
+                match root.pointer(pointer) {
+                    Some(node) if node.is_array() => {
+                        if !self.emitted.contains(&target_name)
+                            && self.in_progress.insert(target_name.clone())
+                        {
+                            let ty = self.type_expr(node, &target_name, root, root_name);
+                            self.add_type(&target_name, String::new(), TypeKind::Alias(ty));
+                            self.in_progress.remove(&target_name);
+                        }
+                        TypeExpr::Named(target_name)
+                    }
+                    Some(_) => TypeExpr::Rec(target_name),
+                    None => TypeExpr::Any,
+                }
+            }
+            field_type => scalar_type(field_type, attr),
+        }
+    }
+
+    fn object_fields(
+        &mut self,
+        attr: &Value,
+        name: &str,
+        root: &Value,
+        root_name: &str,
+    ) -> Vec<Field> {
+        let mut fields = Vec::new();
+        let obj = match attr.as_object() {
+            Some(obj) => obj,
+            None => return fields,
+        };
+        for (key, field_attr) in obj {
+            if key.starts_with('$') || key.contains('/') || field_attr.is_null() {
+                continue;
+            }
+            if !field_attr.is_object() && !field_attr.is_array() {
+                continue;
+            }
+            if field_attr.get("$del").and_then(|v| v.as_bool()) == Some(true) {
+                continue;
+            }
+            let required = field_attr
+                .get("required")
+                .or_else(|| field_attr.get("$required"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            let field_type_name = format!("{}{}", name, pascal_case(key));
+            fields.push(Field {
+                name: key.to_string(),
+                ty: self.type_expr(field_attr, &field_type_name, root, root_name),
+                required,
+                desc: field_desc(field_attr),
+            });
+        }
+        fields
+    }
+}
+
+/// json pointer对应的类型名称, 数组元素的路径是Item, Item2...
+pub fn pointer_type_name(root_name: &str, pointer: &str) -> String {
+    let mut result = root_name.to_string();
+    for segment in pointer.split('/').filter(|s| !s.is_empty()) {
+        match segment.parse::<usize>() {
+            Ok(i) => result.push_str(&item_name(i)),
+            Err(_) => result.push_str(&pascal_case(segment)),
+        }
+    }
+    result
+}
+
+fn item_name(i: usize) -> String {
+    if i == 0 {
+        "Item".to_string()
+    } else {
+        format!("Item{}", i + 1)
+    }
+}
+
+fn scalar_type(field_type: &str, attr: &Value) -> TypeExpr {
+    if let Some(Value::Array(values)) = attr.get("enum") {
+        if !values.is_empty() {
+            return TypeExpr::Enum(values.clone());
+        }
+    }
+    if let Some(Value::Array(values)) = attr.get("$enum") {
+        // $enum 是 [值, 说明] 的列表
+        let values: Vec<Value> = values.iter().filter_map(|v| v.get(0).cloned()).collect();
+        if !values.is_empty() {
+            return TypeExpr::Enum(values);
+        }
+    }
+    match field_type {
+        "number" | "int" | "posint" | "negint" => TypeExpr::Int,
+        "float" | "posfloat" | "negfloat" => TypeExpr::Float,
+        "bool" => TypeExpr::Bool,
+        "date" => TypeExpr::Date,
+        "datetime" => TypeExpr::DateTime,
+        "timestamp" => TypeExpr::Timestamp,
+        "uuid" => TypeExpr::Uuid,
+        _ => TypeExpr::String,
+    }
+}
+
+/// 字段的说明, 由name和desc组成
+pub fn field_desc(attr: &Value) -> String {
+    let get = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| attr.get(k).and_then(|v| v.as_str()))
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    };
+    let name = get(&["name", "$name"]);
+    let desc = get(&["desc", "$desc"]);
+    match (name.is_empty(), desc.is_empty()) {
+        (false, false) if name != desc => format!("{}: {}", name, desc),
+        (true, false) => desc,
+        _ => name,
+    }
+}
+
+/// 没有定义任何字段
+pub fn is_empty_model(model: &Value) -> bool {
+    match model {
+        Value::Null => true,
+        Value::Object(obj) => obj.keys().all(|k| k.starts_with('$')),
+        Value::Array(items) => items.is_empty(),
+        _ => true,
+    }
+}
+
+/// url中的参数名, /users/{user_id}/{name:\w+}/ => [user_id, name]
+pub fn get_url_param_names(url: &str) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let param = &rest[start + 1..end];
+        let param = param.split(':').next().unwrap_or("").trim();
+        if !param.is_empty() && !result.iter().any(|p| p == param) {
+            result.push(param.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    result
+}
+
+/// url转为名称用的单词, /users/{user_id}/ => users by user_id
+pub fn url_words(url: &str) -> String {
+    let words: Vec<String> = url
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != "*")
+        .map(|s| {
+            if s.starts_with('{') {
+                let param = s.trim_matches(|c| c == '{' || c == '}' || c == '*');
+                format!("by {}", param.split(':').next().unwrap_or(""))
+            } else {
+                s.to_string()
+            }
+        })
+        .collect();
+    if words.is_empty() {
+        "root".to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+fn split_words(s: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            prev_lower = false;
+            continue;
+        }
+        // userId 拆分为 user 和 id
+        if c.is_ascii_uppercase() && prev_lower && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// 转为大驼峰名称, 不是以字母开头时在前面加上下划线
+pub fn pascal_case(s: &str) -> String {
+    let mut result = String::new();
+    for word in split_words(s) {
+        let mut chars = word.chars();
+        if let Some(c) = chars.next() {
+            result.push(c.to_ascii_uppercase());
+            result.push_str(&chars.as_str().to_lowercase());
+        }
+    }
+    if !result.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        result.insert(0, '_');
+    }
+    result
+}
+
+/// 转为小驼峰名称
+pub fn camel_case(s: &str) -> String {
+    let pascal = pascal_case(s);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(c) => format!("{}{}", c.to_ascii_lowercase(), chars.as_str()),
+        None => pascal,
+    }
+}
+
+/// 名称重复时在后面加上序号
+pub fn unique_name(names: &mut HashSet<String>, name: &str) -> String {
+    let mut result = name.to_string();
+    let mut i = 2;
+    while names.contains(&result) {
+        result = format!("{}{}", name, i);
+        i += 1;
+    }
+    names.insert(result.clone());
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn code_model_names_test() {
+        assert_eq!(
+            vec!["user_id", "name"],
+            get_url_param_names("/users/{user_id}/{name:\\w+}/")
+        );
+        assert_eq!("GetUsersByUserId", pascal_case("get users by user_id"));
+        assert_eq!("orderItemList", camel_case("order/item-list"));
+        assert_eq!(
+            "ResponseChildrenItem",
+            pointer_type_name("Response", "/children/0")
+        );
+
+        let model = json!({
+            "name": {"name": "name"},
+            "children": [{"$type": "rec", "$ref": "/"}],
+            "$type": "object"
+        });
+        let mut builder = Builder::default();
+        assert_eq!(Some("Tree".to_string()), builder.part_type("Tree", &model));
+        match &builder.types[0].kind {
+            TypeKind::Object(fields) => {
+                assert_eq!("children", fields[0].name);
+                assert_eq!(
+                    TypeExpr::Array(Box::new(TypeExpr::Rec("Tree".to_string()))),
+                    fields[0].ty
+                );
+            }
+            _ => panic!("Tree should be an object"),
+        }
+    }
+}
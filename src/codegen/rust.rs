@@ -0,0 +1,657 @@
+//! 生成Rust服务端代码: types.rs 中是serde的结构体和枚举, routes.rs 中是actix-web或者axum的路由表
+//! handlers.rs 中是需要手动实现的接口函数, 已经存在时不会覆盖
+
+use std::collections::{BTreeSet, HashSet};
+
+use serde_json::Value;
+
+use super::model::{camel_case, pascal_case, ApiFn, CodeModel, Field, TypeExpr, TypeKind};
+use super::GenFile;
+
+const HEADER: &str = "//! Generated by panda api from the api docs, do not edit.\n";
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// 生成的文件, framework 为 actix 或者 axum
+pub fn generate(model: &CodeModel, framework: &str) -> Vec<GenFile> {
+    let axum = framework == "axum";
+    vec![
+        GenFile::new(
+            "mod.rs",
+            format!(
+                "{}\npub mod handlers;\npub mod routes;\npub mod types;\n",
+                HEADER
+            ),
+        ),
+        GenFile::new("types.rs", types_file(model)),
+        GenFile::new("routes.rs", routes_file(model, axum)),
+        GenFile {
+            keep_existing: true,
+            ..GenFile::new("handlers.rs", handlers_file(model, axum))
+        },
+    ]
+}
+
+/// 小写下划线的名称
+pub fn snake_case(s: &str) -> String {
+    let mut result = String::new();
+    for (i, c) in camel_case(s).chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                result.push('_');
+            }
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// 字段名称, 关键字使用原始标识符
+fn field_ident(name: &str) -> String {
+    let ident = snake_case(name);
+    if KEYWORDS.contains(&ident.as_str()) {
+        if ident == "self" || ident == "Self" || ident == "super" || ident == "crate" {
+            return format!("{}_", ident);
+        }
+        return format!("r#{}", ident);
+    }
+    ident
+}
+
+fn doc_comment(desc: &str, indent: &str) -> String {
+    desc.trim()
+        .lines()
+        .map(|line| format!("{}/// {}\n", indent, line).replace(" \n", "\n"))
+        .collect()
+}
+
+/// 字符串的枚举生成enum, 其它的枚举使用基础类型
+struct EnumDef {
+    name: String,
+    values: Vec<String>,
+}
+
+struct TypesWriter {
+    enums: Vec<EnumDef>,
+    uses_datetime: bool,
+    /// 数组和map中的时间使用的PandaDateTime和PandaTimestamp
+    uses_nested_datetime: bool,
+    uses_nested_timestamp: bool,
+}
+
+impl TypesWriter {
+    /// Rust中的类型, name是字段生成枚举时使用的名称, nested表示在Vec或者HashMap中
+    fn rust_type(&mut self, ty: &TypeExpr, name: &str, nested: bool) -> String {
+        match ty {
+            TypeExpr::String => "String".to_string(),
+            TypeExpr::Int => "i64".to_string(),
+            TypeExpr::Float => "f64".to_string(),
+            TypeExpr::Bool => "bool".to_string(),
+            TypeExpr::Date => "chrono::NaiveDate".to_string(),
+            // 时间在字段上使用serde with转换格式, 在数组和map中使用带格式的包装类型
+            TypeExpr::DateTime if nested => {
+                self.uses_datetime = true;
+                self.uses_nested_datetime = true;
+                "PandaDateTime".to_string()
+            }
+            TypeExpr::DateTime => "chrono::NaiveDateTime".to_string(),
+            TypeExpr::Timestamp if nested => {
+                self.uses_nested_timestamp = true;
+                "PandaTimestamp".to_string()
+            }
+            TypeExpr::Timestamp => "chrono::DateTime<chrono::Utc>".to_string(),
+            TypeExpr::Uuid => "uuid::Uuid".to_string(),
+            TypeExpr::Any | TypeExpr::Union(_) => "serde_json::Value".to_string(),
+            TypeExpr::Enum(values) => self.enum_type(values, name),
+            TypeExpr::Array(item) => format!("Vec<{}>", self.rust_type(item, name, true)),
+            TypeExpr::Map(value) => format!(
+                "std::collections::HashMap<String, {}>",
+                self.rust_type(value, name, true)
+            ),
+            TypeExpr::Named(type_name) => type_name.to_string(),
+            // 直接递归的结构体需要放在Box中
+            TypeExpr::Rec(type_name) if nested => type_name.to_string(),
+            TypeExpr::Rec(type_name) => format!("Box<{}>", type_name),
+        }
+    }
+
+    fn enum_type(&mut self, values: &[Value], name: &str) -> String {
+        if values.iter().all(|v| v.is_string()) {
+            let values = values
+                .iter()
+                .map(|v| v.as_str().unwrap_or("").to_string())
+                .collect();
+            if !self.enums.iter().any(|e| e.name == name) {
+                self.enums.push(EnumDef {
+                    name: name.to_string(),
+                    values,
+                });
+            }
+            name.to_string()
+        } else if values.iter().all(|v| v.is_i64()) {
+            "i64".to_string()
+        } else if values.iter().all(|v| v.is_number()) {
+            "f64".to_string()
+        } else if values.iter().all(|v| v.is_boolean()) {
+            "bool".to_string()
+        } else {
+            "serde_json::Value".to_string()
+        }
+    }
+
+    fn field_code(&mut self, owner: &str, field: &Field) -> String {
+        let mut result = doc_comment(&field.desc, "    ");
+        if let TypeExpr::Enum(values) = &field.ty {
+            if !values.iter().all(|v| v.is_string()) {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                result.push_str(&format!("    /// enum: {}\n", values.join(", ")));
+            }
+        }
+
+        let ident = field_ident(&field.name);
+        let mut attrs = Vec::new();
+        if ident.trim_start_matches("r#") != field.name {
+            attrs.push(format!(
+                "rename = {}",
+                Value::String(field.name.to_string())
+            ));
+        }
+        let with = match &field.ty {
+            TypeExpr::DateTime => Some("panda_datetime"),
+            TypeExpr::Timestamp => Some("chrono::serde::ts_seconds"),
+            _ => None,
+        };
+        if let Some(with) = with {
+            self.uses_datetime = self.uses_datetime || with == "panda_datetime";
+            let with = if field.required {
+                with.to_string()
+            } else if with == "panda_datetime" {
+                "panda_datetime::option".to_string()
+            } else {
+                "chrono::serde::ts_seconds_option".to_string()
+            };
+            attrs.push(format!("with = \"{}\"", with));
+        }
+        if !field.required {
+            attrs.push("default".to_string());
+            attrs.push("skip_serializing_if = \"Option::is_none\"".to_string());
+        }
+        if !attrs.is_empty() {
+            result.push_str(&format!("    #[serde({})]\n", attrs.join(", ")));
+        }
+
+        let ty = self.rust_type(
+            &field.ty,
+            &format!("{}{}", owner, pascal_case(&field.name)),
+            false,
+        );
+        let ty = if field.required {
+            ty
+        } else {
+            format!("Option<{}>", ty)
+        };
+        result.push_str(&format!("    pub {}: {},\n", ident, ty));
+        result
+    }
+}
+
+fn types_file(model: &CodeModel) -> String {
+    let mut writer = TypesWriter {
+        enums: Vec::new(),
+        uses_datetime: false,
+        uses_nested_datetime: false,
+        uses_nested_timestamp: false,
+    };
+    let mut body = String::new();
+    for type_def in &model.types {
+        body.push('\n');
+        body.push_str(&doc_comment(&type_def.desc, ""));
+        match &type_def.kind {
+            TypeKind::Object(fields) => {
+                body.push_str("#[derive(Debug, Clone, Serialize, Deserialize)]\n");
+                body.push_str(&format!("pub struct {} {{\n", type_def.name));
+                for field in fields {
+                    body.push_str(&writer.field_code(&type_def.name, field));
+                }
+                body.push_str("}\n");
+            }
+            TypeKind::Alias(ty) => {
+                let ty = writer.rust_type(ty, &format!("{}Item", type_def.name), true);
+                body.push_str(&format!("pub type {} = {};\n", type_def.name, ty));
+            }
+        }
+    }
+
+    for enum_def in &writer.enums {
+        body.push('\n');
+        body.push_str("#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]\n");
+        body.push_str(&format!("pub enum {} {{\n", enum_def.name));
+        let mut names = HashSet::new();
+        for (i, value) in enum_def.values.iter().enumerate() {
+            let mut variant = pascal_case(value);
+            if variant.starts_with('_') || !names.insert(variant.clone()) {
+                variant = format!("Value{}", i + 1);
+            }
+            body.push_str(&format!(
+                "    #[serde(rename = {})]\n    {},\n",
+                Value::String(value.to_string()),
+                variant
+            ));
+        }
+        body.push_str("}\n");
+    }
+
+    let mut result = HEADER.to_string();
+    result.push_str("#![allow(dead_code)]\n\nuse serde::{Deserialize, Serialize};\n");
+    result.push_str(&body);
+    if writer.uses_nested_datetime {
+        result.push_str(NESTED_DATETIME);
+    }
+    if writer.uses_nested_timestamp {
+        result.push_str(NESTED_TIMESTAMP);
+    }
+    if writer.uses_datetime {
+        result.push_str(DATETIME_MODULE);
+    }
+    result
+}
+
+const NESTED_DATETIME: &str = r#"
+/// datetime in a Vec or HashMap, in the panda api format: 2020-01-01 12:00:00
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PandaDateTime(#[serde(with = "panda_datetime")] pub chrono::NaiveDateTime);
+"#;
+
+const NESTED_TIMESTAMP: &str = r#"
+/// timestamp in seconds in a Vec or HashMap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PandaTimestamp(#[serde(with = "chrono::serde::ts_seconds")] pub chrono::DateTime<chrono::Utc>);
+"#;
+
+/// panda的datetime格式是 2020-01-01 12:00:00, 和chrono默认的格式不同
+const DATETIME_MODULE: &str = r#"
+/// datetime fields use the panda api format: 2020-01-01 12:00:00
+pub mod panda_datetime {
+    use chrono::NaiveDateTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    pub fn serialize<S: Serializer>(value: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.format(FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        NaiveDateTime::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom)
+    }
+
+    pub mod option {
+        use chrono::NaiveDateTime;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(value: &Option<NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error> {
+            match Option::<String>::deserialize(deserializer)? {
+                Some(s) => NaiveDateTime::parse_from_str(&s, super::FORMAT)
+                    .map(Some)
+                    .map_err(serde::de::Error::custom),
+                None => Ok(None),
+            }
+        }
+    }
+}
+"#;
+
+/// axum的路由参数不支持正则, 尾部通配使用 {*tail}
+fn axum_url(url: &str) -> String {
+    let mut result = String::new();
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        let param = rest[start + 1..end].split(':').next().unwrap_or("").trim();
+        result.push_str(&format!("{{{}}}", param));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    if result.ends_with('*') {
+        result.pop();
+        result.push_str("{*tail}");
+    }
+    result
+}
+
+fn routes_file(model: &CodeModel, axum: bool) -> String {
+    let mut result = HEADER.to_string();
+    if axum {
+        // axum同一个地址的多个方法要放在一个route中
+        let mut routes: Vec<(String, Vec<String>)> = Vec::new();
+        for group in &model.groups {
+            for api in &group.apis {
+                let handler = format!(
+                    "{}(handlers::{}::{})",
+                    api.method.to_lowercase(),
+                    snake_case(&group.name),
+                    snake_case(&api.name)
+                );
+                let url = axum_url(&api.url);
+                match routes.iter_mut().find(|(u, _)| u == &url) {
+                    Some((_, handlers)) => handlers.push(handler),
+                    None => routes.push((url, vec![handler])),
+                }
+            }
+        }
+        // 只导入每个route第一个方法的函数, 避免未使用的导入
+        let methods: BTreeSet<&str> = routes
+            .iter()
+            .filter_map(|(_, handlers)| handlers[0].split('(').next())
+            .collect();
+        let methods: Vec<&str> = methods.into_iter().collect();
+        if methods.len() == 1 {
+            result.push_str(&format!("use axum::routing::{};\n", methods[0]));
+        } else {
+            result.push_str(&format!("use axum::routing::{{{}}};\n", methods.join(", ")));
+        }
+        result.push_str("use axum::Router;\n\nuse super::handlers;\n\n");
+        result.push_str("pub fn router() -> Router {\n    Router::new()\n");
+        for (url, handlers) in &routes {
+            result.push_str(&format!(
+                "        .route({}, {})\n",
+                Value::String(url.to_string()),
+                handlers.join(".")
+            ));
+        }
+        result.push_str("}\n");
+    } else {
+        result.push_str("use actix_web::web;\n\nuse super::handlers;\n\n");
+        result.push_str("pub fn configure(cfg: &mut web::ServiceConfig) {\n    cfg");
+        for group in &model.groups {
+            for api in &group.apis {
+                result.push_str(&format!(
+                    "\n        .route({}, web::{}().to(handlers::{}::{}))",
+                    Value::String(api.url.to_string()),
+                    api.method.to_lowercase(),
+                    snake_case(&group.name),
+                    snake_case(&api.name)
+                ));
+            }
+        }
+        result.push_str(";\n}\n");
+    }
+    result
+}
+
+fn handlers_file(model: &CodeModel, axum: bool) -> String {
+    let mut result = String::from(
+        "//! Handler stubs generated by panda api, implement them here.\n\
+         //! This file is not overwritten when the code is generated again.\n\
+         #![allow(unused_imports, unused_variables)]\n",
+    );
+    for group in &model.groups {
+        result.push('\n');
+        result.push_str(&doc_comment(&format!("{} {}", group.doc, group.title), ""));
+        result.push_str(&format!("pub mod {} {{\n", snake_case(&group.name)));
+        if axum {
+            result.push_str("    use axum::extract::{Form, Json, Path, Query};\n");
+        } else {
+            result.push_str("    use actix_web::web::{Form, Json, Path, Query};\n");
+        }
+        result.push_str("\n    use super::super::types::*;\n");
+        for api in &group.apis {
+            result.push('\n');
+            result.push_str(&handler_code(api, axum));
+        }
+        result.push_str("}\n");
+    }
+    result
+}
+
+fn handler_code(api: &ApiFn, axum: bool) -> String {
+    let mut args = Vec::new();
+    if let Some(part) = &api.url_param {
+        args.push(format!("Path(params): Path<{}>", part.type_name));
+    }
+    if let Some(part) = &api.query {
+        args.push(format!("Query(query): Query<{}>", part.type_name));
+    }
+    let mut todo = String::new();
+    if let Some(part) = &api.body {
+        match api.body_mode.as_str() {
+            "urlencoded" | "x-www-form-urlencoded" => {
+                args.push(format!("Form(body): Form<{}>", part.type_name));
+            }
+            "form-data" => {
+                // multipart需要按字段读取, 框架没有直接转换为结构体的extractor
+                let extractor = if axum {
+                    "axum::extract::Multipart"
+                } else {
+                    "actix_multipart::Multipart"
+                };
+                todo = format!(
+                    "        // TODO: read the multipart body {} with {}\n",
+                    part.type_name, extractor
+                );
+            }
+            _ => args.push(format!("Json(body): Json<{}>", part.type_name)),
+        }
+    }
+    if !axum {
+        // actix的extractor不支持在参数中解构
+        args = args
+            .iter()
+            .map(|a| {
+                let (pattern, ty) = a.split_at(a.find(':').unwrap_or(0));
+                let name = pattern
+                    .trim_end_matches(')')
+                    .split('(')
+                    .nth(1)
+                    .unwrap_or(pattern);
+                format!("{}{}", name, ty)
+            })
+            .collect();
+    }
+    let response = match &api.response {
+        Some(name) => name.to_string(),
+        None => "serde_json::Value".to_string(),
+    };
+    let mut desc = api.title.to_string();
+    if !api.desc.trim().is_empty() && api.desc != api.title {
+        desc.push('\n');
+        desc.push_str(&api.desc);
+    }
+    desc.push_str(&format!("\n{} {}", api.method, api.url));
+    format!(
+        "{}    pub async fn {}({}) -> Json<{}> {{\n{}        todo!()\n    }}\n",
+        doc_comment(&desc, "    "),
+        snake_case(&api.name),
+        args.join(", "),
+        response,
+        todo
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codegen::model::build_code_model;
+    use crate::db;
+    use std::fs;
+
+    /// 按一个小的接口文档生成代码, 返回文件名和内容
+    fn generate_files(framework: &str) -> Vec<(String, String)> {
+        let root =
+            std::env::temp_dir().join(format!("panda-gen-{}-{}", framework, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let doc = r#"{
+            name: "Orders",
+            apis: [
+                {name: "order detail", url: "/orders/{id}/", method: "GET", response: {
+                    id: {type: "int"},
+                    created: {type: "datetime"},
+                    paid_at: [{type: "timestamp"}],
+                    logs: {$type: "map", $value: {type: "datetime"}}
+                }},
+                {name: "create order", url: "/orders/", method: "POST", body_mode: "urlencoded",
+                 body: {name: {type: "string", required: true}}},
+                {name: "upload", url: "/orders/{id}/files/", method: "POST", body_mode: "form-data",
+                 body: {file: {type: "file"}}}
+            ]
+        }"#;
+        fs::write(root.join("orders.json5"), doc).unwrap();
+        let data = db::Database::load(&root);
+        let _ = fs::remove_dir_all(&root);
+        generate(&build_code_model(&data), framework)
+            .into_iter()
+            .map(|f| (f.filename, f.content))
+            .collect()
+    }
+
+    #[test]
+    fn generate_actix_test() {
+        let files = generate_files("actix");
+        let file = |name: &str| {
+            files
+                .iter()
+                .find(|(filename, _)| filename == name)
+                .map(|(_, content)| content.as_str())
+                .unwrap()
+        };
+
+        let types = file("types.rs");
+        let (types, datetime_module) = types.split_at(types.find("\n/// datetime fields").unwrap());
+        assert!(datetime_module.contains("pub mod panda_datetime {"));
+        assert_eq!(
+            r#"//! Generated by panda api from the api docs, do not edit.
+#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetOrdersByIdUrlParam {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetOrdersByIdResponse {
+    #[serde(with = "panda_datetime")]
+    pub created: chrono::NaiveDateTime,
+    pub id: i64,
+    pub logs: std::collections::HashMap<String, PandaDateTime>,
+    pub paid_at: Vec<PandaTimestamp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostOrdersBody {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostOrdersByIdFilesUrlParam {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostOrdersByIdFilesBody {
+    pub file: String,
+}
+
+/// datetime in a Vec or HashMap, in the panda api format: 2020-01-01 12:00:00
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PandaDateTime(#[serde(with = "panda_datetime")] pub chrono::NaiveDateTime);
+
+/// timestamp in seconds in a Vec or HashMap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PandaTimestamp(#[serde(with = "chrono::serde::ts_seconds")] pub chrono::DateTime<chrono::Utc>);
+"#,
+            types
+        );
+
+        assert_eq!(
+            r#"//! Generated by panda api from the api docs, do not edit.
+use actix_web::web;
+
+use super::handlers;
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/orders/{id}/", web::get().to(handlers::orders::get_orders_by_id))
+        .route("/orders/", web::post().to(handlers::orders::post_orders))
+        .route("/orders/{id}/files/", web::post().to(handlers::orders::post_orders_by_id_files));
+}
+"#,
+            file("routes.rs")
+        );
+
+        assert_eq!(
+            r#"//! Handler stubs generated by panda api, implement them here.
+//! This file is not overwritten when the code is generated again.
+#![allow(unused_imports, unused_variables)]
+
+/// orders.json5 Orders
+pub mod orders {
+    use actix_web::web::{Form, Json, Path, Query};
+
+    use super::super::types::*;
+
+    /// order detail
+    /// GET /orders/{id}/
+    pub async fn get_orders_by_id(params: Path<GetOrdersByIdUrlParam>) -> Json<GetOrdersByIdResponse> {
+        todo!()
+    }
+
+    /// create order
+    /// POST /orders/
+    pub async fn post_orders(body: Form<PostOrdersBody>) -> Json<serde_json::Value> {
+        todo!()
+    }
+
+    /// upload
+    /// POST /orders/{id}/files/
+    pub async fn post_orders_by_id_files(params: Path<PostOrdersByIdFilesUrlParam>) -> Json<serde_json::Value> {
+        // TODO: read the multipart body PostOrdersByIdFilesBody with actix_multipart::Multipart
+        todo!()
+    }
+}
+"#,
+            file("handlers.rs")
+        );
+    }
+
+    #[test]
+    fn generate_axum_test() {
+        let files = generate_files("axum");
+        let routes = &files.iter().find(|(f, _)| f == "routes.rs").unwrap().1;
+        assert!(routes.contains(
+            ".route(\"/orders/{id}/files/\", post(handlers::orders::post_orders_by_id_files))"
+        ));
+        let handlers = &files.iter().find(|(f, _)| f == "handlers.rs").unwrap().1;
+        assert!(handlers.contains("use axum::extract::{Form, Json, Path, Query};"));
+        assert!(handlers.contains("pub async fn post_orders(Form(body): Form<PostOrdersBody>)"));
+        assert!(handlers.contains("with axum::extract::Multipart"));
+    }
+
+    #[test]
+    fn axum_url_test() {
+        assert_eq!(axum_url("/users/{user_id:\\d+}/"), "/users/{user_id}/");
+        assert_eq!(axum_url("/files/*"), "/files/{*tail}");
+        assert_eq!(field_ident("userName"), "user_name");
+        assert_eq!(field_ident("type"), "r#type");
+    }
+}
@@ -0,0 +1,294 @@
+//! 生成TypeScript代码: types.ts 中是接口的类型, client.ts 中是按文档文件分组的请求函数
+
+use serde_json::Value;
+
+use super::model::{ApiFn, CodeModel, Field, TypeDef, TypeExpr, TypeKind};
+use super::GenFile;
+
+const HEADER: &str = "// Generated by panda api from the api docs, do not edit.\n";
+
+/// 生成的文件名和内容
+pub fn generate(model: &CodeModel) -> Vec<GenFile> {
+    vec![
+        GenFile::new("types.ts", types_file(model)),
+        GenFile::new("client.ts", client_file(model)),
+    ]
+}
+
+fn types_file(model: &CodeModel) -> String {
+    let mut result = HEADER.to_string();
+    for type_def in &model.types {
+        result.push('\n');
+        result.push_str(&type_def_code(type_def));
+    }
+    result
+}
+
+fn type_def_code(type_def: &TypeDef) -> String {
+    let mut result = doc_comment(&type_def.desc, "");
+    match &type_def.kind {
+        TypeKind::Object(fields) if fields.is_empty() => {
+            result.push_str(&format!("export interface {} {{}}\n", type_def.name));
+        }
+        TypeKind::Object(fields) => {
+            result.push_str(&format!("export interface {} {{\n", type_def.name));
+            for field in fields {
+                result.push_str(&field_code(field));
+            }
+            result.push_str("}\n");
+        }
+        TypeKind::Alias(ty) => {
+            result.push_str(&format!(
+                "export type {} = {};\n",
+                type_def.name,
+                type_code(ty)
+            ));
+        }
+    }
+    result
+}
+
+fn field_code(field: &Field) -> String {
+    format!(
+        "{}  {}{}: {};\n",
+        doc_comment(&field.desc, "  "),
+        property_name(&field.name),
+        if field.required { "" } else { "?" },
+        type_code(&field.ty)
+    )
+}
+
+/// TypeScript中的类型
+pub fn type_code(ty: &TypeExpr) -> String {
+    match ty {
+        TypeExpr::String | TypeExpr::Date | TypeExpr::DateTime | TypeExpr::Uuid => {
+            "string".to_string()
+        }
+        TypeExpr::Int | TypeExpr::Float | TypeExpr::Timestamp => "number".to_string(),
+        TypeExpr::Bool => "boolean".to_string(),
+        TypeExpr::Any => "any".to_string(),
+        TypeExpr::Enum(values) => values
+            .iter()
+            .map(|v| match v {
+                Value::String(_) | Value::Number(_) | Value::Bool(_) | Value::Null => v.to_string(),
+                _ => "any".to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(" | "),
+        TypeExpr::Array(item) => match item.as_ref() {
+            TypeExpr::Union(_) | TypeExpr::Enum(_) => format!("({})[]", type_code(item)),
+            _ => format!("{}[]", type_code(item)),
+        },
+        TypeExpr::Union(items) => items
+            .iter()
+            .map(type_code)
+            .collect::<Vec<String>>()
+            .join(" | "),
+        TypeExpr::Map(value) => format!("Record<string, {}>", type_code(value)),
+        TypeExpr::Named(name) | TypeExpr::Rec(name) => name.to_string(),
+    }
+}
+
+/// 不是合法标识符的字段名加上引号
+fn property_name(name: &str) -> String {
+    let is_ident = name.chars().enumerate().all(|(i, c)| {
+        c == '_' || c == '$' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+    });
+    if is_ident && !name.is_empty() {
+        name.to_string()
+    } else {
+        Value::String(name.to_string()).to_string()
+    }
+}
+
+fn doc_comment(desc: &str, indent: &str) -> String {
+    let desc = desc.trim();
+    if desc.is_empty() {
+        return String::new();
+    }
+    let desc = desc.replace("*/", "*\\/");
+    let lines: Vec<&str> = desc.lines().collect();
+    if lines.len() == 1 {
+        return format!("{}/** {} */\n", indent, lines[0]);
+    }
+    let mut result = format!("{}/**\n", indent);
+    for line in lines {
+        result.push_str(format!("{} * {}", indent, line).trim_end());
+        result.push('\n');
+    }
+    result.push_str(&format!("{} */\n", indent));
+    result
+}
+
+const CLIENT_RUNTIME: &str = r#"import * as T from "./types";
+
+export const config = {
+  baseUrl: "",
+  headers: {} as Record<string, string>,
+};
+
+export class ApiError extends Error {
+  constructor(public status: number, public data: any) {
+    super(`request failed with status ${status}`);
+  }
+}
+
+function searchParams(data: object): URLSearchParams {
+  const params = new URLSearchParams();
+  for (const [key, value] of Object.entries(data)) {
+    if (value === undefined || value === null) {
+      continue;
+    }
+    for (const v of Array.isArray(value) ? value : [value]) {
+      params.append(key, String(v));
+    }
+  }
+  return params;
+}
+
+function buildQuery(query?: object): string {
+  if (!query) {
+    return "";
+  }
+  const s = searchParams(query).toString();
+  return s ? `?${s}` : "";
+}
+
+async function request<R>(method: string, url: string, query?: object, body?: any, bodyMode?: string): Promise<R> {
+  const headers: Record<string, string> = { ...config.headers };
+  const init: RequestInit = { method, headers };
+  // fetch does not allow a body in GET and HEAD requests
+  if (body !== undefined && method !== "GET" && method !== "HEAD") {
+    if (bodyMode === "form-data") {
+      const form = new FormData();
+      for (const [key, value] of Object.entries(body)) {
+        form.append(key, value instanceof Blob ? value : String(value));
+      }
+      init.body = form;
+    } else if (bodyMode === "urlencoded" || bodyMode === "x-www-form-urlencoded") {
+      init.body = searchParams(body);
+    } else {
+      headers["Content-Type"] = "application/json";
+      init.body = JSON.stringify(body);
+    }
+  }
+  const response = await fetch(config.baseUrl + url + buildQuery(query), init);
+  const text = await response.text();
+  const data = text ? JSON.parse(text) : undefined;
+  if (!response.ok) {
+    throw new ApiError(response.status, data);
+  }
+  return data as R;
+}
+"#;
+
+fn client_file(model: &CodeModel) -> String {
+    let mut result = HEADER.to_string();
+    result.push_str(CLIENT_RUNTIME);
+    for group in &model.groups {
+        result.push('\n');
+        let title = if group.title.is_empty() {
+            group.doc.to_string()
+        } else {
+            format!("{} ({})", group.title, group.doc)
+        };
+        result.push_str(&doc_comment(&title, ""));
+        result.push_str(&format!("export const {} = {{\n", group.name));
+        for api in &group.apis {
+            result.push_str(&api_fn_code(api));
+        }
+        result.push_str("};\n");
+    }
+    result
+}
+
+/// 请求函数, 参数是 {params, query, body}, 没有必填的部分时参数可以不传
+fn api_fn_code(api: &ApiFn) -> String {
+    let mut args = Vec::new();
+    let mut required = false;
+    for (key, part) in &[
+        ("params", &api.url_param),
+        ("query", &api.query),
+        ("body", &api.body),
+    ] {
+        if let Some(part) = part {
+            let optional = if part.required { "" } else { "?" };
+            args.push(format!("{}{}: T.{}", key, optional, part.type_name));
+            required = required || part.required;
+        }
+    }
+    let arg = if args.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "args{}: {{ {} }}",
+            if required { "" } else { "?" },
+            args.join("; ")
+        )
+    };
+
+    let response = match &api.response {
+        Some(name) => format!("T.{}", name),
+        None => "any".to_string(),
+    };
+    let args_ref = if required { "args" } else { "args?" };
+    let query = if api.query.is_some() {
+        format!("{}.query", args_ref)
+    } else {
+        "undefined".to_string()
+    };
+    let body = if api.body.is_some() {
+        format!("{}.body", args_ref)
+    } else {
+        "undefined".to_string()
+    };
+
+    let desc = if api.desc.trim().is_empty() || api.desc == api.title {
+        api.title.to_string()
+    } else {
+        format!("{}\n{}", api.title, api.desc)
+    };
+    format!(
+        "{}  {}: ({}) =>\n    request<{}>({}, {}, {}, {}, {}),\n",
+        doc_comment(&desc, "  "),
+        api.name,
+        arg,
+        response,
+        Value::String(api.method.to_string()),
+        url_code(&api.url, &api.url_params),
+        query,
+        body,
+        Value::String(api.body_mode.to_string())
+    )
+}
+
+/// 请求地址, url中的参数替换为args.params中的值
+fn url_code(url: &str, url_params: &[String]) -> String {
+    if url_params.is_empty() {
+        return Value::String(url.to_string()).to_string();
+    }
+    let mut result = String::from("`");
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&escape_template(&rest[..start]));
+        let param = rest[start + 1..end].split(':').next().unwrap_or("").trim();
+        result.push_str(&format!(
+            "${{encodeURIComponent(String(args.params[{}]))}}",
+            Value::String(param.to_string())
+        ));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(&escape_template(rest));
+    result.push('`');
+    result
+}
+
+fn escape_template(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
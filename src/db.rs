@@ -1,30 +1,47 @@
 use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 use ignore::Walk as WalkDir;
 use json5;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 
-#[derive(Debug)]
+use crate::graphql;
+use crate::jwt;
+use crate::router::Router;
+use crate::search::SearchIndex;
+use crate::version;
+
+/// 接口文档数据的存储
+/// 每次重新加载都生成一个新的不可变快照，然后原子替换，读取请求永远不会被重新加载阻塞
+pub type DataStore = ArcSwap<Database>;
+
+/// 权限规则中 * 代表的所有请求方法
+pub const ALL_METHODS: [&str; 7] = ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"];
+
+#[derive(Debug, Clone)]
 pub struct Database {
     pub basic_data: BasicData,
     pub api_docs: HashMap<String, ApiDoc>,
     // {fileanme:api_doc}
-    pub api_data: HashMap<String, Vec<Arc<Mutex<ApiData>>>>,
+    pub api_data: HashMap<String, Vec<Arc<ApiData>>>,
     // {url:[a_api_doc1, a_api_data2]}
     pub fileindex_data: HashMap<String, HashSet<String>>,
     // ref和相关文件的索引，当文件更新后，要找到所有ref他的地方，然后进行更新
-    pub websocket_api: Arc<Mutex<ApiData>>,
     pub auth_doc: Option<AuthDoc>,
     pub settings: Option<Value>,
     pub menus: HashMap<String, Menu>,
+    pub router: Router,
+    // 带参数url的路由表
+    pub search_index: SearchIndex,
+    // 全文搜索的索引
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct BasicData {
     pub read_me: String,
     pub project_name: String,
@@ -39,7 +56,11 @@ pub struct ApiDoc {
     pub desc: String,
     pub order: i64,
     pub filename: String,
-    pub apis: Vec<Arc<Mutex<ApiData>>>,
+    pub apis: Vec<Arc<ApiData>>,
+    pub version: String,
+    pub since: String,
+    pub deprecated: Value,
+    // 废弃信息 {desc, replacement, date, sunset}
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -68,6 +89,19 @@ pub struct ApiData {
     pub response_headers: Value,
     pub response: Value,
     pub test_data: Value,
+    pub push: Value,
+    // websocket接口服务器主动推送的消息
+    pub stream: Value,
+    // 流式返回的设置, sse或者ndjson
+    pub graphql: Value,
+    // graphql接口的schema和字段mock设置
+    #[serde(skip)]
+    pub graphql_schema: Option<Arc<graphql::Schema>>,
+    // 加载文档时解析好的schema, schema有错误时是None
+    pub version: String,
+    pub since: String,
+    pub deprecated: Value,
+    // 废弃信息 {desc, replacement, date, sunset}, 没有废弃时是Null
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -80,11 +114,36 @@ pub struct AuthDoc {
     pub auth_type: String,
     // auth 类型
     pub auth_place: String,
-    // auth 放在什么地方：headers 或者是 url上
+    // auth 放在什么地方：headers 或者是 url上, 显示在文档页面
+    pub token_places: Vec<TokenPlace>,
+    // 请求中可以存放token的位置
     pub filename: String,
     // 文件名称
     pub groups: Vec<AuthData>,
     pub no_perm_response: Value,
+    pub no_perm_status_code: u16,
+    // 没有访问权限时返回的状态码, 默认403
+    pub no_auth_response: Value,
+    pub no_auth_status_code: u16,
+    // 请求中没有token时的返回, 默认401
+    pub invalid_token_response: Value,
+    pub invalid_token_status_code: u16,
+    // token无效或者过期时的返回, 默认401
+    pub jwt: Option<jwt::JwtConfig>,
+    // auth_type 为 jwt 时的设置
+    pub oauth: Option<Value>,
+    // auth_type 为 oauth2 或者 oidc 时, 模拟的授权服务器设置
+}
+
+/// token在请求中存放的位置
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TokenPlace {
+    pub place: String,
+    // header, query 或者 cookie
+    pub name: String,
+    // header、query参数或者cookie的名称
+    pub prefix: String,
+    // token前面的前缀, 如 Bearer
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -95,6 +154,25 @@ pub struct AuthData {
     pub has_perms: HashMap<String, HashSet<String>>,
     pub no_perms: HashMap<String, HashSet<String>>,
     pub no_perm_response: Value,
+    pub no_perm_status_code: u16,
+    pub extends: Vec<String>,
+    // 继承的权限组, 权限已经合并到has_perms和no_perms中
+    pub has_perm_rules: Vec<ConditionalPerm>,
+    pub no_perm_rules: Vec<ConditionalPerm>,
+    // 带when条件的权限规则
+    pub has_perm_sources: HashMap<String, String>,
+    pub no_perm_sources: HashMap<String, String>,
+    // $ref展开的url对应的原始规则, 在权限矩阵中显示
+}
+
+/// 带条件的权限规则, when中的条件都满足时规则才生效
+/// 如 {url:"/users/{user_id}/", methods:["PUT"], when:{"$url_param/user_id":"$claims/uid"}}
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ConditionalPerm {
+    pub perms: HashMap<String, HashSet<String>>,
+    pub when: Map<String, Value>,
+    pub source: String,
+    // $ref展开的规则的原始内容
 }
 
 fn fix_json(org_string: String) -> String {
@@ -118,13 +196,13 @@ fn fix_json(org_string: String) -> String {
 }
 
 /// 加载auth认证的相关数据
-pub fn load_auth_data(api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
+pub fn load_auth_data(root: &Path, api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
     let auth_files = ["_auth.json5", "_auth.json"];
 
     let mut auth_value = json!({});
     let mut filename = "";
     for file in auth_files.iter() {
-        match fs::read_to_string(file) {
+        match fs::read_to_string(root.join(file)) {
             Ok(v) => {
                 let v = fix_json(v);
                 match json5::from_str(&v) {
@@ -164,15 +242,49 @@ pub fn load_auth_data(api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
         None => "Bearer",
     };
 
-    let auth_place = match obj.get("auth_place") {
-        Some(v) => v.as_str().unwrap(),
-        None => "headers",
-    };
+    let token_places = parse_token_places(obj.get("auth_place"));
+    let auth_place = token_places
+        .iter()
+        .map(|p| match p.place.as_str() {
+            "query" => format!("query ?{}={{token}}", p.name),
+            "cookie" => format!("cookie {}={{token}}", p.name),
+            _ => format!("header {}: {}{{token}}", p.name, p.prefix),
+        })
+        .collect::<Vec<String>>()
+        .join(" | ");
 
     let no_perm_response = match obj.get("no_perm_response") {
         Some(v) => v.clone(),
         None => json!({"code":-1, "error":"no perm to visit"}),
     };
+    let no_perm_status_code = get_status_code(obj.get("no_perm_status_code"), 403);
+    // 没有单独设置时, 和没有权限的返回相同
+    let no_auth_response = match obj.get("no_auth_response") {
+        Some(v) => v.clone(),
+        None => no_perm_response.clone(),
+    };
+    let no_auth_status_code = get_status_code(obj.get("no_auth_status_code"), 401);
+    let invalid_token_response = match obj.get("invalid_token_response") {
+        Some(v) => v.clone(),
+        None => no_perm_response.clone(),
+    };
+    let invalid_token_status_code = get_status_code(obj.get("invalid_token_status_code"), 401);
+
+    // jwt的用户通过登录接口获取token, 按username查找用户
+    // oauth2模拟授权服务器签发的也是jwt token
+    let auth_type_name = auth_type.to_lowercase();
+    let is_oauth = auth_type_name == "oauth2" || auth_type_name == "oidc";
+    let jwt = if auth_type_name == "jwt" || is_oauth {
+        Some(jwt::load_jwt_config(root, obj.get("jwt")))
+    } else {
+        None
+    };
+    let oauth = if is_oauth {
+        Some(obj.get("oauth").cloned().unwrap_or_else(|| json!({})))
+    } else {
+        None
+    };
+    let user_key = if jwt.is_some() { "username" } else { "token" };
 
     let mut groups: Vec<AuthData> = Vec::new();
 
@@ -193,7 +305,7 @@ pub fn load_auth_data(api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
                 if let Some(v) = data.get("users") {
                     if let Some(uu) = v.as_array() {
                         for user in uu {
-                            if let Some(t) = user.get("token") {
+                            if let Some(t) = user.get(user_key) {
                                 if let Some(token) = t.as_str() {
                                     users.insert(token.to_string(), user.clone());
                                 }
@@ -204,11 +316,25 @@ pub fn load_auth_data(api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
 
                 let has_perms = parse_auth_perms(data.get("has_perms"), api_docs);
                 let no_perms = parse_auth_perms(data.get("no_perms"), api_docs);
+                let has_perm_rules = parse_conditional_perms(data.get("has_perms"), api_docs);
+                let no_perm_rules = parse_conditional_perms(data.get("no_perms"), api_docs);
+                let has_perm_sources = parse_perm_sources(data.get("has_perms"), api_docs);
+                let no_perm_sources = parse_perm_sources(data.get("no_perms"), api_docs);
+                let extends = match data.get("extends") {
+                    Some(Value::String(v)) => vec![v.to_string()],
+                    Some(Value::Array(v)) => v
+                        .iter()
+                        .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                        .collect(),
+                    _ => Vec::new(),
+                };
 
                 let test_data_no_perm_response = match data.get("no_perm_response") {
                     Some(v) => v.clone(),
                     None => no_perm_response.clone(),
                 };
+                let test_data_no_perm_status_code =
+                    get_status_code(data.get("no_perm_status_code"), no_perm_status_code);
 
                 groups.push(AuthData {
                     name: test_data_name.to_string(),
@@ -217,44 +343,64 @@ pub fn load_auth_data(api_docs: &HashMap<String, ApiDoc>) -> Option<AuthDoc> {
                     has_perms: has_perms,
                     no_perms: no_perms,
                     no_perm_response: test_data_no_perm_response,
+                    no_perm_status_code: test_data_no_perm_status_code,
+                    extends,
+                    has_perm_rules,
+                    no_perm_rules,
+                    has_perm_sources,
+                    no_perm_sources,
                 })
             }
         }
     }
+    resolve_group_extends(&mut groups);
 
     Some(AuthDoc {
         name: name.to_string(),
         desc: desc.to_string(),
         auth_type: auth_type.to_string(),
-        auth_place: auth_place.to_string(),
+        auth_place,
+        token_places,
         filename: filename.to_string(),
         groups: groups,
         no_perm_response: no_perm_response,
+        no_perm_status_code,
+        no_auth_response,
+        no_auth_status_code,
+        invalid_token_response,
+        invalid_token_status_code,
+        jwt,
+        oauth,
     })
 }
 
-pub fn load_basic_data() -> (BasicData, Option<Value>) {
+/// 读取设置的http状态码, 没有设置或者不合法时使用默认值
+fn get_status_code(value: Option<&Value>, default: u16) -> u16 {
+    match value.and_then(|v| v.as_u64()) {
+        Some(v) if (100..600).contains(&v) => v as u16,
+        _ => default,
+    }
+}
+
+pub fn load_basic_data(root: &Path) -> (BasicData, Option<Value>) {
     let settings_files = ["_settings.json5", "_settings.json"];
 
     let mut setting_value = json!({});
     let mut return_value: Option<Value> = None;
     for settings_file in settings_files.iter() {
-        match fs::read_to_string(settings_file) {
-            Ok(v) => {
-                let v = fix_json(v);
-                match json5::from_str(&v) {
-                    Ok(v) => {
-                        setting_value = v;
-                        return_value = Some(setting_value.clone());
-                        break;
-                    }
-                    Err(e) => {
-                        println!("Parse json file {} error : {:?}", settings_file, e);
-                    }
+        if let Ok(v) = fs::read_to_string(root.join(settings_file)) {
+            let v = fix_json(v);
+            match json5::from_str(&v) {
+                Ok(v) => {
+                    setting_value = v;
+                    return_value = Some(setting_value.clone());
+                    break;
+                }
+                Err(e) => {
+                    println!("Parse json file {} error : {:?}", settings_file, e);
                 }
             }
-            Err(_) => (),
-        };
+        }
     }
 
     let obj = setting_value.as_object().unwrap();
@@ -271,7 +417,7 @@ pub fn load_basic_data() -> (BasicData, Option<Value>) {
     };
     let project_desc = project_desc.to_string();
 
-    let read_me = match fs::read_to_string("README.md") {
+    let read_me = match fs::read_to_string(root.join("README.md")) {
         Ok(x) => x,
         Err(_) => {
             if &project_desc == "" {
@@ -300,54 +446,60 @@ pub fn load_basic_data() -> (BasicData, Option<Value>) {
 
 impl Database {
     /// 加载api docs 接口的json数据、配置、相关文档
-    pub fn load() -> Database {
-        let (basic_data, settings) = load_basic_data();
+    /// root是接口文档项目的根目录，文档中的文件名都是相对于root的路径
+    pub fn load(root: &Path) -> Database {
+        let (basic_data, settings) = load_basic_data(root);
 
         let mut api_docs = HashMap::new();
-        let mut api_data: HashMap<String, Vec<Arc<Mutex<ApiData>>>> = HashMap::new();
+        let mut api_data: HashMap<String, Vec<Arc<ApiData>>> = HashMap::new();
         let mut fileindex_data: HashMap<String, HashSet<String>> = HashMap::new();
 
         let mut menus: HashMap<String, Menu> = HashMap::new();
 
-        let websocket_api = Arc::new(Mutex::new(ApiData::default()));
-
-        for entry in WalkDir::new("./") {
+        for entry in WalkDir::new(root) {
             let e = entry.unwrap();
-            let doc_file = e.path().to_str().unwrap().trim_start_matches("./");
-            if doc_file == "README.md" {
+            let doc_file = match e.path().strip_prefix(root) {
+                Ok(f) => f.to_str().unwrap(),
+                Err(_) => continue,
+            };
+            if doc_file == "README.md" || doc_file.is_empty() {
                 continue;
             }
 
             if doc_file.ends_with(".md") {
-                Self::load_a_md_doc(doc_file, &mut menus);
+                Self::load_a_md_doc(root, doc_file, &mut menus);
             } else if doc_file.ends_with(".json5") {
                 Self::load_a_api_json_file(
+                    root,
                     doc_file,
                     &basic_data,
                     &mut api_data,
                     &mut api_docs,
-                    websocket_api.clone(),
                     &mut fileindex_data,
                     &mut menus,
                 );
             }
         }
 
-        let auth_doc = load_auth_data(&api_docs);
-        Database {
+        let auth_doc = load_auth_data(root, &api_docs);
+        let router = Router::build(api_data.keys(), None);
+        let mut data = Database {
             basic_data,
+            router,
             api_data,
             api_docs,
             menus,
             fileindex_data,
-            websocket_api,
             auth_doc,
             settings,
-        }
+            search_index: SearchIndex::default(),
+        };
+        data.search_index = SearchIndex::build(&data, root);
+        data
     }
 
     /// 加载md文档
-    pub fn load_a_md_doc(doc_file: &str, mut menus: &mut HashMap<String, Menu>) {
+    pub fn load_a_md_doc(root: &Path, doc_file: &str, mut menus: &mut HashMap<String, Menu>) {
         let paths: Vec<&str> = doc_file.split("/").collect();
         let l = paths.len();
         let mut tmp_path = "".to_string();
@@ -380,6 +532,7 @@ impl Database {
                 if i + 1 == l {
                     filename = doc_file.to_string();
                     load_md_doc_config(
+                        root,
                         doc_file,
                         &mut order,
                         &mut menu_title,
@@ -389,6 +542,7 @@ impl Database {
                     );
                 } else {
                     load_folder_config(
+                        root,
                         &tmp_path,
                         &mut order,
                         &mut menu_title,
@@ -417,11 +571,11 @@ impl Database {
     /// 只加载一个api_doc文件的数据
     ///
     pub fn load_a_api_json_file(
+        root: &Path,
         doc_file: &str,
         basic_data: &BasicData,
-        api_data: &mut HashMap<String, Vec<Arc<Mutex<ApiData>>>>,
+        api_data: &mut HashMap<String, Vec<Arc<ApiData>>>,
         api_docs: &mut HashMap<String, ApiDoc>,
-        websocket_api: Arc<Mutex<ApiData>>,
         fileindex_data: &mut HashMap<String, HashSet<String>>,
         mut menus: &mut HashMap<String, Menu>,
     ) -> i32 {
@@ -435,7 +589,7 @@ impl Database {
             return -1;
         }
 
-        let d = match fs::read_to_string(Path::new(doc_file)) {
+        let d = match fs::read_to_string(root.join(doc_file)) {
             Ok(d) => {
                 if &d == "" {
                     return -2;
@@ -505,21 +659,26 @@ impl Database {
         };
 
         let api_vec = load_apis_from_api_doc(
+            root,
             apis,
             doc_file_obj,
             doc_file,
             fileindex_data,
             basic_data,
             api_data,
-            websocket_api.clone(),
         );
 
+        let doc_deprecated = version::parse_deprecated(doc_file_obj.get("deprecated"));
+        version::check_deprecated_date(doc_file, &doc_deprecated);
         let api_doc = ApiDoc {
             name: doc_name,
             desc: doc_desc.clone(),
             order: doc_order,
             filename: doc_file.to_string(),
             apis: api_vec,
+            version: version::get_version_string(doc_file_obj.get("version")),
+            since: version::get_version_string(doc_file_obj.get("since")),
+            deprecated: doc_deprecated,
         };
         api_docs.insert(doc_file.to_string(), api_doc);
 
@@ -564,6 +723,7 @@ impl Database {
                     menu_title = menu_title1;
 
                     load_folder_config(
+                        root,
                         &tmp_path,
                         &mut menu_order,
                         &mut menu_title,
@@ -589,18 +749,223 @@ impl Database {
         }
         1
     }
+
+    /// 移除一个接口文档文件之前加载的所有数据
+    /// 包括 api_docs, api_data中的url, fileindex_data中的索引和菜单
+    pub fn remove_api_doc_file(&mut self, root: &Path, doc_file: &str) {
+        if let Some(api_doc) = self.api_docs.remove(doc_file) {
+            for api in &api_doc.apis {
+                let url = api.url.clone();
+                let mut is_empty = false;
+                if let Some(url_apis) = self.api_data.get_mut(&url) {
+                    url_apis.retain(|x| !Arc::ptr_eq(x, api));
+                    is_empty = url_apis.is_empty();
+                }
+                if is_empty {
+                    self.api_data.remove(&url);
+                }
+            }
+        }
+
+        for doc_files in self.fileindex_data.values_mut() {
+            doc_files.remove(doc_file);
+        }
+        self.fileindex_data
+            .retain(|_, doc_files| !doc_files.is_empty());
+
+        remove_menu(root, &mut self.menus, doc_file);
+    }
+
+    /// 把单独加载的接口文档数据合并到当前数据中
+    pub fn merge_api_doc_data(
+        &mut self,
+        api_docs: HashMap<String, ApiDoc>,
+        api_data: HashMap<String, Vec<Arc<ApiData>>>,
+        fileindex_data: HashMap<String, HashSet<String>>,
+        menus: HashMap<String, Menu>,
+    ) {
+        for (url, apis) in api_data {
+            match self.api_data.get_mut(&url) {
+                Some(x) => x.extend(apis),
+                None => {
+                    self.api_data.insert(url, apis);
+                }
+            }
+        }
+
+        for (k, v) in api_docs {
+            self.api_docs.insert(k, v);
+        }
+
+        for (ref_file, doc_files) in fileindex_data {
+            if ref_file.is_empty() {
+                continue;
+            }
+            match self.fileindex_data.get_mut(&ref_file) {
+                Some(x) => x.extend(doc_files),
+                None => {
+                    self.fileindex_data.insert(ref_file, doc_files);
+                }
+            }
+        }
+
+        merge_menus(&mut self.menus, menus);
+    }
+
+    /// 目录的$_folder.md改动后，更新目录菜单的配置
+    pub fn reload_folder_menu(&mut self, root: &Path, folder_md_file: &str) {
+        let foldername = folder_md_file
+            .trim_end_matches("$_folder.md")
+            .trim_end_matches("/");
+        let menu_paths = get_menu_paths(foldername);
+
+        let is_empty = match find_menu_mut(&mut self.menus, &menu_paths) {
+            Some(menu) => {
+                let (mut order, mut menu_title) =
+                    get_order_and_title_from_filename(foldername, "md");
+                let mut desc = "".to_string();
+                let mut md_content = "".to_string();
+                let mut filename = "".to_string();
+                load_folder_config(
+                    root,
+                    foldername,
+                    &mut order,
+                    &mut menu_title,
+                    &mut desc,
+                    &mut md_content,
+                    &mut filename,
+                );
+                menu.order = order;
+                menu.name = menu_title;
+                menu.desc = desc;
+                menu.filename = filename;
+                menu.children.is_empty()
+            }
+            None => {
+                if root.join(folder_md_file).exists() {
+                    Self::load_a_md_doc(root, folder_md_file, &mut self.menus);
+                }
+                return;
+            }
+        };
+
+        if is_empty && !root.join(folder_md_file).exists() {
+            remove_menu(root, &mut self.menus, foldername);
+        }
+    }
+
+    /// 文件变化后更新这个文件的搜索索引
+    pub fn refresh_search_index(&mut self, root: &Path, doc_file: &str) {
+        let mut search_index = std::mem::take(&mut self.search_index);
+        search_index.update(self, root, doc_file);
+        self.search_index = search_index;
+    }
+
+    /// 接口url有变化后，重新生成路由表
+    pub fn refresh_router(&mut self) {
+        self.router = Router::build(self.api_data.keys(), Some(&self.router));
+    }
+
+    /// 找到请求地址对应的所有接口，先按url直接查找，找不到再通过路由表匹配带参数的url
+    pub fn find_api_data(&self, req_path: &str) -> Option<&Vec<Arc<ApiData>>> {
+        match self.api_data.get(req_path) {
+            Some(v) => Some(v),
+            None => match self.router.find(req_path) {
+                Some(api_url) => self.api_data.get(api_url),
+                None => None,
+            },
+        }
+    }
+}
+
+/// 从菜单树中移除一个文件对应的菜单
+/// 移除后如果目录下已经没有菜单了，并且目录没有$_folder.md，那么目录菜单也一起移除
+pub fn remove_menu(root: &Path, menus: &mut HashMap<String, Menu>, doc_file: &str) {
+    let menu_paths = get_menu_paths(doc_file);
+    remove_menu_by_paths(root, menus, &menu_paths);
+}
+
+/// 文件路径对应的每一级菜单的key, a/b/c.md => [a, a/b, a/b/c.md]
+fn get_menu_paths(doc_file: &str) -> Vec<String> {
+    let mut menu_paths: Vec<String> = Vec::new();
+    for path in doc_file.split("/") {
+        match menu_paths.last() {
+            Some(p) => menu_paths.push(format!("{}/{}", p, path)),
+            None => menu_paths.push(path.to_string()),
+        }
+    }
+    menu_paths
+}
+
+fn find_menu_mut<'a>(
+    menus: &'a mut HashMap<String, Menu>,
+    menu_paths: &[String],
+) -> Option<&'a mut Menu> {
+    let menu = menus.get_mut(menu_paths.first()?)?;
+    if menu_paths.len() == 1 {
+        return Some(menu);
+    }
+    find_menu_mut(&mut menu.children, &menu_paths[1..])
+}
+
+fn remove_menu_by_paths(root: &Path, menus: &mut HashMap<String, Menu>, menu_paths: &[String]) {
+    let menu_path = match menu_paths.first() {
+        Some(v) => v,
+        None => return,
+    };
+
+    if menu_paths.len() == 1 {
+        menus.remove(menu_path);
+        return;
+    }
+
+    let mut is_empty = false;
+    if let Some(menu) = menus.get_mut(menu_path) {
+        remove_menu_by_paths(root, &mut menu.children, &menu_paths[1..]);
+        is_empty =
+            menu.children.is_empty() && !root.join(format!("{}/$_folder.md", menu_path)).exists();
+    }
+    if is_empty {
+        menus.remove(menu_path);
+    }
+}
+
+/// 把新加载的菜单合并到菜单树中, 已存在的目录只合并子菜单，文件菜单直接替换
+fn merge_menus(menus: &mut HashMap<String, Menu>, new_menus: HashMap<String, Menu>) {
+    for (k, v) in new_menus {
+        match menus.get_mut(&k) {
+            Some(menu) if !v.children.is_empty() => {
+                merge_menus(&mut menu.children, v.children);
+            }
+            _ => {
+                menus.insert(k, v);
+            }
+        }
+    }
+}
+
+/// 获取json5文件的解析错误信息，能正常解析返回None
+pub fn get_json_file_error(root: &Path, doc_file: &str) -> Option<String> {
+    let d = match fs::read_to_string(root.join(doc_file)) {
+        Ok(d) => fix_json(d),
+        Err(e) => return Some(format!("{}", e)),
+    };
+    match json5::from_str::<Value>(&d) {
+        Ok(_) => None,
+        Err(e) => Some(format!("{}", e)),
+    }
 }
 
 /// 把接口文档的所有接口加载到一个Vec中
 fn load_apis_from_api_doc(
+    root: &Path,
     apis: Value,
     doc_file_obj: &Map<String, Value>,
     doc_file: &str,
     fileindex_data: &mut HashMap<String, HashSet<String>>,
     basic_data: &BasicData,
-    api_data: &mut HashMap<String, Vec<Arc<Mutex<ApiData>>>>,
-    websocket_api: Arc<Mutex<ApiData>>,
-) -> Vec<Arc<Mutex<ApiData>>> {
+    api_data: &mut HashMap<String, Vec<Arc<ApiData>>>,
+) -> Vec<Arc<ApiData>> {
     let mut api_vec = Vec::new();
     if let Some(api_array) = apis.as_array() {
         for api in api_array {
@@ -609,7 +974,7 @@ fn load_apis_from_api_doc(
             if let Some(ref_file_path_v) = api.get("$ref") {
                 // 处理api $ref加载数据
                 if let Some(ref_file_path) = ref_file_path_v.as_str() {
-                    let (ref_file, ref_value) = load_ref_file_data(ref_file_path, doc_file);
+                    let (ref_file, ref_value) = load_ref_file_data(root, ref_file_path, doc_file);
                     if &ref_file != "" {
                         match fileindex_data.get_mut(&ref_file) {
                             Some(x) => {
@@ -625,7 +990,7 @@ fn load_apis_from_api_doc(
 
                     if let Some(value) = ref_value {
                         let (mut ref_files2, value) =
-                            parse_attribute_ref_value(value, doc_file_obj, doc_file);
+                            parse_attribute_ref_value(root, value, doc_file_obj, doc_file);
                         ref_files.append(&mut ref_files2);
                         ref_data = value;
                     }
@@ -685,6 +1050,28 @@ fn load_apis_from_api_doc(
             let auth =
                 get_api_field_bool_value("auth", false, api, &ref_data, &basic_data.global_value);
 
+            // 接口没有设置版本和废弃信息的时候使用文档的设置
+            let lifecycle_value = |key| {
+                version::get_lifecycle_value(
+                    key,
+                    api,
+                    &ref_data,
+                    doc_file_obj,
+                    &basic_data.global_value,
+                )
+            };
+            let api_version = version::get_version_string(lifecycle_value("version"));
+            let since = version::get_version_string(lifecycle_value("since"));
+            let deprecated = version::parse_deprecated(lifecycle_value("deprecated"));
+            // 文档的废弃信息在加载文档时检查, 这里只检查接口自己设置的
+            if api
+                .get("deprecated")
+                .or_else(|| ref_data.get("deprecated"))
+                .is_some()
+            {
+                version::check_deprecated_date(&format!("{} {}", doc_file, url), &deprecated);
+            }
+
             let url_param = match api.get("url_param") {
                 Some(url_param) => url_param.clone(),
                 None => match ref_data.get("url_param") {
@@ -693,7 +1080,7 @@ fn load_apis_from_api_doc(
                 },
             };
             let (mut ref_files2, url_param) =
-                parse_attribute_ref_value(url_param, doc_file_obj, doc_file);
+                parse_attribute_ref_value(root, url_param, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
 
             let body = match api.get("body") {
@@ -703,7 +1090,8 @@ fn load_apis_from_api_doc(
                     None => Value::Null,
                 },
             };
-            let (mut ref_files2, body) = parse_attribute_ref_value(body, doc_file_obj, doc_file);
+            let (mut ref_files2, body) =
+                parse_attribute_ref_value(root, body, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
 
             let request_headers = match api.get("request_headers") {
@@ -714,7 +1102,7 @@ fn load_apis_from_api_doc(
                 },
             };
             let (mut ref_files2, request_headers) =
-                parse_attribute_ref_value(request_headers, doc_file_obj, doc_file);
+                parse_attribute_ref_value(root, request_headers, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
 
             let response_headers = match api.get("response_headers") {
@@ -725,7 +1113,7 @@ fn load_apis_from_api_doc(
                 },
             };
             let (mut ref_files2, response_headers) =
-                parse_attribute_ref_value(response_headers, doc_file_obj, doc_file);
+                parse_attribute_ref_value(root, response_headers, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
 
             let query = match api.get("query") {
@@ -736,7 +1124,8 @@ fn load_apis_from_api_doc(
                 },
             };
 
-            let (mut ref_files2, query) = parse_attribute_ref_value(query, doc_file_obj, doc_file);
+            let (mut ref_files2, query) =
+                parse_attribute_ref_value(root, query, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
 
             // 最后查询global_value
@@ -768,13 +1157,68 @@ fn load_apis_from_api_doc(
 
             // 处理response中的$ref
             let (mut ref_files2, mut response) =
-                parse_attribute_ref_value(Value::Object(response), doc_file_obj, doc_file);
+                parse_attribute_ref_value(root, Value::Object(response), doc_file_obj, doc_file);
 
             if is_special_private {
                 response = response.pointer("/$_special_private").unwrap().clone();
             }
 
             ref_files.append(&mut ref_files2);
+
+            // websocket接口服务器主动推送的消息，每个消息的response可以$ref模型
+            let mut push = Value::Null;
+            if let Some(push_list) = api.get("push") {
+                match push_list.as_array() {
+                    Some(push_list) => {
+                        let mut new_push_list = Vec::new();
+                        for item in push_list {
+                            let mut item = item.clone();
+                            if let Some(r) = item.get("response") {
+                                let (mut ref_files2, r) = parse_attribute_ref_value(
+                                    root,
+                                    r.clone(),
+                                    doc_file_obj,
+                                    doc_file,
+                                );
+                                ref_files.append(&mut ref_files2);
+                                item["response"] = r;
+                            }
+                            new_push_list.push(item);
+                        }
+                        push = Value::Array(new_push_list);
+                    }
+                    None => log::error!("push need a array"),
+                }
+            }
+
+            // 流式返回, stream:true 或者 {mode, interval, count, loop, events}
+            let stream = match api.get("stream") {
+                Some(v) if v.is_object() || v.is_boolean() => v.clone(),
+                Some(_) => {
+                    log::error!("stream need a object");
+                    Value::Null
+                }
+                None => Value::Null,
+            };
+
+            // graphql接口, schema可以直接写SDL, 也可以引用_data中的.graphql文件
+            let mut graphql = Value::Null;
+            let mut graphql_schema = None;
+            if method.contains(&"GRAPHQL".to_string()) {
+                let (schema_file, schema) = load_graphql_schema(root, api, doc_file);
+                if !schema_file.is_empty() {
+                    ref_files.push(schema_file);
+                }
+                match graphql::parse_schema(&schema) {
+                    Ok(s) => graphql_schema = Some(Arc::new(s)),
+                    Err(e) => log::error!("graphql schema of {} error: {}", url, e),
+                }
+                graphql = json!({
+                    "schema": schema,
+                    "mock": api.get("mock").cloned().unwrap_or(Value::Null)
+                });
+            }
+
             for ref_file in ref_files {
                 if &ref_file != "" {
                     match fileindex_data.get_mut(&ref_file) {
@@ -809,20 +1253,21 @@ fn load_apis_from_api_doc(
                 query,
                 response,
                 test_data,
+                push,
+                stream,
+                graphql,
+                graphql_schema,
                 url_param,
                 request_headers,
                 response_headers,
                 auth: auth,
                 url: url.clone(),
                 method: method.clone(),
+                version: api_version,
+                since,
+                deprecated,
             };
-            let a_api_data = Arc::new(Mutex::new(o_api_data.clone()));
-
-            if method.contains(&"WEBSOCKET".to_string()) {
-                // 如果method是websocket,表面有websocket接口， 那么就把websocket接口更新配置到websocket配置
-                let mut websocket_api = websocket_api.lock().unwrap();
-                *websocket_api = o_api_data.clone();
-            }
+            let a_api_data = Arc::new(o_api_data);
             // 形成 { url: {method:api} }
             match api_data.get_mut(&url) {
                 Some(data) => {
@@ -861,6 +1306,7 @@ pub fn get_order_and_title_from_filename(doc_file: &str, file_type: &str) -> (i3
 /// 加载md文档中文件头的config内容,
 /// 以```{开头```}结尾
 pub fn load_md_doc_config(
+    root: &Path,
     doc_file: &str,
     order: &mut i32,
     menu_title: &mut String,
@@ -868,7 +1314,7 @@ pub fn load_md_doc_config(
     md_content: &mut String,
     filename: &mut String,
 ) {
-    if let Ok(content) = fs::read_to_string(Path::new(doc_file)) {
+    if let Ok(content) = fs::read_to_string(root.join(doc_file)) {
         *md_content = content.clone();
         // 获取md文档顶部的配置信息
         let re = Regex::new(r"^\s*(```)?\s*(\{[\s\S]*?\})\s*(```)\s*").unwrap();
@@ -921,6 +1367,7 @@ pub fn load_md_doc_config(
 
 /// 加载目录的菜单配置文件
 fn load_folder_config(
+    root: &Path,
     foldername: &str,
     order: &mut i32,
     menu_title: &mut String,
@@ -930,6 +1377,7 @@ fn load_folder_config(
 ) {
     let folder_md_doc = format!("{0}/$_folder.md", foldername);
     load_md_doc_config(
+        root,
         &folder_md_doc,
         order,
         menu_title,
@@ -939,8 +1387,34 @@ fn load_folder_config(
     )
 }
 
+/// 加载graphql接口的schema, 返回引用的schema文件和schema内容
+fn load_graphql_schema(root: &Path, api: &Value, doc_file: &str) -> (String, String) {
+    let schema = api.get("schema").and_then(|v| v.as_str()).unwrap_or("");
+    if !schema.trim_end().ends_with(".graphql") {
+        return ("".to_string(), schema.to_string());
+    }
+
+    let filename = schema.trim();
+    let file_path = if filename.starts_with("./") {
+        let path = Path::new(doc_file).parent().unwrap();
+        path.join(filename.trim_start_matches("./"))
+            .to_str()
+            .unwrap()
+            .to_string()
+    } else {
+        filename.trim_start_matches('/').to_string()
+    };
+    match fs::read_to_string(root.join(&file_path)) {
+        Ok(d) => (file_path, d),
+        Err(_) => {
+            println!("file {} not found", &file_path);
+            (file_path, "".to_string())
+        }
+    }
+}
+
 /// 加载ref对应文件的数据
-fn load_ref_file_data(ref_file: &str, doc_file: &str) -> (String, Option<Value>) {
+fn load_ref_file_data(root: &Path, ref_file: &str, doc_file: &str) -> (String, Option<Value>) {
     let ref_info: Vec<&str> = ref_file.split(":").collect();
 
     match ref_info.get(0) {
@@ -960,7 +1434,7 @@ fn load_ref_file_data(ref_file: &str, doc_file: &str) -> (String, Option<Value>)
             }
             file_path = file_path.trim_start_matches("/").to_string();
             // 加载数据文件
-            if let Ok(d) = fs::read_to_string(Path::new(&file_path)) {
+            if let Ok(d) = fs::read_to_string(root.join(&file_path)) {
                 let d = fix_json(d);
                 let data: Value = match json5::from_str(&d) {
                     Ok(v) => v,
@@ -1152,6 +1626,7 @@ fn get_api_field_bool_value(
 /// 第一个参数表示获取到的值，body, query, resonse 等, 判断是否有引用值 或者 全局值
 /// 对不满足要求的数据会全部进行过滤
 fn parse_attribute_ref_value(
+    root: &Path,
     value: Value,
     doc_file_obj: &Map<String, Value>,
     doc_file: &str,
@@ -1176,8 +1651,14 @@ fn parse_attribute_ref_value(
         // 如果是递归，就不进行文件的引入操作，递归的文件引入在生成mock数据时才进行引入
         if !is_rec {
             // 处理文件引入
-            new_value =
-                load_a_ref_value(new_value, &mut ref_files, value_obj, doc_file_obj, doc_file);
+            new_value = load_a_ref_value(
+                root,
+                new_value,
+                &mut ref_files,
+                value_obj,
+                doc_file_obj,
+                doc_file,
+            );
         }
 
         for (field_key, field_attrs) in value_obj {
@@ -1210,7 +1691,7 @@ fn parse_attribute_ref_value(
 
             // 处理属性中的value
             let (mut ref_files2, field_value) =
-                parse_attribute_ref_value(field_attrs.clone(), doc_file_obj, doc_file);
+                parse_attribute_ref_value(root, field_attrs.clone(), doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
             //            new_value.insert(field_key.trim_start_matches("$").to_string(), field_value);
             new_value.insert(field_key.to_string(), field_value);
@@ -1235,8 +1716,12 @@ fn parse_attribute_ref_value(
         if let Some(value_array) = value.as_array() {
             if value_array.len() == 1 {
                 if let Some(value_array_one) = value_array.get(0) {
-                    let (ref_files, array_item_value) =
-                        parse_attribute_ref_value(value_array_one.clone(), doc_file_obj, doc_file);
+                    let (ref_files, array_item_value) = parse_attribute_ref_value(
+                        root,
+                        value_array_one.clone(),
+                        doc_file_obj,
+                        doc_file,
+                    );
                     return (ref_files, Value::Array(vec![array_item_value]));
                 } else {
                     println!(" file array value empty '{}' got {:?}", doc_file, value);
@@ -1252,6 +1737,7 @@ fn parse_attribute_ref_value(
 
 /// 加载某个$ref 路径的数据出来
 fn load_a_ref_value(
+    root: &Path,
     mut new_value: Map<String, Value>,
     ref_files: &mut Vec<String>,
     value_obj: &Map<String, Value>,
@@ -1287,11 +1773,11 @@ fn load_a_ref_value(
             v_str = new_v_str.as_str();
         }
         // 处理response, body里面的ref
-        let (ref_file, ref_data) = load_ref_file_data(v_str, doc_file);
+        let (ref_file, ref_data) = load_ref_file_data(root, v_str, doc_file);
         ref_files.push(ref_file);
         let mut has_include = false;
         if let Some(vv) = ref_data {
-            let (mut ref_files2, mut vv) = parse_attribute_ref_value(vv, doc_file_obj, doc_file);
+            let (mut ref_files2, vv) = parse_attribute_ref_value(root, vv, doc_file_obj, doc_file);
             ref_files.append(&mut ref_files2);
             new_value = match vv.as_object() {
                 Some(ref_data_map) => {
@@ -1346,14 +1832,13 @@ fn load_all_api_docs_url(
     exclude: &HashMap<String, HashSet<String>>,
 ) {
     let mut all_methods: HashSet<String> = HashSet::with_capacity(7);
-    for v in &["POST", "GET", "PUT", "DELETE", "OPTIONS", "HEAD", "PATCH"] {
+    for v in &ALL_METHODS {
         all_methods.insert(v.to_string());
     }
 
     let doc_file = doc_file.trim_start_matches("$");
     if let Some(api_doc) = api_docs.get(doc_file) {
-        for a in &api_doc.apis {
-            let api = a.lock().unwrap();
+        for api in &api_doc.apis {
             // 如果exclude 排除这个url，并且排除所有方法，那么就没有任何这个url的权限
             if let Some(exclude_methods) = exclude.get(&api.url) {
                 if exclude_methods.is_empty() {
@@ -1392,6 +1877,63 @@ fn load_all_api_docs_url(
     }
 }
 
+/// 解析auth_place的一个或者多个位置, 如 "headers", "query:token" 或者 {place, name, prefix}
+fn parse_token_places(value: Option<&Value>) -> Vec<TokenPlace> {
+    let items = match value {
+        Some(Value::Array(items)) => items.clone(),
+        Some(v) => vec![v.clone()],
+        None => vec![json!("headers")],
+    };
+
+    let mut token_places = Vec::new();
+    for item in items {
+        let (place, name, prefix) = match &item {
+            Value::String(s) => {
+                let mut parts = s.splitn(2, ':');
+                let place = parts.next().unwrap_or("").trim().to_string();
+                let name = parts.next().map(|v| v.trim().to_string());
+                (place, name, None)
+            }
+            Value::Object(obj) => {
+                let get = |key| obj.get(key).and_then(|v| v.as_str()).map(|v| v.to_string());
+                (get("place").unwrap_or_default(), get("name"), get("prefix"))
+            }
+            _ => continue,
+        };
+        let place = match place.to_lowercase().as_str() {
+            "header" | "headers" => "header",
+            "query" | "url" => "query",
+            "cookie" | "cookies" => "cookie",
+            _ => {
+                log::error!("auth_place {} is not supported", place);
+                continue;
+            }
+        };
+        let name = name.unwrap_or_else(|| {
+            match place {
+                "query" => "access_token",
+                "cookie" => "session",
+                _ => "Authorization",
+            }
+            .to_string()
+        });
+        // 只有Authorization默认使用Bearer前缀
+        let prefix = prefix.unwrap_or_else(|| {
+            if name.to_lowercase() == "authorization" {
+                "Bearer ".to_string()
+            } else {
+                "".to_string()
+            }
+        });
+        token_places.push(TokenPlace {
+            place: place.to_string(),
+            name,
+            prefix,
+        });
+    }
+    token_places
+}
+
 /// 把权限解析为一个map
 fn parse_auth_perms(
     perms_data: Option<&Value>,
@@ -1426,6 +1968,10 @@ fn parse_auth_perms(
                         }
                     }
                     Value::Object(perm_obj) => {
+                        if perm_obj.contains_key("when") {
+                            // 带条件的规则单独解析
+                            continue;
+                        }
                         exclude = parse_auth_perms(perm_obj.get("$exclude"), api_docs);
                         if let Some(m) = perm_obj.get("methods") {
                             if m.is_string() {
@@ -1491,6 +2037,193 @@ fn parse_auth_perms(
     result
 }
 
+/// 解析权限中带when条件的规则
+fn parse_conditional_perms(
+    perms_data: Option<&Value>,
+    api_docs: &HashMap<String, ApiDoc>,
+) -> Vec<ConditionalPerm> {
+    let mut result = Vec::new();
+    if let Some(Value::Array(perms)) = perms_data {
+        for perm in perms {
+            let mut perm_obj = match perm.as_object() {
+                Some(v) => v.clone(),
+                None => continue,
+            };
+            let when = match perm_obj.remove("when") {
+                Some(Value::Object(when)) => when,
+                _ => continue,
+            };
+            // 去掉条件后就是一个普通的权限规则
+            let rule = Value::Object(perm_obj);
+            let perms = parse_auth_perms(Some(&json!([rule])), api_docs);
+            let source = if is_ref_perm(&rule) {
+                rule.to_string()
+            } else {
+                String::new()
+            };
+            result.push(ConditionalPerm {
+                perms,
+                when,
+                source,
+            });
+        }
+    }
+    result
+}
+
+/// 是否是按$ref或者$开头的接口文件展开的规则
+fn is_ref_perm(perm: &Value) -> bool {
+    let is_ref = |v: Option<&Value>| match v.and_then(|v| v.as_str()) {
+        Some(s) => s.starts_with('$'),
+        None => false,
+    };
+    match perm {
+        Value::String(s) => s.starts_with('$'),
+        Value::Array(v) => is_ref(v.first()),
+        Value::Object(v) => v.contains_key("$ref") || is_ref(v.get("url")),
+        _ => false,
+    }
+}
+
+/// 展开的权限url对应的原始规则, 如 {"$ref":"$auth.json5","$exclude":["/login/"]}
+fn parse_perm_sources(
+    perms_data: Option<&Value>,
+    api_docs: &HashMap<String, ApiDoc>,
+) -> HashMap<String, String> {
+    let perms = match perms_data {
+        Some(Value::Array(perms)) => perms.clone(),
+        Some(v) => vec![v.clone()],
+        None => Vec::new(),
+    };
+    let mut result = HashMap::new();
+    for perm in perms.iter().filter(|p| is_ref_perm(p)) {
+        for url in parse_auth_perms(Some(&json!([perm])), api_docs).keys() {
+            result
+                .entry(url.to_string())
+                .or_insert_with(|| perm.to_string());
+        }
+    }
+    result
+}
+
+/// 合并权限, 同一个url的请求方法合并在一起
+fn merge_perms(
+    target: &mut HashMap<String, HashSet<String>>,
+    source: &HashMap<String, HashSet<String>>,
+) {
+    for (url, methods) in source {
+        let target_methods = target
+            .entry(url.to_string())
+            .or_insert_with(|| methods.clone());
+        let is_all = |m: &HashSet<String>| m.is_empty() || m.contains("*");
+        if is_all(target_methods) || is_all(methods) {
+            target_methods.clear();
+            target_methods.insert("*".to_string());
+        } else {
+            target_methods.extend(methods.iter().cloned());
+        }
+    }
+}
+
+/// 从禁止的权限中去掉授予的请求方法, 同一个url的方法都被授予时去掉这个url
+fn subtract_perms(
+    deny: &HashMap<String, HashSet<String>>,
+    grant: &HashMap<String, HashSet<String>>,
+) -> HashMap<String, HashSet<String>> {
+    let is_all = |m: &HashSet<String>| m.is_empty() || m.contains("*");
+    let mut result = HashMap::new();
+    for (url, methods) in deny {
+        let granted = match grant.get(url) {
+            Some(granted) => granted,
+            None => {
+                result.insert(url.to_string(), methods.clone());
+                continue;
+            }
+        };
+        if is_all(granted) {
+            continue;
+        }
+        let methods: HashSet<String> = if is_all(methods) {
+            ALL_METHODS.iter().map(|m| m.to_string()).collect()
+        } else {
+            methods.clone()
+        };
+        let methods: HashSet<String> = methods.difference(granted).cloned().collect();
+        if !methods.is_empty() {
+            result.insert(url.to_string(), methods);
+        }
+    }
+    result
+}
+
+/// 把extends的权限组的权限合并进来, 支持多级继承, 循环继承的组只合并一次
+/// 父组的no_perms也继承, 只去掉子组自己的has_perms重新授予的部分
+fn resolve_group_extends(groups: &mut [AuthData]) {
+    let own_groups = groups.to_vec();
+    for group in groups.iter_mut() {
+        let mut visited = HashSet::new();
+        *group = effective_group(group, &own_groups, &mut visited);
+    }
+}
+
+/// 权限组合并了继承的权限之后实际的权限
+fn effective_group(
+    group: &AuthData,
+    own_groups: &[AuthData],
+    visited: &mut HashSet<String>,
+) -> AuthData {
+    visited.insert(group.name.clone());
+    let mut result = group.clone();
+    for parent_name in &group.extends {
+        if visited.contains(parent_name) {
+            continue;
+        }
+        let parent = match own_groups.iter().find(|g| &g.name == parent_name) {
+            Some(g) => effective_group(g, own_groups, visited),
+            None => {
+                log::warn!(
+                    "group {} extends a missing group {}",
+                    group.name,
+                    parent_name
+                );
+                continue;
+            }
+        };
+
+        merge_perms(&mut result.has_perms, &parent.has_perms);
+        result
+            .has_perm_rules
+            .extend(parent.has_perm_rules.iter().cloned());
+        let no_perms = subtract_perms(&parent.no_perms, &group.has_perms);
+        merge_perms(&mut result.no_perms, &no_perms);
+        for rule in &parent.no_perm_rules {
+            let perms = subtract_perms(&rule.perms, &group.has_perms);
+            if !perms.is_empty() {
+                result.no_perm_rules.push(ConditionalPerm {
+                    perms,
+                    ..rule.clone()
+                });
+            }
+        }
+
+        for (url, source) in &parent.has_perm_sources {
+            result
+                .has_perm_sources
+                .entry(url.to_string())
+                .or_insert_with(|| source.to_string());
+        }
+        for (url, source) in &parent.no_perm_sources {
+            if no_perms.contains_key(url) {
+                result
+                    .no_perm_sources
+                    .entry(url.to_string())
+                    .or_insert_with(|| source.to_string());
+            }
+        }
+    }
+    result
+}
+
 fn parse_index(s: &str) -> Option<usize> {
     if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
         return None;
@@ -1607,6 +2340,10 @@ pub fn get_field_type(field_attr: &Value) -> String {
                 }
             }
             for (k, v) in field_attr_object {
+                if k == "deprecated" && version::is_lifecycle_deprecated(v) {
+                    // 字段的废弃信息不是子字段
+                    continue;
+                }
                 if v.is_object() || v.is_array() {
                     return "object".to_lowercase();
                 }
@@ -1616,10 +2353,103 @@ pub fn get_field_type(field_attr: &Value) -> String {
     return "string".to_lowercase();
 }
 
+/// 字段嵌套的最大层数
+const MAX_FIELD_DEPTH: usize = 16;
+
+/// 遍历模型中的字段, 参数是字段路径(如 data/refund_amount)和字段定义, 只进入对象和数组类型的字段
+pub fn walk_fields(model: &Value, f: &mut dyn FnMut(&str, &Value)) {
+    walk_fields_in("", model, f, 0);
+}
+
+fn walk_fields_in(prefix: &str, model: &Value, f: &mut dyn FnMut(&str, &Value), depth: usize) {
+    if depth > MAX_FIELD_DEPTH {
+        return;
+    }
+    match model {
+        Value::Array(items) => {
+            for item in items {
+                walk_fields_in(prefix, item, f, depth + 1);
+            }
+        }
+        Value::Object(obj) => {
+            for (key, attr) in obj {
+                if key.starts_with('$') || !(attr.is_object() || attr.is_array()) {
+                    continue;
+                }
+                if attr.get("$del").and_then(|v| v.as_bool()) == Some(true) {
+                    continue;
+                }
+                let path = if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}/{}", prefix, key)
+                };
+                f(&path, attr);
+                let field_type = get_field_type(attr);
+                if field_type == "object" || field_type == "array" {
+                    walk_fields_in(&path, attr, f, depth + 1);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn get_json_file_error_test() {
+        let root = std::env::temp_dir().join(format!("panda-json-error-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("ok.json5"), "{name: 'ok', apis: [],}").unwrap();
+        fs::write(root.join("bad.json5"), "{name: 'bad', apis: [}").unwrap();
+        assert_eq!(None, get_json_file_error(&root, "ok.json5"));
+        assert!(get_json_file_error(&root, "bad.json5").is_some());
+        assert!(get_json_file_error(&root, "missing.json5").is_some());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn parse_perm_sources_test() {
+        let api = |url: &str| {
+            Arc::new(ApiData {
+                url: url.to_string(),
+                method: vec!["GET".to_string()],
+                ..Default::default()
+            })
+        };
+        let mut api_docs = HashMap::new();
+        api_docs.insert(
+            "auth.json5".to_string(),
+            ApiDoc {
+                name: "Auth".to_string(),
+                desc: String::new(),
+                order: 0,
+                filename: "auth.json5".to_string(),
+                apis: vec![api("/login/"), api("/logout/")],
+                version: String::new(),
+                since: String::new(),
+                deprecated: Value::Null,
+            },
+        );
+        let perms = json!([
+            "/me/",
+            {"$ref": "$auth.json5", "$exclude": ["/login/"]},
+            {"$ref": "$auth.json5", "when": {"$query/a": "1"}}
+        ]);
+        let sources = parse_perm_sources(Some(&perms), &api_docs);
+        assert_eq!(1, sources.len());
+        assert_eq!(
+            r#"{"$exclude":["/login/"],"$ref":"$auth.json5"}"#,
+            sources["/logout/"]
+        );
+
+        let rules = parse_conditional_perms(Some(&perms), &api_docs);
+        assert_eq!(r#"{"$ref":"$auth.json5"}"#, rules[0].source);
+    }
+
     #[test]
     fn get_field_type_test() {
         let data = json!({
@@ -1633,5 +2463,172 @@ mod test {
 
         let data = json!([{"name":"ID", "enum":[1,2,3]}]);
         assert_eq!("array", get_field_type(&data));
+
+        // 废弃信息不是子字段, 叫deprecated的子字段还是子字段
+        let data = json!({"deprecated":{"desc":"use tags", "date":"2026-01-01"}});
+        assert_eq!("string", get_field_type(&data));
+        let data = json!({"deprecated":{"type":"bool"}});
+        assert_eq!("object", get_field_type(&data));
+    }
+
+    #[test]
+    fn remove_api_doc_file_test() {
+        let mut api_docs = HashMap::new();
+        let mut api_data: HashMap<String, Vec<Arc<ApiData>>> = HashMap::new();
+        let mut menus = HashMap::new();
+        for doc_file in &["order/a.json5", "order/b.json5"] {
+            let api = Arc::new(ApiData {
+                url: "/order/".to_string(),
+                ..Default::default()
+            });
+            api_data
+                .entry("/order/".to_string())
+                .or_default()
+                .push(api.clone());
+            api_docs.insert(
+                doc_file.to_string(),
+                ApiDoc {
+                    name: doc_file.to_string(),
+                    desc: "".to_string(),
+                    order: 0,
+                    filename: doc_file.to_string(),
+                    apis: vec![api],
+                    version: "".to_string(),
+                    since: "".to_string(),
+                    deprecated: Value::Null,
+                },
+            );
+            let mut children = HashMap::new();
+            children.insert(doc_file.to_string(), Menu::default());
+            let mut new_menus = HashMap::new();
+            new_menus.insert(
+                "order".to_string(),
+                Menu {
+                    children,
+                    ..Default::default()
+                },
+            );
+            merge_menus(&mut menus, new_menus);
+        }
+        let mut fileindex_data = HashMap::new();
+        let mut ref_docs = HashSet::new();
+        ref_docs.insert("order/a.json5".to_string());
+        fileindex_data.insert("_data/order.json5".to_string(), ref_docs);
+
+        let mut data = Database {
+            basic_data: BasicData {
+                read_me: "".to_string(),
+                project_name: "".to_string(),
+                project_desc: "".to_string(),
+                global_value: Value::Null,
+            },
+            api_docs,
+            api_data,
+            fileindex_data,
+            auth_doc: None,
+            settings: None,
+            menus,
+            router: Router::default(),
+            search_index: SearchIndex::default(),
+        };
+        assert_eq!(2, data.menus["order"].children.len());
+
+        let root = Path::new(".");
+        data.remove_api_doc_file(root, "order/a.json5");
+        assert_eq!(1, data.api_data["/order/"].len());
+        assert!(data.fileindex_data.is_empty());
+        assert_eq!(1, data.menus["order"].children.len());
+
+        data.remove_api_doc_file(root, "order/b.json5");
+        assert!(data.api_data.is_empty());
+        assert!(data.api_docs.is_empty());
+        assert!(data.menus.is_empty());
+    }
+
+    #[test]
+    fn parse_token_places_test() {
+        let places = parse_token_places(None);
+        assert_eq!("Authorization", places[0].name);
+        assert_eq!("Bearer ", places[0].prefix);
+
+        let value = json!(["url", "cookie:sid", {"place": "header", "name": "X-Api-Key"}]);
+        let places = parse_token_places(Some(&value));
+        assert_eq!(3, places.len());
+        assert_eq!(
+            ("query", "access_token"),
+            (places[0].place.as_str(), places[0].name.as_str())
+        );
+        assert_eq!(
+            ("cookie", "sid"),
+            (places[1].place.as_str(), places[1].name.as_str())
+        );
+        assert_eq!("", places[2].prefix);
+    }
+
+    #[test]
+    fn resolve_group_extends_test() {
+        let api_docs = HashMap::new();
+        let group = |name: &str, extends: Value, has_perms: Value| AuthData {
+            name: name.to_string(),
+            extends: extends
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_str().unwrap().to_string())
+                .collect(),
+            has_perms: parse_auth_perms(Some(&has_perms), &api_docs),
+            has_perm_rules: parse_conditional_perms(Some(&has_perms), &api_docs),
+            ..Default::default()
+        };
+        let mut groups = vec![
+            group("viewer", json!([]), json!([["/posts/", "GET"]])),
+            group(
+                "editor",
+                json!(["viewer", "admin"]),
+                json!([["/posts/", "PUT"], {"url": "/me/", "when": {"$body/id": 1}}]),
+            ),
+            group("admin", json!(["editor"]), json!([["/posts/", "DELETE"]])),
+        ];
+        resolve_group_extends(&mut groups);
+
+        let methods = |g: &AuthData| {
+            let mut m: Vec<String> = g.has_perms["/posts/"].iter().cloned().collect();
+            m.sort();
+            m
+        };
+        assert_eq!(vec!["GET"], methods(&groups[0]));
+        // editor和admin循环继承, 各自合并一次
+        assert_eq!(vec!["DELETE", "GET", "PUT"], methods(&groups[1]));
+        assert_eq!(vec!["DELETE", "GET", "PUT"], methods(&groups[2]));
+        assert_eq!(1, groups[2].has_perm_rules.len());
+        assert!(!groups[2].has_perms.contains_key("/me/"));
+
+        // 父组禁止的权限也继承, 子组自己授予的除外
+        let deny = json!([["/posts/", "DELETE"], "/admin/"]);
+        let mut groups = vec![
+            AuthData {
+                no_perms: parse_auth_perms(Some(&deny), &api_docs),
+                ..group("writer", json!([]), json!([["/posts/", "PUT"], "*"]))
+            },
+            group("reader", json!(["writer"]), json!([])),
+            group(
+                "owner",
+                json!(["writer"]),
+                json!([["/posts/", "DELETE"], ["/admin/", "GET"]]),
+            ),
+            group("guest", json!(["reader"]), json!([])),
+        ];
+        resolve_group_extends(&mut groups);
+        let is_denied = |g: &AuthData, url: &str, method: &str| {
+            crate::api::find_perm_rule(url, method, &g.no_perms).is_some()
+        };
+        assert!(is_denied(&groups[1], "/posts/", "DELETE"));
+        assert!(is_denied(&groups[1], "/admin/", "GET"));
+        assert!(is_denied(&groups[3], "/admin/", "GET"));
+
+        assert_eq!(vec!["DELETE", "PUT"], methods(&groups[2]));
+        assert!(!is_denied(&groups[2], "/posts/", "DELETE"));
+        assert!(!is_denied(&groups[2], "/admin/", "GET"));
+        assert!(is_denied(&groups[2], "/admin/", "POST"));
     }
 }
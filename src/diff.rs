@@ -0,0 +1,485 @@
+//! 比较两个版本的接口文档, 列出接口和字段的变化, 并区分是否是不兼容的改动
+//! 版本可以是两个目录, 也可以是git的提交, 提交中的文档会先导出到临时目录再加载
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::db;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Change {
+    /// api_added, api_removed, api_moved, api_method_changed,
+    /// field_added, field_removed, field_retyped, field_required
+    pub kind: String,
+    pub breaking: bool,
+    pub method: String,
+    pub url: String,
+    /// 接口的版本, 同一个url的不同版本分开比较
+    pub version: String,
+    /// url_param, query, body 或者 response, 接口的变化为空
+    pub part: String,
+    pub field: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffReport {
+    pub from: String,
+    pub to: String,
+    pub breaking: usize,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FieldInfo {
+    field_type: String,
+    required: bool,
+}
+
+/// 一个请求方法的接口
+struct Endpoint<'a> {
+    doc: &'a str,
+    method: &'a str,
+    api: &'a db::ApiData,
+}
+
+/// 比较两个版本, 参数是目录或者git的版本
+pub fn run_diff(from: &str, to: &str, root: &Path) -> Result<DiffReport, String> {
+    let (from_root, from_tmp) = resolve_root(from, root)?;
+    let to_result = resolve_root(to, root);
+    let result = to_result.map(|(to_root, to_tmp)| {
+        let from_data = db::Database::load(&from_root);
+        let to_data = db::Database::load(&to_root);
+        if let Some(tmp) = to_tmp {
+            let _ = fs::remove_dir_all(tmp);
+        }
+        DiffReport {
+            from: from.to_string(),
+            to: to.to_string(),
+            ..diff_api_docs(&from_data.api_docs, &to_data.api_docs)
+        }
+    });
+    if let Some(tmp) = from_tmp {
+        let _ = fs::remove_dir_all(tmp);
+    }
+    result
+}
+
+/// 目录直接使用, 否则当作git版本导出文档目录到临时目录, 返回文档根目录和需要删除的临时目录
+fn resolve_root(rev: &str, root: &Path) -> Result<(PathBuf, Option<PathBuf>), String> {
+    let path = Path::new(rev);
+    if path.is_dir() {
+        return Ok((path.to_path_buf(), None));
+    }
+
+    // 文档目录可能是git仓库的子目录
+    let output = git_output(root, &["rev-parse", "--show-toplevel", "--show-prefix"])?;
+    let mut lines = output.lines();
+    let toplevel = lines.next().unwrap_or_default().trim().to_string();
+    let prefix = lines
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .trim_end_matches('/');
+    let tree = if prefix.is_empty() {
+        rev.to_string()
+    } else {
+        format!("{}:{}", rev, prefix)
+    };
+
+    let tmp = std::env::temp_dir().join(format!(
+        "panda-diff-{}-{}",
+        std::process::id(),
+        rev.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    ));
+    let _ = fs::remove_dir_all(&tmp);
+    fs::create_dir_all(&tmp).map_err(|e| format!("create {} error: {}", tmp.display(), e))?;
+
+    let mut archive = Command::new("git")
+        .args(["archive", "--format=tar", &tree])
+        .current_dir(&toplevel)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("run git error: {}", e))?;
+    let archive_stdout = archive.stdout.take().ok_or("git archive has no output")?;
+    let status = Command::new("tar")
+        .args(["-x", "-C"])
+        .arg(&tmp)
+        .stdin(archive_stdout)
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("run tar error: {}", e))?;
+    let archived = archive.wait().map(|s| s.success()).unwrap_or(false);
+    if !archived || !status.success() {
+        let _ = fs::remove_dir_all(&tmp);
+        return Err(format!("{} is not a folder or a git revision", rev));
+    }
+    Ok((tmp.clone(), Some(tmp)))
+}
+
+fn git_output(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| format!("run git error: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn endpoints(api_docs: &HashMap<String, db::ApiDoc>) -> Vec<Endpoint<'_>> {
+    let mut result = Vec::new();
+    for (doc, api_doc) in api_docs {
+        for api in &api_doc.apis {
+            for method in &api.method {
+                result.push(Endpoint { doc, method, api });
+            }
+        }
+    }
+    result.sort_by(|a, b| (a.api.url.as_str(), a.method).cmp(&(b.api.url.as_str(), b.method)));
+    result
+}
+
+/// 比较两个版本的接口文档 {filename: api_doc}
+pub fn diff_api_docs(
+    from: &HashMap<String, db::ApiDoc>,
+    to: &HashMap<String, db::ApiDoc>,
+) -> DiffReport {
+    let from_endpoints = endpoints(from);
+    let to_endpoints = endpoints(to);
+    let key = |e: &Endpoint| format!("{} {} {}", e.method, e.api.url, e.api.version);
+    let to_keys: HashMap<String, usize> = to_endpoints
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (key(e), i))
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut matched = vec![false; to_endpoints.len()];
+    let mut removed = Vec::new();
+    for endpoint in &from_endpoints {
+        match to_keys.get(&key(endpoint)) {
+            Some(&i) => {
+                matched[i] = true;
+                diff_api(endpoint, &to_endpoints[i], &mut changes);
+            }
+            None => removed.push(endpoint),
+        }
+    }
+
+    // 同一个文档中同名同版本同url的接口换了方法, 当作请求方法的变化
+    // 同一个文档中同名同版本同方法的接口换了url, 当作接口地址的变化
+    for endpoint in removed {
+        let find = |same: &dyn Fn(&Endpoint) -> bool| {
+            to_endpoints.iter().enumerate().find(|(i, e)| {
+                !matched[*i]
+                    && e.doc == endpoint.doc
+                    && e.api.name == endpoint.api.name
+                    && e.api.version == endpoint.api.version
+                    && same(e)
+            })
+        };
+        let (kind, found) = match find(&|e| e.api.url == endpoint.api.url) {
+            Some(found) => ("api_method_changed", Some(found)),
+            None => ("api_moved", find(&|e| e.method == endpoint.method)),
+        };
+        match found {
+            Some((i, to_endpoint)) => {
+                matched[i] = true;
+                let detail = if kind == "api_moved" {
+                    format!("url changed to {}", to_endpoint.api.url)
+                } else {
+                    format!("{} -> {}", endpoint.method, to_endpoint.method)
+                };
+                changes.push(api_change(kind, true, endpoint, &detail));
+                diff_api(endpoint, to_endpoint, &mut changes);
+            }
+            None => changes.push(api_change("api_removed", true, endpoint, "")),
+        }
+    }
+    for (i, endpoint) in to_endpoints.iter().enumerate() {
+        if !matched[i] {
+            changes.push(api_change("api_added", false, endpoint, ""));
+        }
+    }
+
+    DiffReport {
+        from: "".to_string(),
+        to: "".to_string(),
+        breaking: changes.iter().filter(|c| c.breaking).count(),
+        changes,
+    }
+}
+
+fn api_change(kind: &str, breaking: bool, endpoint: &Endpoint, detail: &str) -> Change {
+    Change {
+        kind: kind.to_string(),
+        breaking,
+        method: endpoint.method.to_string(),
+        url: endpoint.api.url.to_string(),
+        version: endpoint.api.version.to_string(),
+        part: "".to_string(),
+        field: "".to_string(),
+        detail: detail.to_string(),
+    }
+}
+
+fn diff_api(from: &Endpoint, to: &Endpoint, changes: &mut Vec<Change>) {
+    let parts = [
+        ("url_param", &from.api.url_param, &to.api.url_param),
+        ("query", &from.api.query, &to.api.query),
+        ("body", &from.api.body, &to.api.body),
+        ("response", &from.api.response, &to.api.response),
+    ];
+    for (part, from_model, to_model) in parts.iter() {
+        let is_request = *part != "response";
+        let from_fields = get_fields(from_model);
+        let to_fields = get_fields(to_model);
+        let change = |kind: &str, breaking: bool, field: &str, detail: String| Change {
+            kind: kind.to_string(),
+            breaking,
+            method: to.method.to_string(),
+            url: to.api.url.to_string(),
+            version: to.api.version.to_string(),
+            part: part.to_string(),
+            field: field.to_string(),
+            detail,
+        };
+
+        for (field, from_info) in &from_fields {
+            match to_fields.get(field) {
+                // 请求中去掉的字段服务端不再读取, 返回中去掉的字段客户端可能还在使用
+                None => changes.push(change("field_removed", !is_request, field, "".to_string())),
+                Some(to_info) => {
+                    if from_info.field_type != to_info.field_type {
+                        changes.push(change(
+                            "field_retyped",
+                            true,
+                            field,
+                            format!("{} -> {}", from_info.field_type, to_info.field_type),
+                        ));
+                    }
+                    if from_info.required != to_info.required {
+                        // 请求字段变为必填, 或者返回字段变为可能没有, 都会影响现有的调用方
+                        let breaking = if is_request {
+                            to_info.required
+                        } else {
+                            !to_info.required
+                        };
+                        let detail = if to_info.required {
+                            "now required"
+                        } else {
+                            "now optional"
+                        };
+                        changes.push(change(
+                            "field_required",
+                            breaking,
+                            field,
+                            detail.to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        for (field, to_info) in &to_fields {
+            if !from_fields.contains_key(field) {
+                let breaking = is_request && to_info.required;
+                let detail = if breaking { "required" } else { "" };
+                changes.push(change("field_added", breaking, field, detail.to_string()));
+            }
+        }
+    }
+}
+
+/// 字段路径对应的类型和是否必填
+fn get_fields(model: &Value) -> BTreeMap<String, FieldInfo> {
+    let mut fields = BTreeMap::new();
+    db::walk_fields(model, &mut |path, attr| {
+        let required = attr
+            .get("required")
+            .or_else(|| attr.get("$required"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        fields.insert(
+            path.to_string(),
+            FieldInfo {
+                field_type: db::get_field_type(attr),
+                required,
+            },
+        );
+    });
+    fields
+}
+
+fn change_text(change: &Change) -> String {
+    let api = if change.version.is_empty() {
+        format!("`{} {}`", change.method, change.url)
+    } else {
+        format!("`{} {}` {}", change.method, change.url, change.version)
+    };
+    let mut text = match change.kind.as_str() {
+        "api_added" => format!("{} added", api),
+        "api_removed" => format!("{} removed", api),
+        "api_moved" => format!("{} moved", api),
+        "api_method_changed" => format!("{} method changed", api),
+        kind => format!(
+            "{} {} field `{}` {}",
+            api,
+            change.part,
+            change.field,
+            kind.trim_start_matches("field_")
+        ),
+    };
+    if !change.detail.is_empty() {
+        text.push_str(&format!(" ({})", change.detail));
+    }
+    text
+}
+
+/// 可以直接放到发布说明中的markdown
+pub fn to_markdown(report: &DiffReport) -> String {
+    let mut result = format!("# API changes from {} to {}\n", report.from, report.to);
+    if report.changes.is_empty() {
+        result.push_str("\nNo api changes.\n");
+        return result;
+    }
+    for (title, breaking) in &[("Breaking changes", true), ("Non-breaking changes", false)] {
+        let changes: Vec<&Change> = report
+            .changes
+            .iter()
+            .filter(|c| c.breaking == *breaking)
+            .collect();
+        if changes.is_empty() {
+            continue;
+        }
+        result.push_str(&format!("\n## {}\n\n", title));
+        for change in changes {
+            result.push_str(&format!("- {}\n", change_text(change)));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn api_docs(apis: Vec<db::ApiData>) -> HashMap<String, db::ApiDoc> {
+        let mut api_docs = HashMap::new();
+        api_docs.insert(
+            "a.json5".to_string(),
+            db::ApiDoc {
+                name: "A".to_string(),
+                desc: "".to_string(),
+                order: 0,
+                filename: "a.json5".to_string(),
+                apis: apis.into_iter().map(Arc::new).collect(),
+                version: "".to_string(),
+                since: "".to_string(),
+                deprecated: Value::Null,
+            },
+        );
+        api_docs
+    }
+
+    fn api(name: &str, url: &str, body: Value, response: Value) -> db::ApiData {
+        db::ApiData {
+            name: name.to_string(),
+            url: url.to_string(),
+            method: vec!["POST".to_string()],
+            body,
+            response,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_api_docs_test() {
+        let from = api_docs(vec![
+            api(
+                "create",
+                "/users/",
+                json!({"name": {"name": "name"}, "age": {"type": "int", "required": false}}),
+                json!({"id": {"type": "int"}, "name": {"name": "name"}}),
+            ),
+            api("delete", "/users/delete/", json!({}), json!({})),
+            api("update", "/users/update/", json!({}), json!({})),
+        ]);
+        let to = api_docs(vec![
+            api(
+                "create",
+                "/users/",
+                json!({"name": {"name": "name"}, "age": {"type": "int"}, "tag": {"required": false}}),
+                json!({"id": {"type": "string"}}),
+            ),
+            api("delete", "/users/remove/", json!({}), json!({})),
+            db::ApiData {
+                method: vec!["PUT".to_string()],
+                ..api("update", "/users/update/", json!({}), json!({}))
+            },
+        ]);
+        let report = diff_api_docs(&from, &to);
+        let texts: Vec<(bool, String)> = report
+            .changes
+            .iter()
+            .map(|c| (c.breaking, change_text(c)))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    true,
+                    "`POST /users/` body field `age` required (now required)".to_string()
+                ),
+                (false, "`POST /users/` body field `tag` added".to_string()),
+                (
+                    true,
+                    "`POST /users/` response field `id` retyped (int -> string)".to_string()
+                ),
+                (
+                    true,
+                    "`POST /users/` response field `name` removed".to_string()
+                ),
+                (
+                    true,
+                    "`POST /users/delete/` moved (url changed to /users/remove/)".to_string()
+                ),
+                (
+                    true,
+                    "`POST /users/update/` method changed (POST -> PUT)".to_string()
+                ),
+            ],
+            texts
+        );
+        assert_eq!(5, report.breaking);
+
+        // 同一个url的两个版本分别比较
+        let versioned = |version: &str, response: Value| db::ApiData {
+            version: version.to_string(),
+            ..api("list", "/users/list/", json!({}), response)
+        };
+        let from = api_docs(vec![
+            versioned("v1", json!({"id": {"type": "int"}})),
+            versioned("v2", json!({"id": {"type": "string"}})),
+        ]);
+        let to = api_docs(vec![
+            versioned("v1", json!({"id": {"type": "int"}})),
+            versioned("v2", json!({"id": {"type": "string"}, "tag": {}})),
+        ]);
+        let report = diff_api_docs(&from, &to);
+        let texts: Vec<String> = report.changes.iter().map(change_text).collect();
+        assert_eq!(
+            vec!["`POST /users/list/` v2 response field `tag` added".to_string()],
+            texts
+        );
+    }
+}
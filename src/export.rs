@@ -0,0 +1,182 @@
+//! 导出静态的文档网站, 不用运行panda也可以浏览
+//! 文档页面请求的 /__api_docs/ 数据预先生成到 data.js 中, 由皮肤中的 static.js 返回给页面
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::api;
+use crate::db;
+use crate::jwt;
+
+/// 静态网站中示例代码默认的服务器地址
+pub const DEFAULT_SERVER: &str = "http://127.0.0.1:9000";
+
+/// 导出静态html文档到out目录
+pub fn export_html(root: &Path, out: &Path) -> Result<(), String> {
+    let data = db::Database::load(root);
+    let theme_dir = api::get_theme_file_path(root, "");
+    if !theme_dir.join("index.html").exists() {
+        return Err(format!("theme not found in {}", theme_dir.display()));
+    }
+
+    fs::create_dir_all(out).map_err(|e| format!("create {} error: {}", out.display(), e))?;
+    copy_theme(&theme_dir, &out.join("theme"))?;
+
+    let index = fs::read_to_string(theme_dir.join("index.html"))
+        .map_err(|e| format!("read index.html error: {}", e))?;
+    write_file(&out.join("index.html"), &static_index(&index))?;
+
+    let static_data = get_static_data(&data, root);
+    let content = format!(
+        "window.PANDA_STATIC_DATA = {};\n",
+        serde_json::to_string(&static_data).unwrap_or_default()
+    );
+    write_file(&out.join("data.js"), &content)?;
+    println!("exported the docs to {}", out.display());
+    Ok(())
+}
+
+/// 静态网站的首页, 使用相对路径, 去掉实时刷新并在页面脚本前加载预先生成的数据
+fn static_index(index: &str) -> String {
+    let index = index
+        .replace("/__api_docs/theme/", "theme/")
+        .replace("<script src=theme/js/live-reload.js></script>", "");
+    let scripts = "<script src=data.js></script><script src=theme/js/static.js></script>";
+    match index.find("<script") {
+        Some(i) => format!("{}{}{}", &index[..i], scripts, &index[i..]),
+        None => index.replace("</body>", &format!("{}</body>", scripts)),
+    }
+}
+
+/// 文档页面请求的地址和返回的数据
+fn get_static_data(data: &db::Database, root: &Path) -> Map<String, Value> {
+    let mut static_data = Map::new();
+    static_data.insert(
+        "/__api_docs/".to_string(),
+        api::get_api_doc_basic_value(data, None),
+    );
+
+    let mut doc_files = vec![
+        "_auth.json5".to_string(),
+        "_settings.json5".to_string(),
+        jwt::JWT_DOC_FILE.to_string(),
+    ];
+    collect_menu_files(&data.menus, &mut doc_files);
+    for doc in data.api_docs.values() {
+        doc_files.push(doc.filename.to_string());
+    }
+    doc_files.sort();
+    doc_files.dedup();
+
+    let base_url = get_default_server(data);
+    for doc_file in &doc_files {
+        let value = api::get_api_doc_value(data, root, doc_file, &base_url, None);
+        if !value.is_null() {
+            static_data.insert(
+                format!("/__api_docs/api_data/?filename={}", doc_file),
+                value,
+            );
+        }
+    }
+
+    let mut data_files = Vec::new();
+    collect_data_files(root, Path::new("_data"), &mut data_files);
+    for data_file in data_files {
+        if let Ok(content) = fs::read_to_string(root.join(&data_file)) {
+            static_data.insert(
+                format!("/__api_docs/_data/?filename={}", data_file),
+                json!(content),
+            );
+        }
+    }
+    static_data
+}
+
+/// 示例代码使用 _settings.json5 中的第一个服务器
+fn get_default_server(data: &db::Database) -> String {
+    data.settings
+        .as_ref()
+        .and_then(|s| s.get("servers"))
+        .and_then(|s| s.as_object())
+        .and_then(|servers| servers.values().find_map(|s| s.get("url")?.as_str()))
+        .unwrap_or(DEFAULT_SERVER)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn collect_menu_files(menus: &HashMap<String, db::Menu>, doc_files: &mut Vec<String>) {
+    for menu in menus.values() {
+        if !menu.filename.is_empty() {
+            doc_files.push(menu.filename.to_string());
+        }
+        collect_menu_files(&menu.children, doc_files);
+    }
+}
+
+/// _data 目录中的文件, 路径相对于文档根目录
+fn collect_data_files(root: &Path, dir: &Path, data_files: &mut Vec<String>) {
+    let entries = match fs::read_dir(root.join(dir)) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        let path = dir.join(entry.file_name());
+        if entry.path().is_dir() {
+            collect_data_files(root, &path, data_files);
+        } else if let Some(path) = path.to_str() {
+            data_files.push(path.replace('\\', "/"));
+        }
+    }
+}
+
+/// 复制皮肤文件, js中的资源地址改为相对路径, 不复制source map
+fn copy_theme(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("create {} error: {}", to.display(), e))?;
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("read {} error: {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let target = to.join(entry.file_name());
+        if path.is_dir() {
+            copy_theme(&path, &target)?;
+            continue;
+        }
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("map") => {}
+            Some("js") => {
+                let content = fs::read_to_string(&path)
+                    .map_err(|e| format!("read {} error: {}", path.display(), e))?;
+                write_file(
+                    &target,
+                    &content.replace("\"/__api_docs/theme/\"", "\"theme/\""),
+                )?;
+            }
+            _ => {
+                fs::copy(&path, &target)
+                    .map_err(|e| format!("copy {} error: {}", path.display(), e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("write {} error: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn static_index_test() {
+        let index = "<head><link href=/__api_docs/theme/css/app.css rel=stylesheet></head><body><script src=/__api_docs/theme/js/app.js></script><script src=/__api_docs/theme/js/live-reload.js></script></body>";
+        assert_eq!(
+            "<head><link href=theme/css/app.css rel=stylesheet></head><body><script src=data.js></script><script src=theme/js/static.js></script><script src=theme/js/app.js></script></body>",
+            static_index(index)
+        );
+    }
+}
@@ -0,0 +1,1162 @@
+//! GraphQL接口: 解析接口文档中的schema(SDL)和请求的query
+//! 按operation名称和variables匹配test_data，匹配不到就按schema生成mock数据
+
+use std::collections::HashMap;
+
+use rand::{thread_rng, Rng};
+use serde_json::{json, Map, Value};
+
+use crate::api;
+use crate::db;
+
+/// 没有定义长度的列表，mock数据最多生成几条
+const MAX_LIST_LEN: usize = 3;
+/// 查询最多嵌套的层数, 列表每层最多有MAX_LIST_LEN个元素, 太深的查询生成的数据太多
+const MAX_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+    Spread,
+    Str(String),
+    Int(i64),
+    Float(f64),
+}
+
+/// 把graphql文本拆分为token, 逗号和注释都忽略
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' || c == '\u{feff}' {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '.' {
+            if chars.get(i + 1) == Some(&'.') && chars.get(i + 2) == Some(&'.') {
+                tokens.push(Token::Spread);
+                i += 3;
+            } else {
+                return Err(format!("Unexpected character \".\" at {}", i));
+            }
+        } else if "!$&():=@[]{}|".contains(c) {
+            tokens.push(Token::Punct(c));
+            i += 1;
+        } else if c == '_' || c.is_ascii_alphabetic() {
+            let start = i;
+            while i < chars.len() && (chars[i] == '_' || chars[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else if c == '-' || c.is_ascii_digit() {
+            let start = i;
+            let mut is_float = false;
+            i += 1;
+            while i < chars.len() {
+                let n = chars[i];
+                if n.is_ascii_digit() {
+                    i += 1;
+                } else if n == '.' || n == 'e' || n == 'E' {
+                    is_float = true;
+                    i += 1;
+                } else if (n == '+' || n == '-') && (chars[i - 1] == 'e' || chars[i - 1] == 'E') {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            let s: String = chars[start..i].iter().collect();
+            let token = if is_float {
+                s.parse().map(Token::Float).ok()
+            } else {
+                s.parse().map(Token::Int).ok()
+            };
+            match token {
+                Some(t) => tokens.push(t),
+                None => return Err(format!("Invalid number {}", s)),
+            }
+        } else if c == '"' {
+            let (s, next) = read_string(&chars, i)?;
+            tokens.push(Token::Str(s));
+            i = next;
+        } else {
+            return Err(format!("Unexpected character \"{}\" at {}", c, i));
+        }
+    }
+    Ok(tokens)
+}
+
+/// 读取字符串, 支持 """ 块字符串, 返回字符串和之后的位置
+fn read_string(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    let mut s = String::new();
+    if chars.get(start + 1) == Some(&'"') && chars.get(start + 2) == Some(&'"') {
+        let mut i = start + 3;
+        while i < chars.len() {
+            if chars[i] == '"' && chars.get(i + 1) == Some(&'"') && chars.get(i + 2) == Some(&'"') {
+                return Ok((s.trim().to_string(), i + 3));
+            }
+            s.push(chars[i]);
+            i += 1;
+        }
+        return Err("Unterminated string".to_string());
+    }
+
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => return Ok((s, i + 1)),
+            '\\' => {
+                i += 1;
+                match chars.get(i) {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('b') => s.push('\u{8}'),
+                    Some('f') => s.push('\u{c}'),
+                    Some('u') => {
+                        let hex: String = chars.iter().skip(i + 1).take(4).collect();
+                        match u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                        {
+                            Some(c) => s.push(c),
+                            None => return Err(format!("Invalid unicode escape \\u{}", hex)),
+                        }
+                        i += 4;
+                    }
+                    Some(c) => s.push(*c),
+                    None => break,
+                }
+            }
+            '\n' => break,
+            c => s.push(c),
+        }
+        i += 1;
+    }
+    Err("Unterminated string".to_string())
+}
+
+/// 参数的值, 变量在执行的时候才能确定
+#[derive(Debug, Clone, PartialEq)]
+enum InputValue {
+    Variable(String),
+    Const(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Directive {
+    name: String,
+    arguments: Vec<(String, InputValue)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    alias: Option<String>,
+    name: String,
+    directives: Vec<Directive>,
+    selections: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selection {
+    Field(Field),
+    FragmentSpread(String, Vec<Directive>),
+    InlineFragment(Option<String>, Vec<Directive>, Vec<Selection>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Operation {
+    /// query, mutation 或者 subscription
+    kind: String,
+    name: Option<String>,
+    /// 变量的默认值
+    defaults: Map<String, Value>,
+    selections: Vec<Selection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Fragment {
+    type_condition: String,
+    selections: Vec<Selection>,
+}
+
+/// 一个graphql请求文档
+#[derive(Debug, Clone, Default)]
+struct Document {
+    operations: Vec<Operation>,
+    fragments: HashMap<String, Fragment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TypeRef {
+    Named(String),
+    List(Box<TypeRef>),
+    NonNull(Box<TypeRef>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TypeKind {
+    Object,
+    Interface,
+    Input,
+    Enum,
+    Scalar,
+    Union,
+}
+
+#[derive(Debug, Clone)]
+struct TypeDef {
+    kind: TypeKind,
+    fields: HashMap<String, TypeRef>,
+    interfaces: Vec<String>,
+    /// union的成员类型或者enum的值
+    members: Vec<String>,
+}
+
+/// 接口文档中定义的schema
+#[derive(Debug, Clone)]
+pub struct Schema {
+    query: String,
+    mutation: String,
+    subscription: String,
+    types: HashMap<String, TypeDef>,
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Parser, String> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+        })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token, String> {
+        match self.tokens.get(self.pos) {
+            Some(t) => {
+                self.pos += 1;
+                Ok(t.clone())
+            }
+            None => Err("Unexpected end of document".to_string()),
+        }
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn is_name(&self, name: &str) -> bool {
+        match self.peek() {
+            Some(Token::Name(n)) => n == name,
+            _ => false,
+        }
+    }
+
+    /// 下一个是指定的符号就跳过它
+    fn skip_punct(&mut self, c: char) -> bool {
+        if self.is_punct(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), String> {
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            t => Err(format!("Expected \"{}\", found {:?}", c, t)),
+        }
+    }
+
+    fn expect_name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Token::Name(n) => Ok(n),
+            t => Err(format!("Expected Name, found {:?}", t)),
+        }
+    }
+
+    /// 跳过schema中的描述字符串
+    fn skip_description(&mut self) {
+        if let Some(Token::Str(_)) = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<InputValue, String> {
+        let value = match self.next()? {
+            Token::Punct('$') => return Ok(InputValue::Variable(self.expect_name()?)),
+            Token::Int(v) => json!(v),
+            Token::Float(v) => json!(v),
+            Token::Str(v) => Value::String(v),
+            Token::Name(n) => match n.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                _ => Value::String(n),
+            },
+            Token::Punct('[') => {
+                let mut list = Vec::new();
+                while !self.skip_punct(']') {
+                    list.push(const_value(self.parse_value()?));
+                }
+                Value::Array(list)
+            }
+            Token::Punct('{') => {
+                let mut obj = Map::new();
+                while !self.skip_punct('}') {
+                    let name = self.expect_name()?;
+                    self.expect_punct(':')?;
+                    obj.insert(name, const_value(self.parse_value()?));
+                }
+                Value::Object(obj)
+            }
+            t => return Err(format!("Unexpected {:?}", t)),
+        };
+        Ok(InputValue::Const(value))
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<(String, InputValue)>, String> {
+        let mut arguments = Vec::new();
+        if self.skip_punct('(') {
+            while !self.skip_punct(')') {
+                let name = self.expect_name()?;
+                self.expect_punct(':')?;
+                arguments.push((name, self.parse_value()?));
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn parse_directives(&mut self) -> Result<Vec<Directive>, String> {
+        let mut directives = Vec::new();
+        while self.skip_punct('@') {
+            directives.push(Directive {
+                name: self.expect_name()?,
+                arguments: self.parse_arguments()?,
+            });
+        }
+        Ok(directives)
+    }
+
+    fn parse_type(&mut self) -> Result<TypeRef, String> {
+        let t = if self.skip_punct('[') {
+            let t = self.parse_type()?;
+            self.expect_punct(']')?;
+            TypeRef::List(Box::new(t))
+        } else {
+            TypeRef::Named(self.expect_name()?)
+        };
+        if self.skip_punct('!') {
+            return Ok(TypeRef::NonNull(Box::new(t)));
+        }
+        Ok(t)
+    }
+
+    fn parse_selection_set(&mut self) -> Result<Vec<Selection>, String> {
+        self.expect_punct('{')?;
+        let mut selections = Vec::new();
+        while !self.skip_punct('}') {
+            if self.peek() == Some(&Token::Spread) {
+                self.pos += 1;
+                if self.is_name("on") {
+                    self.pos += 1;
+                    let type_condition = self.expect_name()?;
+                    let directives = self.parse_directives()?;
+                    let sub = self.parse_selection_set()?;
+                    selections.push(Selection::InlineFragment(
+                        Some(type_condition),
+                        directives,
+                        sub,
+                    ));
+                } else if let Some(Token::Name(_)) = self.peek() {
+                    let name = self.expect_name()?;
+                    let directives = self.parse_directives()?;
+                    selections.push(Selection::FragmentSpread(name, directives));
+                } else {
+                    let directives = self.parse_directives()?;
+                    let sub = self.parse_selection_set()?;
+                    selections.push(Selection::InlineFragment(None, directives, sub));
+                }
+                continue;
+            }
+
+            let mut name = self.expect_name()?;
+            let mut alias = None;
+            if self.skip_punct(':') {
+                alias = Some(name);
+                name = self.expect_name()?;
+            }
+            self.parse_arguments()?;
+            let directives = self.parse_directives()?;
+            let sub = if self.is_punct('{') {
+                self.parse_selection_set()?
+            } else {
+                Vec::new()
+            };
+            selections.push(Selection::Field(Field {
+                alias,
+                name,
+                directives,
+                selections: sub,
+            }));
+        }
+        Ok(selections)
+    }
+
+    fn parse_document(&mut self) -> Result<Document, String> {
+        let mut document = Document::default();
+        while !self.is_end() {
+            if self.is_punct('{') {
+                document.operations.push(Operation {
+                    kind: "query".to_string(),
+                    name: None,
+                    defaults: Map::new(),
+                    selections: self.parse_selection_set()?,
+                });
+                continue;
+            }
+            let keyword = self.expect_name()?;
+            match keyword.as_str() {
+                "query" | "mutation" | "subscription" => {
+                    let mut name = None;
+                    if let Some(Token::Name(_)) = self.peek() {
+                        name = Some(self.expect_name()?);
+                    }
+                    let mut defaults = Map::new();
+                    if self.skip_punct('(') {
+                        while !self.skip_punct(')') {
+                            self.expect_punct('$')?;
+                            let var_name = self.expect_name()?;
+                            self.expect_punct(':')?;
+                            self.parse_type()?;
+                            if self.skip_punct('=') {
+                                defaults.insert(var_name, const_value(self.parse_value()?));
+                            }
+                            self.parse_directives()?;
+                        }
+                    }
+                    self.parse_directives()?;
+                    document.operations.push(Operation {
+                        kind: keyword,
+                        name,
+                        defaults,
+                        selections: self.parse_selection_set()?,
+                    });
+                }
+                "fragment" => {
+                    let name = self.expect_name()?;
+                    if !self.is_name("on") {
+                        return Err(format!("Expected \"on\" after fragment {}", name));
+                    }
+                    self.pos += 1;
+                    let type_condition = self.expect_name()?;
+                    self.parse_directives()?;
+                    let selections = self.parse_selection_set()?;
+                    document.fragments.insert(
+                        name,
+                        Fragment {
+                            type_condition,
+                            selections,
+                        },
+                    );
+                }
+                _ => return Err(format!("Unexpected Name \"{}\"", keyword)),
+            }
+        }
+        if document.operations.is_empty() {
+            return Err("Document has no operation".to_string());
+        }
+        Ok(document)
+    }
+
+    /// 解析schema中一个类型的字段定义, 字段参数和默认值都忽略
+    fn parse_fields_definition(&mut self) -> Result<HashMap<String, TypeRef>, String> {
+        let mut fields = HashMap::new();
+        if !self.skip_punct('{') {
+            return Ok(fields);
+        }
+        while !self.skip_punct('}') {
+            self.skip_description();
+            let name = self.expect_name()?;
+            self.skip_arguments_definition()?;
+            self.expect_punct(':')?;
+            let field_type = self.parse_type()?;
+            if self.skip_punct('=') {
+                self.parse_value()?;
+            }
+            self.parse_directives()?;
+            fields.insert(name, field_type);
+        }
+        Ok(fields)
+    }
+
+    /// 解析 A | B | C 这样的名称列表
+    fn parse_name_list(&mut self, separator: char) -> Result<Vec<String>, String> {
+        let mut names = Vec::new();
+        self.skip_punct(separator);
+        names.push(self.expect_name()?);
+        while self.skip_punct(separator) {
+            names.push(self.expect_name()?);
+        }
+        Ok(names)
+    }
+
+    fn parse_schema(&mut self) -> Result<Schema, String> {
+        let mut schema = Schema {
+            query: "Query".to_string(),
+            mutation: "Mutation".to_string(),
+            subscription: "Subscription".to_string(),
+            types: HashMap::new(),
+        };
+
+        while !self.is_end() {
+            self.skip_description();
+            let mut keyword = self.expect_name()?;
+            if keyword == "extend" {
+                keyword = self.expect_name()?;
+            }
+            if keyword == "schema" {
+                self.parse_directives()?;
+                self.expect_punct('{')?;
+                while !self.skip_punct('}') {
+                    let operation = self.expect_name()?;
+                    self.expect_punct(':')?;
+                    let type_name = self.expect_name()?;
+                    match operation.as_str() {
+                        "query" => schema.query = type_name,
+                        "mutation" => schema.mutation = type_name,
+                        "subscription" => schema.subscription = type_name,
+                        _ => return Err(format!("Unknown operation type {}", operation)),
+                    }
+                }
+                continue;
+            }
+            if keyword == "directive" {
+                self.expect_punct('@')?;
+                self.expect_name()?;
+                self.skip_arguments_definition()?;
+                if self.is_name("repeatable") {
+                    self.pos += 1;
+                }
+                if !self.is_name("on") {
+                    return Err("Expected \"on\" in directive definition".to_string());
+                }
+                self.pos += 1;
+                self.parse_name_list('|')?;
+                continue;
+            }
+
+            let kind = match keyword.as_str() {
+                "type" => TypeKind::Object,
+                "interface" => TypeKind::Interface,
+                "input" => TypeKind::Input,
+                "enum" => TypeKind::Enum,
+                "scalar" => TypeKind::Scalar,
+                "union" => TypeKind::Union,
+                _ => return Err(format!("Unexpected Name \"{}\"", keyword)),
+            };
+            let name = self.expect_name()?;
+            let mut interfaces = Vec::new();
+            let mut members = Vec::new();
+            let mut fields = HashMap::new();
+            match kind {
+                TypeKind::Object | TypeKind::Interface | TypeKind::Input => {
+                    if self.is_name("implements") {
+                        self.pos += 1;
+                        interfaces = self.parse_name_list('&')?;
+                    }
+                    self.parse_directives()?;
+                    fields = self.parse_fields_definition()?;
+                }
+                TypeKind::Enum => {
+                    self.parse_directives()?;
+                    if self.skip_punct('{') {
+                        while !self.skip_punct('}') {
+                            self.skip_description();
+                            members.push(self.expect_name()?);
+                            self.parse_directives()?;
+                        }
+                    }
+                }
+                TypeKind::Scalar => {
+                    self.parse_directives()?;
+                }
+                TypeKind::Union => {
+                    self.parse_directives()?;
+                    if self.skip_punct('=') {
+                        members = self.parse_name_list('|')?;
+                    }
+                }
+            }
+
+            // extend 的类型合并到原来的定义中
+            let type_def = schema.types.entry(name).or_insert(TypeDef {
+                kind,
+                fields: HashMap::new(),
+                interfaces: Vec::new(),
+                members: Vec::new(),
+            });
+            type_def.fields.extend(fields);
+            type_def.interfaces.append(&mut interfaces);
+            type_def.members.append(&mut members);
+        }
+
+        if !schema.types.contains_key(&schema.query) {
+            return Err(format!(
+                "Query root type \"{}\" is not defined",
+                schema.query
+            ));
+        }
+        Ok(schema)
+    }
+
+    /// 跳过字段和指令定义的参数
+    fn skip_arguments_definition(&mut self) -> Result<(), String> {
+        if self.skip_punct('(') {
+            while !self.skip_punct(')') {
+                self.skip_description();
+                self.expect_name()?;
+                self.expect_punct(':')?;
+                self.parse_type()?;
+                if self.skip_punct('=') {
+                    self.parse_value()?;
+                }
+                self.parse_directives()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn const_value(value: InputValue) -> Value {
+    match value {
+        InputValue::Const(v) => v,
+        InputValue::Variable(_) => Value::Null,
+    }
+}
+
+/// 解析schema定义, 接口文档加载的时候用来检查schema是否正确
+pub fn parse_schema(source: &str) -> Result<Schema, String> {
+    Parser::new(source)?.parse_schema()
+}
+
+fn parse_document(source: &str) -> Result<Document, String> {
+    Parser::new(source)?.parse_document()
+}
+
+fn graphql_error(message: &str) -> Value {
+    json!({ "errors": [{ "message": message }] })
+}
+
+/// 按schema生成mock数据
+struct Resolver<'a> {
+    schema: &'a Schema,
+    fragments: &'a HashMap<String, Fragment>,
+    variables: &'a Map<String, Value>,
+    /// 字段的mock设置, 如 {"Order.status": {enum:["paid", "sent"]}}
+    mock: &'a Value,
+    /// 正在展开的fragment, 防止fragment循环引用
+    fragment_stack: Vec<String>,
+    errors: Vec<Value>,
+}
+
+impl<'a> Resolver<'a> {
+    /// 按 @skip 和 @include 判断字段是否需要返回
+    fn is_included(&self, directives: &[Directive]) -> bool {
+        for directive in directives {
+            let condition = directive
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "if")
+                .map(|(_, value)| match value {
+                    InputValue::Variable(v) => {
+                        self.variables.get(v).cloned().unwrap_or(Value::Null)
+                    }
+                    InputValue::Const(v) => v.clone(),
+                });
+            let condition = condition.and_then(|v| v.as_bool()).unwrap_or(false);
+            match directive.name.as_str() {
+                "skip" if condition => return false,
+                "include" if !condition => return false,
+                _ => (),
+            }
+        }
+        true
+    }
+
+    /// fragment的类型条件是否适用于当前的类型
+    fn is_type_match(&self, type_name: &str, type_condition: &Option<String>) -> bool {
+        let condition = match type_condition {
+            Some(c) => c,
+            None => return true,
+        };
+        if condition == type_name {
+            return true;
+        }
+        match self.schema.types.get(condition) {
+            Some(t) if t.kind == TypeKind::Union => t.members.iter().any(|m| m == type_name),
+            Some(t) if t.kind == TypeKind::Interface => match self.schema.types.get(type_name) {
+                Some(current) => current.interfaces.iter().any(|i| i == condition),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// interface 和 union 随机选择一个具体的类型
+    fn concrete_type(&self, type_name: &str) -> Option<String> {
+        let type_def = self.schema.types.get(type_name)?;
+        let mut candidates: Vec<&String> = match type_def.kind {
+            TypeKind::Union => type_def.members.iter().collect(),
+            TypeKind::Interface => self
+                .schema
+                .types
+                .iter()
+                .filter(|(_, t)| {
+                    t.kind == TypeKind::Object && t.interfaces.iter().any(|i| i == type_name)
+                })
+                .map(|(name, _)| name)
+                .collect(),
+            _ => return Some(type_name.to_string()),
+        };
+        candidates.sort();
+        if candidates.is_empty() {
+            return None;
+        }
+        let i = thread_rng().gen_range(0, candidates.len());
+        Some(candidates[i].to_string())
+    }
+
+    fn resolve_selections(
+        &mut self,
+        type_name: &str,
+        selections: &[Selection],
+        result: &mut Map<String, Value>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    if !self.is_included(&field.directives) {
+                        continue;
+                    }
+                    let key = field.alias.as_ref().unwrap_or(&field.name).to_string();
+                    let value = self.resolve_field(type_name, field);
+                    merge_value(result, key, value);
+                }
+                Selection::FragmentSpread(name, directives) => {
+                    if !self.is_included(directives) {
+                        continue;
+                    }
+                    if self.fragment_stack.contains(name) {
+                        self.add_error(format!(
+                            "Cannot spread fragment \"{}\" within itself",
+                            name
+                        ));
+                        continue;
+                    }
+                    let fragment = match self.fragments.get(name) {
+                        Some(f) => f,
+                        None => {
+                            self.add_error(format!("Unknown fragment \"{}\"", name));
+                            continue;
+                        }
+                    };
+                    if self.is_type_match(type_name, &Some(fragment.type_condition.clone())) {
+                        self.fragment_stack.push(name.to_string());
+                        self.resolve_selections(type_name, &fragment.selections, result);
+                        self.fragment_stack.pop();
+                    }
+                }
+                Selection::InlineFragment(type_condition, directives, sub) => {
+                    if self.is_included(directives) && self.is_type_match(type_name, type_condition)
+                    {
+                        self.resolve_selections(type_name, sub, result);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_field(&mut self, type_name: &str, field: &Field) -> Value {
+        if field.name == "__typename" {
+            return Value::String(type_name.to_string());
+        }
+        let field_type = self
+            .schema
+            .types
+            .get(type_name)
+            .and_then(|t| t.fields.get(&field.name))
+            .cloned();
+        match field_type {
+            Some(field_type) => {
+                let mock_key = format!("{}.{}", type_name, field.name);
+                self.resolve_type(&field_type, field, &mock_key)
+            }
+            None => {
+                self.add_error(format!(
+                    "Cannot query field \"{}\" on type \"{}\"",
+                    field.name, type_name
+                ));
+                Value::Null
+            }
+        }
+    }
+
+    fn resolve_type(&mut self, field_type: &TypeRef, field: &Field, mock_key: &str) -> Value {
+        match field_type {
+            TypeRef::NonNull(t) => self.resolve_type(t, field, mock_key),
+            TypeRef::List(t) => {
+                let len = thread_rng().gen_range(1, MAX_LIST_LEN + 1);
+                let list = (0..len)
+                    .map(|_| self.resolve_type(t, field, mock_key))
+                    .collect();
+                Value::Array(list)
+            }
+            TypeRef::Named(name) => {
+                let type_def = self.schema.types.get(name);
+                let kind = type_def.map(|t| t.kind).unwrap_or(TypeKind::Scalar);
+                if kind == TypeKind::Object
+                    || kind == TypeKind::Interface
+                    || kind == TypeKind::Union
+                {
+                    if field.selections.is_empty() {
+                        self.add_error(format!(
+                            "Field \"{}\" of type \"{}\" must have a selection of subfields",
+                            field.name, name
+                        ));
+                        return Value::Null;
+                    }
+                    let concrete = match self.concrete_type(name) {
+                        Some(t) => t,
+                        None => {
+                            self.add_error(format!(
+                                "Type \"{}\" has no possible object type",
+                                name
+                            ));
+                            return Value::Null;
+                        }
+                    };
+                    let mut result = Map::new();
+                    self.resolve_selections(&concrete, &field.selections, &mut result);
+                    return Value::Object(result);
+                }
+
+                let field_attr = match self.mock.get(mock_key) {
+                    Some(v) => v.clone(),
+                    None => match type_def {
+                        Some(t) if t.kind == TypeKind::Enum => {
+                            json!({ "type": "string", "enum": t.members })
+                        }
+                        _ => scalar_field_attr(name),
+                    },
+                };
+                api::create_mock_value(&field_attr, "", &field_attr, &Value::Null, &Value::Null)
+                    .unwrap_or(Value::Null)
+            }
+        }
+    }
+
+    fn add_error(&mut self, message: String) {
+        self.errors.push(json!({ "message": message }));
+    }
+}
+
+/// graphql内置的标量类型对应的mock字段设置, Int是32位整数
+fn scalar_field_attr(type_name: &str) -> Value {
+    match type_name {
+        "Int" => json!({ "type": "int", "min_value": 0, "max_value": 10000 }),
+        "Float" => json!({ "type": "posfloat" }),
+        "Boolean" => json!({ "type": "bool" }),
+        "ID" => json!({ "type": "uuid" }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+/// 合并fragment和字段的结果, 同一个字段的子字段合并在一起
+fn merge_value(result: &mut Map<String, Value>, key: String, value: Value) {
+    match (result.get_mut(&key), value) {
+        (Some(Value::Object(old)), Value::Object(new)) => {
+            for (k, v) in new {
+                merge_value(old, k, v);
+            }
+        }
+        (Some(_), _) => (),
+        (None, value) => {
+            result.insert(key, value);
+        }
+    }
+}
+
+/// 查询嵌套的层数, fragment展开计算, 循环引用的fragment不再展开
+fn selection_depth(
+    selections: &[Selection],
+    fragments: &HashMap<String, Fragment>,
+    stack: &mut Vec<String>,
+) -> usize {
+    let mut depth = 0;
+    for selection in selections {
+        let d = match selection {
+            Selection::Field(field) if field.selections.is_empty() => 1,
+            Selection::Field(field) => 1 + selection_depth(&field.selections, fragments, stack),
+            Selection::FragmentSpread(name, _) => match fragments.get(name) {
+                Some(fragment) if !stack.contains(name) => {
+                    stack.push(name.to_string());
+                    let d = selection_depth(&fragment.selections, fragments, stack);
+                    stack.pop();
+                    d
+                }
+                _ => 0,
+            },
+            Selection::InlineFragment(_, _, sub) => selection_depth(sub, fragments, stack),
+        };
+        depth = depth.max(d);
+    }
+    depth
+}
+
+/// 从请求中获取query, operationName和variables, GET请求的variables是json字符串
+fn get_request_params(
+    request_body: &Value,
+    request_query: &Value,
+) -> (String, Option<String>, Value) {
+    let params = if request_body.get("query").is_some() {
+        request_body
+    } else {
+        request_query
+    };
+    let query = params
+        .get("query")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let operation_name = params
+        .get("operationName")
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+    let variables = match params.get("variables") {
+        Some(Value::String(s)) => serde_json::from_str(s).unwrap_or(Value::Null),
+        Some(v) => v.clone(),
+        None => Value::Null,
+    };
+    (query, operation_name, variables)
+}
+
+/// 处理一个graphql请求, 返回response和延迟的毫秒数
+/// 先按operation名称和variables匹配test_data, 匹配不到再按schema生成mock数据
+pub fn get_graphql_response(
+    api: &db::ApiData,
+    request_body: &Value,
+    request_query: &Value,
+) -> (Value, u64) {
+    let (query, operation_name, variables) = get_request_params(request_body, request_query);
+    if query.is_empty() {
+        return (graphql_error("Must provide query string."), 0);
+    }
+
+    let document = match parse_document(&query) {
+        Ok(d) => d,
+        Err(e) => return (graphql_error(&format!("Syntax Error: {}", e)), 0),
+    };
+    let operation = match &operation_name {
+        Some(name) => document
+            .operations
+            .iter()
+            .find(|o| o.name.as_ref() == Some(name)),
+        None if document.operations.len() == 1 => document.operations.first(),
+        None => {
+            return (
+                graphql_error("Must provide operation name if query contains multiple operations."),
+                0,
+            )
+        }
+    };
+    let operation = match operation {
+        Some(o) => o,
+        None => {
+            let name = operation_name.unwrap_or_default();
+            return (
+                graphql_error(&format!("Unknown operation named \"{}\".", name)),
+                0,
+            );
+        }
+    };
+
+    if let Some(test_data) = api.test_data.as_array() {
+        for test_case in test_data {
+            if let Some(name) = test_case.get("operation") {
+                if name.as_str() != operation.name.as_deref() {
+                    continue;
+                }
+            }
+            let case_variables = test_case.get("variables").unwrap_or(&Value::Null);
+            if !api::is_value_equal(&variables, case_variables) {
+                continue;
+            }
+            let delay = test_case.get("delay").and_then(|v| v.as_u64()).unwrap_or(0);
+            let response = test_case.get("response").cloned().unwrap_or(Value::Null);
+            if response.get("data").is_some() || response.get("errors").is_some() {
+                return (response, delay);
+            }
+            return (json!({ "data": response }), delay);
+        }
+    }
+
+    let schema = match &api.graphql_schema {
+        Some(s) => s,
+        None => {
+            return (
+                graphql_error("Schema Error: the schema of this api is invalid"),
+                0,
+            )
+        }
+    };
+    let depth = selection_depth(&operation.selections, &document.fragments, &mut Vec::new());
+    if depth > MAX_DEPTH {
+        let e = format!("Query depth {} exceeds the max depth {}.", depth, MAX_DEPTH);
+        return (graphql_error(&e), 0);
+    }
+    let root_type = match operation.kind.as_str() {
+        "mutation" => &schema.mutation,
+        "subscription" => &schema.subscription,
+        _ => &schema.query,
+    };
+    if !schema.types.contains_key(root_type) {
+        let e = format!("Schema is not configured for {}s.", operation.kind);
+        return (graphql_error(&e), 0);
+    }
+
+    let mut all_variables = operation.defaults.clone();
+    if let Some(v) = variables.as_object() {
+        all_variables.extend(v.clone());
+    }
+    let mut resolver = Resolver {
+        schema,
+        fragments: &document.fragments,
+        variables: &all_variables,
+        mock: api.graphql.get("mock").unwrap_or(&Value::Null),
+        fragment_stack: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut data = Map::new();
+    resolver.resolve_selections(root_type, &operation.selections, &mut data);
+
+    let mut response = json!({ "data": data });
+    if !resolver.errors.is_empty() {
+        response["errors"] = Value::Array(resolver.errors);
+    }
+    (response, 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    const SCHEMA: &str = r#"
+        """订单"""
+        type Order implements Node {
+            id: ID!
+            status: Status
+            items(first: Int = 10): [Item!]!
+        }
+        interface Node { id: ID! }
+        type Item { name: String, price: Float }
+        enum Status { PAID SENT }
+        union SearchResult = Order | Item
+        type Query {
+            order(id: ID!): Order
+            search(keyword: String): [SearchResult]
+        }
+    "#;
+
+    fn graphql_api(test_data: Value) -> db::ApiData {
+        db::ApiData {
+            graphql: json!({ "schema": SCHEMA, "mock": {"Item.name": {"type": "string", "enum": ["apple"]}} }),
+            graphql_schema: parse_schema(SCHEMA).ok().map(Arc::new),
+            test_data,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn graphql_mock_test() {
+        let api = graphql_api(Value::Null);
+        let query = r#"
+            query GetOrder($id: ID!, $withItems: Boolean = true) {
+                first: order(id: $id) { ...OrderFields }
+                second: order(id: "2") { id status @skip(if: true) }
+            }
+            fragment OrderFields on Order {
+                id
+                __typename
+                items @include(if: $withItems) { name }
+            }
+        "#;
+        let (response, _) = get_graphql_response(&api, &json!({ "query": query }), &Value::Null);
+        let first = &response["data"]["first"];
+        assert_eq!("Order", first["__typename"]);
+        assert!(first["id"].is_string());
+        assert_eq!("apple", first["items"][0]["name"]);
+        assert!(response["data"]["second"].get("status").is_none());
+        assert!(response.get("errors").is_none());
+
+        let query = "{ search { ... on Item { price } ... on Order { status } __typename } }";
+        let (response, _) = get_graphql_response(&api, &json!({ "query": query }), &Value::Null);
+        for item in response["data"]["search"].as_array().unwrap() {
+            match item["__typename"].as_str().unwrap() {
+                "Item" => assert!(item["price"].is_number()),
+                _ => assert!(["PAID", "SENT"].contains(&item["status"].as_str().unwrap())),
+            }
+        }
+
+        let query = "{ order(id: 1) { total } }";
+        let (response, _) = get_graphql_response(&api, &json!({ "query": query }), &Value::Null);
+        assert_eq!(
+            "Cannot query field \"total\" on type \"Order\"",
+            response["errors"][0]["message"]
+        );
+
+        let query = format!("{{ {} id {} }}", "order { ".repeat(10), "} ".repeat(10));
+        let (response, _) = get_graphql_response(&api, &json!({ "query": query }), &Value::Null);
+        assert_eq!(
+            "Query depth 11 exceeds the max depth 10.",
+            response["errors"][0]["message"]
+        );
+    }
+
+    #[test]
+    fn graphql_test_data_test() {
+        let api = graphql_api(json!([
+            {"operation": "GetOrder", "variables": {"id": "1"}, "response": {"order": {"id": "1"}}},
+            {"operation": "GetOrder", "response": {"errors": [{"message": "id is required"}]}}
+        ]));
+        let query = "query GetOrder($id: ID) { order(id: $id) { id } }";
+        let request = json!({ "query": query, "variables": {"id": "1"} });
+        let (response, _) = get_graphql_response(&api, &request, &Value::Null);
+        assert_eq!(json!({"data": {"order": {"id": "1"}}}), response);
+
+        let request = json!({ "query": query, "operationName": "GetOrder" });
+        let (response, _) = get_graphql_response(&api, &request, &Value::Null);
+        assert_eq!("id is required", response["errors"][0]["message"]);
+
+        assert!(parse_schema("type Order { id: ID }").is_err());
+    }
+}
@@ -0,0 +1,298 @@
+//! 导入浏览器开发者工具导出的HAR文件
+//! 请求按url的模式分组, 数字, uuid和长的十六进制路径段是url参数, 抓到的请求和返回写成接口的test_data
+
+use regex::Regex;
+use reqwest::Url;
+use serde_json::Value;
+
+use crate::import::{self, Exchange, ImportApi, ImportDoc};
+
+/// HAR中一个接口的所有请求
+struct EntryGroup {
+    method: String,
+    url: String,
+    body_mode: String,
+    exchanges: Vec<Exchange>,
+}
+
+/// HAR文件转换为接口文档, 按第一个路径段分成文档文件
+pub fn import_har(content: &str) -> Result<Vec<ImportDoc>, String> {
+    let har: Value =
+        serde_json::from_str(content).map_err(|e| format!("parse har error: {}", e))?;
+    let entries = har
+        .pointer("/log/entries")
+        .and_then(|v| v.as_array())
+        .ok_or("the har file has no log.entries")?;
+
+    let mut groups: Vec<EntryGroup> = Vec::new();
+    let mut skipped = 0;
+    for entry in entries {
+        let (method, url, body_mode, exchange) = match parse_entry(entry) {
+            Some(v) => v,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        match groups
+            .iter_mut()
+            .find(|g| g.method == method && g.url == url)
+        {
+            Some(group) => {
+                // 相同的请求和返回只保留一个
+                if !group.exchanges.contains(&exchange) {
+                    group.exchanges.push(exchange);
+                }
+            }
+            None => groups.push(EntryGroup {
+                method,
+                url,
+                body_mode,
+                exchanges: vec![exchange],
+            }),
+        }
+    }
+    if skipped > 0 {
+        println!("skipped {} requests which are not json apis", skipped);
+    }
+
+    let mut docs: Vec<ImportDoc> = Vec::new();
+    for group in groups {
+        let api = ImportApi {
+            name: format!("{} {}", group.method, group.url),
+            method: group.method,
+            url: group.url,
+            body_mode: group.body_mode,
+            ..Default::default()
+        };
+        let exchanges: Vec<&Exchange> = group.exchanges.iter().collect();
+        let api = import::infer_api(api, &exchanges, &exchanges);
+        let name = doc_name(&api.url);
+        match docs.iter_mut().find(|doc| doc.name == name) {
+            Some(doc) => doc.apis.push(api),
+            None => docs.push(ImportDoc {
+                filename: format!("{}.json5", import::safe_filename(&name)),
+                name,
+                desc: "".to_string(),
+                apis: vec![api],
+            }),
+        }
+    }
+    Ok(docs)
+}
+
+/// 接口的请求方法, url模式, 请求体格式和这次请求的数据, 不是json接口的请求返回None
+fn parse_entry(entry: &Value) -> Option<(String, String, String, Exchange)> {
+    let request = entry.get("request")?;
+    let method = request.get("method")?.as_str()?.to_uppercase();
+    if method == "OPTIONS" {
+        return None;
+    }
+    let url = Url::parse(request.get("url")?.as_str()?).ok()?;
+    let response = entry.get("response").unwrap_or(&Value::Null);
+    let get_str = |value: &Value, pointer: &str| -> String {
+        value
+            .pointer(pointer)
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    };
+    let request_mime = get_str(request, "/postData/mimeType").to_lowercase();
+    let response_mime = get_str(response, "/content/mimeType").to_lowercase();
+    let is_form = request_mime.contains("form");
+    if !response_mime.contains("json") && !request_mime.contains("json") && !is_form {
+        return None;
+    }
+
+    let (pattern, url_param) = url_pattern(url.path());
+    let status = response.get("status").and_then(|v| v.as_u64()).unwrap_or(0);
+    // test_data中不能设置状态码, 失败的请求用状态码命名
+    let name = if status >= 300 {
+        format!("status {}", status)
+    } else {
+        "".to_string()
+    };
+    let mut exchange = Exchange {
+        name,
+        url: url.path().to_string(),
+        url_param,
+        status,
+        ..Default::default()
+    };
+    exchange.query = match request.get("queryString").and_then(|v| v.as_array()) {
+        Some(items) => name_values(items),
+        None => url.query_pairs().into_owned().collect(),
+    };
+
+    let body_mode = if request_mime.contains("multipart/form-data") {
+        "form-data"
+    } else if request_mime.contains("x-www-form-urlencoded") {
+        "urlencoded"
+    } else {
+        "json"
+    };
+    let text = get_str(request, "/postData/text");
+    if is_form {
+        exchange.form = match request
+            .pointer("/postData/params")
+            .and_then(|v| v.as_array())
+        {
+            Some(params) => name_values(params),
+            None => Url::parse(&format!("http://localhost/?{}", text))
+                .map(|u| u.query_pairs().into_owned().collect())
+                .unwrap_or_default(),
+        };
+    } else {
+        exchange.body = import::parse_json(&text);
+    }
+
+    let mut text = get_str(response, "/content/text");
+    if get_str(response, "/content/encoding") == "base64" {
+        text = base64::decode(text.trim())
+            .ok()
+            .and_then(|v| String::from_utf8(v).ok())
+            .unwrap_or_default();
+    }
+    exchange.response = import::parse_json(&text);
+    Some((method, pattern, body_mode.to_string(), exchange))
+}
+
+/// HAR中的 [{name, value}]
+fn name_values(items: &[Value]) -> Vec<(String, String)> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let name = item.get("name")?.as_str()?;
+            let value = item.get("value").and_then(|v| v.as_str()).unwrap_or("");
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// 数字, uuid和长的十六进制字符串是路径中的参数
+fn is_param_segment(segment: &str) -> bool {
+    let uuid = Regex::new(
+        r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+    )
+    .unwrap();
+    if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+        return true;
+    }
+    if uuid.is_match(segment) {
+        return true;
+    }
+    segment.len() >= 16
+        && segment.chars().all(|c| c.is_ascii_hexdigit())
+        && segment.chars().any(|c| c.is_ascii_digit())
+}
+
+/// 路径中的参数替换为 {id}, 前面的参数按前一个路径段命名, 如 /users/12/orders/5 => /users/{user_id}/orders/{id}
+fn url_pattern(path: &str) -> (String, Vec<(String, String)>) {
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    let last_param = segments.iter().rposition(|s| is_param_segment(s));
+
+    let mut result = Vec::new();
+    let mut params: Vec<(String, String)> = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        if !is_param_segment(segment) {
+            result.push(segment.to_string());
+            continue;
+        }
+        let prev = if i > 0 && !is_param_segment(segments[i - 1]) {
+            singular(segments[i - 1])
+        } else {
+            "".to_string()
+        };
+        let base = if Some(i) == last_param || prev.is_empty() {
+            "id".to_string()
+        } else {
+            format!("{}_id", prev)
+        };
+        let mut name = base.to_string();
+        let mut n = 2;
+        while params.iter().any(|(k, _)| *k == name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        result.push(format!("{{{}}}", name));
+        params.push((name, segment.to_string()));
+    }
+    (format!("/{}", result.join("/")), params)
+}
+
+fn singular(word: &str) -> String {
+    let word: String = word
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_lowercase();
+    if word.ends_with("ies") && word.len() > 3 {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.ends_with('s') && !word.ends_with("ss") && word.len() > 1 {
+        word[..word.len() - 1].to_string()
+    } else {
+        word
+    }
+}
+
+/// 文档按第一个不是api和版本号的路径段命名, 如 /api/v1/users/ => users
+fn doc_name(url: &str) -> String {
+    let version = Regex::new(r"^[vV]\d+(\.\d+)*$").unwrap();
+    url.split('/')
+        .find(|s| {
+            !s.is_empty()
+                && !s.starts_with('{')
+                && s.to_lowercase() != "api"
+                && !version.is_match(s)
+        })
+        .unwrap_or("index")
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn import_har_test() {
+        let entry = |method: &str, url: &str, body: Value, response: Value| {
+            json!({
+                "request": {
+                    "method": method,
+                    "url": url,
+                    "postData": {"mimeType": "application/json", "text": body.to_string()}
+                },
+                "response": {
+                    "status": 200,
+                    "content": {"mimeType": "application/json", "text": response.to_string()}
+                }
+            })
+        };
+        let har = json!({"log": {"entries": [
+            entry("GET", "https://shop.example.com/api/v1/users/12/orders/5", json!(null), json!({"id": 5})),
+            entry("GET", "https://shop.example.com/api/v1/users/7/orders/9", json!(null), json!({"id": 9, "paid": true})),
+            entry("GET", "https://shop.example.com/api/v1/users/7/orders/9", json!(null), json!({"id": 9, "paid": true})),
+            {"request": {"method": "GET", "url": "https://shop.example.com/app.js"},
+             "response": {"status": 200, "content": {"mimeType": "application/javascript", "text": ""}}}
+        ]}});
+        let docs = import_har(&har.to_string()).unwrap();
+        assert_eq!(1, docs.len());
+        assert_eq!("users.json5", docs[0].filename);
+        let api = &docs[0].apis[0];
+        assert_eq!("/api/v1/users/{user_id}/orders/{id}", api.url);
+        assert_eq!(
+            json!({"user_id": {"type": "int"}, "id": {"type": "int"}}),
+            api.url_param
+        );
+        assert_eq!(
+            json!({"id": {"type": "int"}, "paid": {"type": "bool", "required": false}}),
+            api.response
+        );
+        assert_eq!(2, api.test_data.len());
+        assert_eq!(
+            json!({"url": "/api/v1/users/12/orders/5", "response": {"id": 5}}),
+            api.test_data[0]
+        );
+    }
+}
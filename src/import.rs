@@ -0,0 +1,540 @@
+//! 从Postman collection和HAR文件导入接口文档
+//! 根据请求和返回中出现的值推断字段的类型, 写成json5格式的接口文档, 已经存在的文件不会覆盖
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::har;
+use crate::postman;
+use crate::Import;
+
+/// json5中一行能放下的对象
+const INLINE_WIDTH: usize = 80;
+
+/// 文档中常用的属性按这个顺序写在前面, 其它的按字母顺序
+const KEY_ORDER: [&str; 13] = [
+    "$type",
+    "name",
+    "desc",
+    "type",
+    "method",
+    "url",
+    "body_mode",
+    "url_param",
+    "query",
+    "body",
+    "response",
+    "test_data",
+    "apis",
+];
+
+/// 导入的一个接口
+#[derive(Debug, Clone, Default)]
+pub struct ImportApi {
+    pub name: String,
+    pub desc: String,
+    pub method: String,
+    pub url: String,
+    pub body_mode: String,
+    pub url_param: Value,
+    pub query: Value,
+    pub body: Value,
+    pub response: Value,
+    pub test_data: Vec<Value>,
+}
+
+/// 导入的一个接口文档文件
+#[derive(Debug, Clone, Default)]
+pub struct ImportDoc {
+    /// 相对于导入目录的文件名
+    pub filename: String,
+    pub name: String,
+    pub desc: String,
+    pub apis: Vec<ImportApi>,
+}
+
+/// 抓到的一次请求和返回
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Exchange {
+    /// 作为用例时的名称
+    pub name: String,
+    /// 请求的地址, 不含参数
+    pub url: String,
+    pub url_param: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    /// json的请求体, 表单的请求体在form中
+    pub body: Option<Value>,
+    pub form: Vec<(String, String)>,
+    /// 返回的状态码, 0是没有返回
+    pub status: u64,
+    pub response: Option<Value>,
+}
+
+/// 读取文件导入接口文档, 默认写到文档根目录
+pub fn run_import(conf: &Import, root: &Path) -> Result<(), String> {
+    let content =
+        fs::read_to_string(&conf.file).map_err(|e| format!("read {} error: {}", conf.file, e))?;
+    let docs = match conf.format.as_str() {
+        "postman" => postman::import_collection(&content)?,
+        "har" => har::import_har(&content)?,
+        format => {
+            return Err(format!(
+                "unsupported import format {}, available: postman, har",
+                format
+            ))
+        }
+    };
+    let out = match &conf.out {
+        Some(out) => Path::new(out).to_path_buf(),
+        None => root.to_path_buf(),
+    };
+    write_docs(&docs, &out)
+}
+
+fn write_docs(docs: &[ImportDoc], out: &Path) -> Result<(), String> {
+    let mut count = 0;
+    for doc in docs {
+        let path = out.join(&doc.filename);
+        if path.exists() {
+            println!("{} exists, skipped", path.display());
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("create {} error: {}", parent.display(), e))?;
+        }
+        fs::write(&path, format!("{}\n", to_json5(&doc_value(doc), 0)))
+            .map_err(|e| format!("write {} error: {}", path.display(), e))?;
+        println!("{} apis imported to {}", doc.apis.len(), path.display());
+        count += 1;
+    }
+    println!("imported {} doc files", count);
+    Ok(())
+}
+
+/// 接口文档文件的内容, 没有设置的属性不写
+pub fn doc_value(doc: &ImportDoc) -> Value {
+    let mut apis = Vec::new();
+    for api in &doc.apis {
+        let mut value = Map::new();
+        value.insert("name".to_string(), json!(api.name));
+        if !api.desc.is_empty() {
+            value.insert("desc".to_string(), json!(api.desc));
+        }
+        value.insert("method".to_string(), json!(api.method));
+        value.insert("url".to_string(), json!(api.url));
+        if !api.body_mode.is_empty() && api.body_mode != "json" {
+            value.insert("body_mode".to_string(), json!(api.body_mode));
+        }
+        for (key, model) in &[
+            ("url_param", &api.url_param),
+            ("query", &api.query),
+            ("body", &api.body),
+            ("response", &api.response),
+        ] {
+            if !model.is_null() {
+                value.insert(key.to_string(), (*model).clone());
+            }
+        }
+        if !api.test_data.is_empty() {
+            value.insert("test_data".to_string(), json!(api.test_data));
+        }
+        apis.push(Value::Object(value));
+    }
+
+    let mut value = Map::new();
+    value.insert("name".to_string(), json!(doc.name));
+    if !doc.desc.is_empty() {
+        value.insert("desc".to_string(), json!(doc.desc));
+    }
+    value.insert("apis".to_string(), Value::Array(apis));
+    Value::Object(value)
+}
+
+/// 按多次请求推断接口的字段, cases写成test_data
+/// 接口的名称, 请求方法, url和body_mode需要先设置好
+pub fn infer_api(mut api: ImportApi, samples: &[&Exchange], cases: &[&Exchange]) -> ImportApi {
+    let pairs = |get: fn(&Exchange) -> &Vec<(String, String)>| -> Vec<Vec<(String, String)>> {
+        samples.iter().map(|e| get(e).clone()).collect()
+    };
+    api.url_param = infer_text_model(&pairs(|e| &e.url_param));
+    api.query = infer_text_model(&pairs(|e| &e.query));
+    api.body = if api.body_mode.is_empty() || api.body_mode == "json" {
+        let bodies: Vec<&Value> = samples.iter().filter_map(|e| e.body.as_ref()).collect();
+        infer_model(&bodies)
+    } else {
+        infer_text_model(&pairs(|e| &e.form))
+    };
+    // 返回的模型按成功的请求推断, 都没有成功时使用所有的返回
+    let responses: Vec<&Value> = samples
+        .iter()
+        .filter(|e| e.status < 300)
+        .filter_map(|e| e.response.as_ref())
+        .collect();
+    api.response = if responses.is_empty() {
+        let all: Vec<&Value> = samples.iter().filter_map(|e| e.response.as_ref()).collect();
+        infer_model(&all)
+    } else {
+        infer_model(&responses)
+    };
+
+    for exchange in cases {
+        let mut case = Map::new();
+        if !exchange.name.is_empty() {
+            case.insert("name".to_string(), json!(exchange.name));
+        }
+        if exchange.url != api.url && !exchange.url.contains('{') {
+            case.insert("url".to_string(), json!(exchange.url));
+        }
+        if !exchange.query.is_empty() {
+            case.insert(
+                "query".to_string(),
+                typed_object(&exchange.query, &api.query),
+            );
+        }
+        if let Some(body) = &exchange.body {
+            case.insert("body".to_string(), body.clone());
+        } else if !exchange.form.is_empty() {
+            // 表单的值在匹配用例时不转换类型
+            let form: Map<String, Value> = exchange
+                .form
+                .iter()
+                .map(|(k, v)| (k.to_string(), json!(v)))
+                .collect();
+            case.insert("body".to_string(), Value::Object(form));
+        }
+        if let Some(response) = &exchange.response {
+            case.insert("response".to_string(), response.clone());
+        }
+        api.test_data.push(Value::Object(case));
+    }
+    api
+}
+
+/// 把多次出现的值合并推断成字段模型, 对象和数组之外的值返回Null
+pub fn infer_model(samples: &[&Value]) -> Value {
+    if samples.iter().all(|v| v.is_object()) && !samples.is_empty() {
+        let mut model = infer_field(samples);
+        if let Value::Object(obj) = &mut model {
+            obj.remove("$type");
+        }
+        return model;
+    }
+    if samples.iter().all(|v| v.is_array()) && !samples.is_empty() {
+        return infer_field(samples);
+    }
+    Value::Null
+}
+
+/// 字段的类型, 每次都出现的字段是必填的
+fn infer_field(samples: &[&Value]) -> Value {
+    let values: Vec<&Value> = samples.iter().copied().filter(|v| !v.is_null()).collect();
+    if values.is_empty() {
+        return json!({"type": "string"});
+    }
+
+    if values.iter().all(|v| v.is_object()) {
+        let mut keys: Vec<&String> = Vec::new();
+        for value in &values {
+            for key in value.as_object().into_iter().flat_map(|o| o.keys()) {
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+        let mut model = Map::new();
+        model.insert("$type".to_string(), json!("object"));
+        for key in keys {
+            let field_values: Vec<&Value> = values.iter().filter_map(|v| v.get(key)).collect();
+            let required = values
+                .iter()
+                .all(|v| v.get(key).map(|f| !f.is_null()).unwrap_or(false));
+            let mut attr = infer_field(&field_values);
+            if !required {
+                set_optional(&mut attr);
+            }
+            model.insert(key.to_string(), attr);
+        }
+        return Value::Object(model);
+    }
+
+    if values.iter().all(|v| v.is_array()) {
+        let items: Vec<&Value> = values
+            .iter()
+            .filter_map(|v| v.as_array())
+            .flatten()
+            .collect();
+        if items.is_empty() {
+            return json!([]);
+        }
+        return json!([infer_field(&items)]);
+    }
+
+    json!({ "type": infer_scalar_type(&values) })
+}
+
+/// 不是每次都出现的字段不是必填的, 对象字段使用$required
+pub fn set_optional(attr: &mut Value) {
+    if let Value::Object(obj) = attr {
+        if obj.contains_key("$type") {
+            obj.insert("$required".to_string(), json!(false));
+        } else {
+            obj.insert("required".to_string(), json!(false));
+        }
+    }
+}
+
+fn infer_scalar_type(values: &[&Value]) -> &'static str {
+    if values.iter().all(|v| v.is_boolean()) {
+        return "bool";
+    }
+    if values.iter().all(|v| v.is_i64() || v.is_u64()) {
+        return "int";
+    }
+    if values.iter().all(|v| v.is_number()) {
+        return "float";
+    }
+    let strings: Vec<&str> = values.iter().filter_map(|v| v.as_str()).collect();
+    if strings.len() == values.len() {
+        return infer_string_type(&strings);
+    }
+    "string"
+}
+
+/// 字符串的格式, 所有的值都符合时才使用对应的类型
+pub fn infer_string_type(values: &[&str]) -> &'static str {
+    let patterns: [(&str, &str); 5] = [
+        (
+            "uuid",
+            r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+        ),
+        ("date", r"^\d{4}-\d{2}-\d{2}$"),
+        ("datetime", r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}$"),
+        ("email", r"^[^@\s]+@[^@\s]+\.[^@\s]+$"),
+        ("url", r"^https?://\S+$"),
+    ];
+    if values.is_empty() {
+        return "string";
+    }
+    for (field_type, pattern) in patterns.iter() {
+        let re = Regex::new(pattern).unwrap();
+        if values.iter().all(|v| re.is_match(v)) {
+            return field_type;
+        }
+    }
+    "string"
+}
+
+/// url参数, query和表单中的值都是文本, 数字和布尔值按文本的内容推断
+pub fn text_value(text: &str) -> Value {
+    if let Ok(v) = text.parse::<i64>() {
+        if !text.starts_with('0') || text == "0" {
+            return json!(v);
+        }
+    }
+    if let Ok(v) = text.parse::<f64>() {
+        if text.contains('.') && !text.starts_with('.') && v.is_finite() {
+            return json!(v);
+        }
+    }
+    match text {
+        "true" => json!(true),
+        "false" => json!(false),
+        _ => json!(text),
+    }
+}
+
+/// 文本的键值对推断成字段模型, 同一个key出现多次时是数组
+pub fn infer_text_model(samples: &[Vec<(String, String)>]) -> Value {
+    let objects: Vec<Value> = samples
+        .iter()
+        .filter(|pairs| !pairs.is_empty())
+        .map(|pairs| text_object(pairs))
+        .collect();
+    let objects: Vec<&Value> = objects.iter().collect();
+    infer_model(&objects)
+}
+
+/// 文本的键值对转换成对象, 值按文本内容转换类型
+pub fn text_object(pairs: &[(String, String)]) -> Value {
+    let mut obj = Map::new();
+    for (key, value) in pairs {
+        let value = text_value(value);
+        match obj.get_mut(key) {
+            Some(Value::Array(items)) => items.push(value),
+            Some(old) => *old = json!([old.clone(), value]),
+            None => {
+                obj.insert(key.to_string(), value);
+            }
+        }
+    }
+    Value::Object(obj)
+}
+
+/// 文本的键值对按字段模型转换类型, 和mock服务器匹配用例时的转换一致
+pub fn typed_object(pairs: &[(String, String)], model: &Value) -> Value {
+    let mut obj = Map::new();
+    for (key, value) in pairs {
+        let field_type = model
+            .get(key)
+            .and_then(|attr| attr.get("type").or_else(|| attr.get(0)?.get("type")))
+            .and_then(|v| v.as_str())
+            .unwrap_or("string");
+        let value = match field_type {
+            "int" | "float" | "bool" => text_value(value),
+            _ => json!(value),
+        };
+        obj.insert(key.to_string(), value);
+    }
+    Value::Object(obj)
+}
+
+/// 解析json或者json5文本, 只要对象和数组
+pub fn parse_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    serde_json::from_str(text)
+        .ok()
+        .or_else(|| json5::from_str(text).ok())
+        .filter(|v: &Value| v.is_object() || v.is_array())
+}
+
+/// 文件名中不能使用的字符替换为_
+pub fn safe_filename(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_matches('.').trim().to_string();
+    if name.is_empty() {
+        "api".to_string()
+    } else {
+        name
+    }
+}
+
+/// 格式化为json5, 键名是标识符时不加引号, 简单的对象放在一行
+pub fn to_json5(value: &Value, indent: usize) -> String {
+    let inline = to_json5_inline(value);
+    if inline.len() + indent * 4 <= INLINE_WIDTH && is_simple(value) {
+        return inline;
+    }
+    let pad = "    ".repeat(indent + 1);
+    let end_pad = "    ".repeat(indent);
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            let items: Vec<String> = ordered_items(obj)
+                .into_iter()
+                .map(|(k, v)| format!("{}{}: {}", pad, json5_key(k), to_json5(v, indent + 1)))
+                .collect();
+            format!("{{\n{},\n{}}}", items.join(",\n"), end_pad)
+        }
+        Value::Array(items) if !items.is_empty() => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|v| format!("{}{}", pad, to_json5(v, indent + 1)))
+                .collect();
+            format!("[\n{},\n{}]", items.join(",\n"), end_pad)
+        }
+        _ => inline,
+    }
+}
+
+/// 只包含一层简单值的对象或数组
+fn is_simple(value: &Value) -> bool {
+    match value {
+        Value::Object(obj) => obj.values().all(|v| match v {
+            Value::Object(o) => o.is_empty(),
+            Value::Array(a) => a.is_empty(),
+            _ => true,
+        }),
+        Value::Array(items) => items.iter().all(|v| is_simple(v) && !v.is_array()),
+        _ => true,
+    }
+}
+
+fn to_json5_inline(value: &Value) -> String {
+    match value {
+        Value::Object(obj) => {
+            let items: Vec<String> = ordered_items(obj)
+                .into_iter()
+                .map(|(k, v)| format!("{}: {}", json5_key(k), to_json5_inline(v)))
+                .collect();
+            format!("{{{}}}", items.join(", "))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(to_json5_inline).collect();
+            format!("[{}]", items.join(", "))
+        }
+        v => v.to_string(),
+    }
+}
+
+fn ordered_items(obj: &Map<String, Value>) -> Vec<(&String, &Value)> {
+    let mut items: Vec<(&String, &Value)> = obj.iter().collect();
+    items.sort_by_key(|(k, _)| {
+        KEY_ORDER
+            .iter()
+            .position(|key| key == k)
+            .unwrap_or(KEY_ORDER.len())
+    });
+    items
+}
+
+fn json5_key(key: &str) -> String {
+    let is_ident = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_ident {
+        key.to_string()
+    } else {
+        json!(key).to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn infer_model_test() {
+        let a =
+            json!({"id": 1, "name": "a", "tags": ["x"], "owner": {"email": "a@b.com"}, "score": 1});
+        let b =
+            json!({"id": 2, "name": null, "tags": [], "owner": {"email": "c@d.com"}, "score": 2.5});
+        let model = infer_model(&[&a, &b]);
+        assert_eq!(
+            json!({
+                "id": {"type": "int"},
+                "name": {"type": "string", "required": false},
+                "tags": [{"type": "string"}],
+                "owner": {"$type": "object", "email": {"type": "email"}},
+                "score": {"type": "float"},
+            }),
+            model
+        );
+        assert_eq!(
+            "{\n    id: {type: \"int\"},\n    \"x-y\": [{type: \"string\"}],\n}",
+            to_json5(
+                &json!({"id": {"type": "int"}, "x-y": [{"type": "string"}]}),
+                0
+            )
+        );
+        assert_eq!(json!(12), text_value("12"));
+        assert_eq!(json!("012"), text_value("012"));
+    }
+}
@@ -0,0 +1,222 @@
+//! 文档在线浏览页面的实时刷新通知
+//! 文件监控线程在接口文档改动、删除或者解析出错时，通过内部的websocket通道推送给打开的文档页面
+//! 与用户在接口文档中定义的websocket接口是完全分开的
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use actix::prelude::*;
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use chrono::Local;
+use rand::{self, rngs::ThreadRng, Rng};
+use serde_json::json;
+
+use crate::server;
+
+/// 内部实时刷新通道的地址
+pub const LIVE_RELOAD_URL: &str = "/__api_docs/ws/";
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 文档文件变动的事件
+#[derive(Message, Debug, Clone)]
+#[rtype(result = "()")]
+pub enum DocEvent {
+    /// 文件重新加载成功
+    Update(String),
+    /// 文件被删除
+    Delete(String),
+    /// 文件解析出错, (文件名, 错误信息)
+    Error(String, String),
+}
+
+impl DocEvent {
+    fn to_json_string(&self) -> String {
+        let time = Local::now().to_rfc3339();
+        let v = match self {
+            DocEvent::Update(filename) => {
+                json!({"event": "update", "filename": filename, "time": time})
+            }
+            DocEvent::Delete(filename) => {
+                json!({"event": "delete", "filename": filename, "time": time})
+            }
+            DocEvent::Error(filename, error) => {
+                json!({"event": "error", "filename": filename, "error": error, "time": time})
+            }
+        };
+        v.to_string()
+    }
+}
+
+/// 管理所有打开的文档页面连接，把文件变动事件广播出去
+pub struct LiveReloadServer {
+    sessions: HashMap<usize, Recipient<server::Message>>,
+    rng: ThreadRng,
+}
+
+impl Default for LiveReloadServer {
+    fn default() -> LiveReloadServer {
+        LiveReloadServer {
+            sessions: HashMap::new(),
+            rng: rand::thread_rng(),
+        }
+    }
+}
+
+impl Actor for LiveReloadServer {
+    type Context = Context<Self>;
+}
+
+impl Handler<server::Connect> for LiveReloadServer {
+    type Result = usize;
+
+    fn handle(&mut self, msg: server::Connect, _: &mut Context<Self>) -> Self::Result {
+        let id = self.rng.gen::<usize>();
+        self.sessions.insert(id, msg.addr);
+        id
+    }
+}
+
+impl Handler<server::Disconnect> for LiveReloadServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: server::Disconnect, _: &mut Context<Self>) {
+        self.sessions.remove(&msg.id);
+    }
+}
+
+impl Handler<DocEvent> for LiveReloadServer {
+    type Result = ();
+
+    fn handle(&mut self, msg: DocEvent, _: &mut Context<Self>) {
+        let message = msg.to_json_string();
+        for addr in self.sessions.values() {
+            let _ = addr.do_send(server::Message(message.clone()));
+        }
+    }
+}
+
+/// 一个文档页面的websocket连接，只接收服务器推送，不处理页面发来的消息
+pub struct LiveReloadSession {
+    id: usize,
+    hb: Instant,
+    addr: Addr<LiveReloadServer>,
+}
+
+impl Actor for LiveReloadSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.hb(ctx);
+
+        let addr = ctx.address();
+        self.addr
+            .send(server::Connect {
+                addr: addr.recipient(),
+            })
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                match res {
+                    Ok(res) => act.id = res,
+                    _ => ctx.stop(),
+                }
+                fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        self.addr.do_send(server::Disconnect { id: self.id });
+        Running::Stop
+    }
+}
+
+impl Handler<server::Message> for LiveReloadSession {
+    type Result = ();
+
+    fn handle(&mut self, msg: server::Message, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for LiveReloadSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Close(_)) | Err(_) => {
+                ctx.stop();
+            }
+            _ => (),
+        }
+    }
+}
+
+impl LiveReloadSession {
+    /// 定时发送ping，超时没有回应就断开连接
+    fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+                act.addr.do_send(server::Disconnect { id: act.id });
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+}
+
+/// 文档页面连接实时刷新通道
+pub async fn live_reload_handle(
+    req: HttpRequest,
+    stream: web::Payload,
+    srv: web::Data<Addr<LiveReloadServer>>,
+) -> Result<HttpResponse, Error> {
+    ws::start(
+        LiveReloadSession {
+            id: 0,
+            hb: Instant::now(),
+            addr: srv.get_ref().clone(),
+        },
+        &req,
+        stream,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn doc_event_json_test() {
+        let parse = |event: DocEvent| -> Value {
+            let mut v: Value = serde_json::from_str(&event.to_json_string()).unwrap();
+            assert!(v["time"].is_string());
+            v.as_object_mut().unwrap().remove("time");
+            v
+        };
+        assert_eq!(
+            json!({"event": "update", "filename": "user.json5"}),
+            parse(DocEvent::Update("user.json5".to_string()))
+        );
+        assert_eq!(
+            json!({"event": "delete", "filename": "user.json5"}),
+            parse(DocEvent::Delete("user.json5".to_string()))
+        );
+        assert_eq!(
+            json!({"event": "error", "filename": "user.json5", "error": "bad json"}),
+            parse(DocEvent::Error(
+                "user.json5".to_string(),
+                "bad json".to_string()
+            ))
+        );
+    }
+}
@@ -1,13 +1,31 @@
-use actix::Actor;
-use actix_web::{middleware, web, App, HttpServer};
-use std::sync::Mutex;
+use actix::{Actor, Addr};
+use actix_web::{guard, middleware, web, App, HttpServer};
+use arc_swap::ArcSwap;
 use structopt::StructOpt;
 mod api;
 mod client;
+mod codegen;
 mod db;
+mod diff;
+mod export;
+mod graphql;
+mod har;
+mod import;
+mod jwt;
+mod live_reload;
 mod mock;
+mod oauth;
+mod perms;
+mod postman;
+mod project;
+mod router;
+mod schema;
+mod search;
 mod server;
+mod snippets;
+mod stream;
 mod utils;
+mod version;
 mod websocket;
 
 #[actix_rt::main]
@@ -22,6 +40,71 @@ async fn main() -> std::io::Result<()> {
             Command::Test(t) => {
                 test_conf = Some(t);
             }
+            Command::Gen(g) => {
+                // 生成客户端代码
+                if let Err(e) = codegen::run_gen(&g, &project::Project::current().root) {
+                    log::error!("{}", e);
+                }
+                return Ok(());
+            }
+            Command::Perms(p) => {
+                // 输出权限矩阵
+                let project = project::Project::current();
+                let db = db::Database::load(&project.root);
+                let matrix = perms::get_perm_matrix(&db);
+                match p.format.as_str() {
+                    "json" => println!("{}", serde_json::to_string_pretty(&matrix).unwrap()),
+                    "html" => match perms::to_html(&matrix, &project.root) {
+                        Ok(html) => println!("{}", html),
+                        Err(e) => log::error!("{}", e),
+                    },
+                    _ => print!("{}", perms::to_csv(&matrix)),
+                }
+                return Ok(());
+            }
+            Command::Diff(d) => {
+                // 比较两个版本的接口文档
+                let root = project::Project::current().root;
+                match diff::run_diff(&d.from, &d.to, &root) {
+                    Ok(report) => {
+                        match d.format.as_str() {
+                            "json" => {
+                                println!("{}", serde_json::to_string_pretty(&report).unwrap())
+                            }
+                            _ => print!("{}", diff::to_markdown(&report)),
+                        }
+                        if d.fail_on_breaking && report.breaking > 0 {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => log::error!("{}", e),
+                }
+                return Ok(());
+            }
+            Command::Export(e) => {
+                // 导出静态文档
+                let root = project::Project::current().root;
+                let result = match e.format.as_str() {
+                    "html" => export::export_html(&root, std::path::Path::new(&e.out)),
+                    "schema" => schema::export_schema(&root, std::path::Path::new(&e.out)),
+                    "postman" => postman::export_postman(&root, std::path::Path::new(&e.out)),
+                    format => Err(format!(
+                        "unsupported export format {}, available: html, schema, postman",
+                        format
+                    )),
+                };
+                if let Err(e) = result {
+                    log::error!("{}", e);
+                }
+                return Ok(());
+            }
+            Command::Import(i) => {
+                // 从其它格式导入接口文档
+                if let Err(e) = import::run_import(&i, &project::Project::current().root) {
+                    log::error!("{}", e);
+                }
+                return Ok(());
+            }
             Command::Token(t) => {
                 // 测试正则生成字符串
                 // generate token
@@ -33,47 +116,103 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
-    match dirs::home_dir() {
-        Some(path) => {
-            let current_dir =
-                std::env::current_dir().expect("Failed to determine current directory");
-            if path == current_dir {
-                log::error!("You can not run panda api on double click, you need run it on shell with command at api docs folder. ex: ./panda , the more at https://github.com/arlicle/panda-api");
+    let mut projects = Vec::new();
+    // 按域名挂载的项目放在前面，这个域名的请求都交给这个项目处理
+    for arg in &conf.project_host {
+        match project::Project::from_host_arg(arg) {
+            Ok(p) => projects.push(p),
+            Err(e) => {
+                log::error!("{}", e);
                 return Ok(());
             }
         }
-        None => log::error!("Impossible to get your home dir!"),
     }
+    for arg in &conf.project {
+        match project::Project::from_prefix_arg(arg) {
+            Ok(p) => projects.push(p),
+            Err(e) => {
+                log::error!("{}", e);
+                return Ok(());
+            }
+        }
+    }
+    // 没有指定项目的时候，当前目录就是唯一的项目
+    let is_multi_project = !projects.is_empty();
 
-    let db = db::Database::load();
-
-    let websocket_api = &db.websocket_api.clone();
-    let w = websocket_api.lock().unwrap();
-
-    let websocket_uri = w.url.clone();
-    let web_db = web::Data::new(Mutex::new(db));
+    if !is_multi_project || test_conf.is_some() {
+        match dirs::home_dir() {
+            Some(path) => {
+                let current_dir =
+                    std::env::current_dir().expect("Failed to determine current directory");
+                if path == current_dir {
+                    log::error!("You can not run panda api on double click, you need run it on shell with command at api docs folder. ex: ./panda , the more at https://github.com/arlicle/panda-api");
+                    return Ok(());
+                }
+            }
+            None => log::error!("Impossible to get your home dir!"),
+        }
+    }
 
     if let Some(test_conf) = test_conf {
-        client::test::run_test(test_conf, web_db.clone()).await;
+        let project = project::Project::current();
+        let web_db = web::Data::new(ArcSwap::from_pointee(db::Database::load(&project.root)));
+        client::test::run_test(test_conf, web_db).await;
         return Ok(());
     }
 
-    utils::watch_api_docs_change(web_db.clone());
+    if !is_multi_project {
+        projects.push(project::Project::current());
+    }
+
+    let mut project_apps = Vec::new();
+    for project in projects {
+        let db = db::Database::load(&project.root);
+        let web_db = web::Data::new(ArcSwap::from_pointee(db));
 
-    let server = server::ChatServer::default();
-    let server = server.start();
+        let live_reload_server = live_reload::LiveReloadServer::default().start();
+        utils::watch_api_docs_change(
+            project.root.clone(),
+            web_db.clone(),
+            live_reload_server.clone(),
+        );
+
+        if is_multi_project {
+            log::info!(
+                "project {} {} at {}",
+                project.name,
+                project.root.display(),
+                project.doc_url(conf.port as u16)
+            );
+        }
+        project_apps.push(ProjectApp {
+            project,
+            web_db,
+            live_reload_server,
+            chat_server: server::ChatServer::default().start(),
+        });
+    }
+
+    let project_list: Vec<(project::Project, web::Data<db::DataStore>)> = project_apps
+        .iter()
+        .map(|x| (x.project.clone(), x.web_db.clone()))
+        .collect();
+    let project_list = web::Data::new(project_list);
+    let used_codes = web::Data::new(oauth::UsedCodes::default());
 
     HttpServer::new(move || {
-        App::new()
-            .data(server.clone())
-            .app_data(web_db.clone())
-            .wrap(
-                middleware::Logger::default()
-                    .exclude("/__api_docs/")
-                    .exclude("/__api_docs/api_data/")
-                    .exclude("/__api_docs/_data/")
-                    .exclude("/__api_docs/theme/"),
-            )
+        let mut logger = middleware::Logger::default().exclude(live_reload::LIVE_RELOAD_URL);
+        for p in &project_apps {
+            for path in &[
+                "/__api_docs/",
+                "/__api_docs/api_data/",
+                "/__api_docs/_data/",
+                live_reload::LIVE_RELOAD_URL,
+            ] {
+                logger = logger.exclude(format!("{}{}", p.project.prefix, path));
+            }
+        }
+        let mut app = App::new()
+            .wrap(logger.exclude("/__api_docs/theme/"))
             //            .wrap(middleware::Logger::new("%a %{User-Agent}i"))
             .wrap(
                 middleware::DefaultHeaders::new()
@@ -82,26 +221,97 @@ async fn main() -> std::io::Result<()> {
                     .header("Access-Control-Allow-Origin", "*")
                     .header("Access-Control-Allow-Methods", "*"),
             )
-            .service(web::resource("/__api_docs/").route(web::get().to(api::get_api_doc_basic)))
-            .service(
-                web::resource("/__api_docs/api_data/").route(web::get().to(api::get_api_doc_data)),
-            )
-            .service(
-                web::resource("/__api_docs/_data/")
-                    .route(web::get().to(api::get_api_doc_schema_data)),
-            )
+            .app_data(used_codes.clone());
+
+        if !is_multi_project {
+            let p = &project_apps[0];
+            return app
+                .data(p.chat_server.clone())
+                .data(p.live_reload_server.clone())
+                .data(p.project.clone())
+                .app_data(p.web_db.clone())
+                .configure(project_services);
+        }
+
+        // 多项目时，每个项目挂载在自己的前缀或者域名下，有各自的数据
+        for p in &project_apps {
+            let scope = match &p.project.host {
+                Some(host) => web::scope("").guard(guard::Host(host)),
+                None => web::scope(&p.project.prefix),
+            };
+            app = app.service(
+                scope
+                    .data(p.chat_server.clone())
+                    .data(p.live_reload_server.clone())
+                    .data(p.project.clone())
+                    .app_data(p.web_db.clone())
+                    .configure(project_services),
+            );
+        }
+        app.app_data(project_list.clone())
+            .data(project::Project::current())
+            .service(web::resource("/__api_docs/projects/").route(web::get().to(api::get_projects)))
             .service(web::resource("/__api_docs/theme/*").route(web::get().to(api::theme_view)))
-            .service(web::resource("/").route(web::get().to(api::theme_view)))
-            .service(web::resource("/media/*").route(web::get().to(api::static_file_view)))
-            .service(web::resource("/_upload/*").route(web::get().to(api::upload_file_view)))
-            .service(web::resource(&websocket_uri).to(api::websocket_handle))
-            .service(web::resource("/*").to(api::action_handle))
+            .service(web::resource("/").route(web::get().to(api::projects_view)))
     })
     .bind(format!("{}:{}", conf.host, conf.port))?
     .run()
     .await
 }
 
+/// 一个项目运行需要的数据
+#[derive(Clone)]
+struct ProjectApp {
+    project: project::Project,
+    web_db: web::Data<db::DataStore>,
+    live_reload_server: Addr<live_reload::LiveReloadServer>,
+    chat_server: Addr<server::ChatServer>,
+}
+
+/// 一个项目的所有服务，单项目时挂载在根路径，多项目时挂载在项目的前缀或者域名下
+fn project_services(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/__api_docs/").route(web::get().to(api::get_api_doc_basic)))
+        .service(web::resource("/__api_docs/api_data/").route(web::get().to(api::get_api_doc_data)))
+        .service(
+            web::resource("/__api_docs/_data/").route(web::get().to(api::get_api_doc_schema_data)),
+        )
+        .service(
+            web::resource("/__api_docs/push/").route(web::post().to(api::websocket_push_handle)),
+        )
+        .service(web::resource("/__api_docs/perms/").route(web::get().to(perms::perm_matrix_view)))
+        .service(
+            web::resource(["/__api_docs/search", "/__api_docs/search/"])
+                .route(web::get().to(search::search_view)),
+        )
+        .service(
+            web::resource(["/__api_docs/schema", "/__api_docs/schema/"])
+                .route(web::get().to(schema::schema_view)),
+        )
+        .service(web::resource(live_reload::LIVE_RELOAD_URL).to(live_reload::live_reload_handle))
+        .service(
+            web::scope(oauth::OAUTH_PATH)
+                .route(
+                    "/.well-known/openid-configuration",
+                    web::get().to(oauth::discovery_view),
+                )
+                .route("/authorize", web::get().to(oauth::authorize_view))
+                .route("/token", web::post().to(oauth::token_view))
+                .route("/userinfo", web::get().to(oauth::userinfo_view))
+                .route("/userinfo", web::post().to(oauth::userinfo_view))
+                .route("/jwks", web::get().to(oauth::jwks_view)),
+        )
+        .service(web::resource("/__api_docs/theme/*").route(web::get().to(api::theme_view)))
+        .service(web::resource("/").route(web::get().to(api::project_home_view)))
+        .service(web::resource("/media/*").route(web::get().to(api::static_file_view)))
+        .service(web::resource("/_upload/*").route(web::get().to(api::upload_file_view)))
+        .service(
+            web::resource("/*")
+                .guard(guard::fn_guard(api::is_websocket_connect))
+                .to(api::websocket_handle),
+        )
+        .service(web::resource("/*").to(api::action_handle));
+}
+
 #[derive(Debug, StructOpt)]
 pub struct TimeInfo {
     /// minute (0 - 59)
@@ -162,6 +372,66 @@ pub struct Test {
     pub timeinfo: TimeInfo,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct Gen {
+    /// language of the generated code: ts or rust
+    pub lang: String,
+
+    /// output folder, default is _gen/<lang>
+    #[structopt(short, long)]
+    pub out: Option<String>,
+
+    /// web framework of the generated rust routes: actix or axum
+    #[structopt(short, long, default_value = "actix")]
+    pub framework: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Export {
+    /// export format: html, schema or postman
+    pub format: String,
+
+    /// output folder
+    pub out: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Import {
+    /// import format: postman or har
+    pub format: String,
+
+    /// the file to import
+    pub file: String,
+
+    /// output folder, default is the docs folder
+    #[structopt(short, long)]
+    pub out: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Diff {
+    /// old version of the docs: a git revision or a folder
+    pub from: String,
+
+    /// new version of the docs: a git revision or a folder
+    pub to: String,
+
+    /// output format: markdown or json
+    #[structopt(short, long, default_value = "markdown")]
+    pub format: String,
+
+    /// exit with code 1 when there are breaking changes
+    #[structopt(long)]
+    pub fail_on_breaking: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct Perms {
+    /// output format: csv, json or html
+    #[structopt(short, long, default_value = "csv")]
+    pub format: String,
+}
+
 #[derive(Debug, StructOpt)]
 pub enum Command {
     /// generate random auth token
@@ -169,6 +439,22 @@ pub enum Command {
     Token(Token),
     /// Run the tests
     Test(Test),
+    /// Generate typed client or server code from the api docs
+    #[structopt(name = "gen")]
+    Gen(Gen),
+    /// Print the permission matrix of _auth.json5
+    #[structopt(name = "perms")]
+    Perms(Perms),
+    /// Export the docs, html: a static site with search, schema: JSON Schema files of the apis,
+    /// postman: a Postman collection and environments
+    #[structopt(name = "export")]
+    Export(Export),
+    /// Import a Postman collection or a HAR capture as json5 api docs
+    #[structopt(name = "import")]
+    Import(Import),
+    /// Compare two versions of the docs and list the breaking changes
+    #[structopt(name = "diff")]
+    Diff(Diff),
 }
 
 /// Panda api command
@@ -183,6 +469,14 @@ pub struct ApplicationArguments {
     #[structopt(short, long, default_value = "9000", env = "PANDA_API_PORT")]
     pub port: usize,
 
+    /// Serve a doc folder under a url prefix, ex: --project svc-order=../order-docs
+    #[structopt(long = "project", number_of_values = 1)]
+    pub project: Vec<String>,
+
+    /// Serve a doc folder on a hostname, ex: --project-host order.local=../order-docs
+    #[structopt(long = "project-host", number_of_values = 1)]
+    pub project_host: Vec<String>,
+
     #[structopt(subcommand)]
     pub command: Option<Command>,
 }
@@ -0,0 +1,607 @@
+//! 模拟的OAuth2 / OpenID Connect授权服务器
+//! 支持授权码模式和PKCE, 用户从_auth.json5的groups中选择, 签发的token可以直接访问 auth:true 的接口
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::{http, web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use openssl::sha::sha256;
+use serde_json::{json, Map, Value};
+
+use crate::api;
+use crate::db;
+use crate::jwt;
+use crate::project::Project;
+
+/// 授权服务器的地址, 在项目前缀之后
+pub const OAUTH_PATH: &str = "/__api_docs/oauth";
+/// 授权码的有效期, 秒
+const CODE_EXPIRES_IN: i64 = 60;
+
+/// 已经换过token的授权码, jti => 过期时间, 所有worker共用一份
+#[derive(Debug, Default)]
+pub struct UsedCodes(Mutex<HashMap<String, i64>>);
+
+impl UsedCodes {
+    /// 记录使用的授权码, 已经用过的返回false
+    fn insert(&self, jti: &str, exp: i64) -> bool {
+        let now = Utc::now().timestamp();
+        let mut codes = self.0.lock().unwrap();
+        codes.retain(|_, v| *v >= now);
+        codes.insert(jti.to_string(), exp).is_none()
+    }
+}
+
+/// 取出授权服务器的设置, 没有设置返回None
+fn get_oauth_config(data: &db::Database) -> Option<(&db::AuthDoc, &jwt::JwtConfig, &Value)> {
+    let auth_doc = data.auth_doc.as_ref()?;
+    Some((auth_doc, auth_doc.jwt.as_ref()?, auth_doc.oauth.as_ref()?))
+}
+
+fn not_configured() -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "code": -1,
+        "msg": "oauth is not configured, set auth_type to oauth2 or oidc in _auth.json5"
+    }))
+}
+
+/// OAuth2标准的错误返回
+fn oauth_error(status: http::StatusCode, error: &str, description: &str) -> HttpResponse {
+    HttpResponse::build(status).json(json!({
+        "error": error,
+        "error_description": description
+    }))
+}
+
+/// 授权服务器的issuer, 默认是当前请求的域名加上项目前缀
+fn get_issuer(req: &HttpRequest, project: &Project, oauth: &Value) -> String {
+    if let Some(issuer) = oauth.get("issuer").and_then(|v| v.as_str()) {
+        return issuer.trim_end_matches('/').to_string();
+    }
+    let info = req.connection_info();
+    format!(
+        "{}://{}{}{}",
+        info.scheme(),
+        info.host(),
+        project.prefix,
+        OAUTH_PATH
+    )
+}
+
+/// 检查client_id和redirect_uri, client要在oauth.clients中, redirect_uri要和设置的完全一致
+fn check_client(oauth: &Value, client_id: &str, redirect_uri: &str) -> Result<(), String> {
+    let client = oauth
+        .get("clients")
+        .and_then(|v| v.as_array())
+        .and_then(|clients| {
+            clients
+                .iter()
+                .find(|c| c.get("client_id").and_then(|v| v.as_str()) == Some(client_id))
+        })
+        .ok_or_else(|| format!("client {} not found in oauth.clients", client_id))?;
+
+    let allowed = match client.get("redirect_uris").and_then(|v| v.as_array()) {
+        Some(uris) => uris.iter().any(|u| u.as_str() == Some(redirect_uri)),
+        None => false,
+    };
+    if !allowed {
+        return Err(format!("redirect_uri {} is not allowed", redirect_uri));
+    }
+    Ok(())
+}
+
+/// 检查设置了client_secret的client
+fn check_client_secret(oauth: &Value, client_id: &str, client_secret: Option<&str>) -> bool {
+    let clients = match oauth.get("clients").and_then(|v| v.as_array()) {
+        Some(clients) => clients,
+        None => return true,
+    };
+    for client in clients {
+        if client.get("client_id").and_then(|v| v.as_str()) != Some(client_id) {
+            continue;
+        }
+        return match client.get("client_secret").and_then(|v| v.as_str()) {
+            Some(secret) => client_secret == Some(secret),
+            None => true,
+        };
+    }
+    true
+}
+
+/// 按用户名找到用户和所在的group
+fn find_user<'a>(
+    auth_doc: &'a db::AuthDoc,
+    username: &str,
+) -> Option<(&'a db::AuthData, &'a Value)> {
+    auth_doc
+        .groups
+        .iter()
+        .find_map(|group| group.users.get(username).map(|user| (group, user)))
+}
+
+fn url_encode(s: &str) -> String {
+    let mut result = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                result.push(b as char)
+            }
+            _ => result.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    result
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// OpenID Connect discovery
+pub async fn discovery_view(
+    req: HttpRequest,
+    db_data: web::Data<db::DataStore>,
+    project: web::Data<Project>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let (_, jwt_config, oauth) = match get_oauth_config(&data) {
+        Some(v) => v,
+        None => return not_configured(),
+    };
+    let issuer = get_issuer(&req, &project, oauth);
+    HttpResponse::Ok().json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer),
+        "token_endpoint": format!("{}/token", issuer),
+        "userinfo_endpoint": format!("{}/userinfo", issuer),
+        "jwks_uri": format!("{}/jwks", issuer),
+        "response_types_supported": ["code"],
+        "grant_types_supported": ["authorization_code", "refresh_token"],
+        "code_challenge_methods_supported": ["S256", "plain"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": [jwt_config.algorithm],
+        "scopes_supported": ["openid", "profile", "email", "offline_access"],
+        "token_endpoint_auth_methods_supported": ["none", "client_secret_post", "client_secret_basic"],
+        "claims_supported": ["sub", "iss", "aud", "exp", "iat", "nonce", "roles", jwt_config.group_claim]
+    }))
+}
+
+/// 公钥
+pub async fn jwks_view(db_data: web::Data<db::DataStore>) -> HttpResponse {
+    let data = db_data.load();
+    match get_oauth_config(&data) {
+        Some((_, jwt_config, _)) => HttpResponse::Ok().json(jwt::jwks(jwt_config)),
+        None => not_configured(),
+    }
+}
+
+/// 授权页面, 没有选择用户的时候显示用户列表, 选择后带着授权码跳转回redirect_uri
+pub async fn authorize_view(
+    req: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    db_data: web::Data<db::DataStore>,
+    project: web::Data<Project>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let (auth_doc, jwt_config, oauth) = match get_oauth_config(&data) {
+        Some(v) => v,
+        None => return not_configured(),
+    };
+    let get = |key: &str| query.get(key).map(|v| v.as_str());
+
+    if get("response_type") != Some("code") {
+        let e = "only response_type code is supported";
+        return oauth_error(
+            http::StatusCode::BAD_REQUEST,
+            "unsupported_response_type",
+            e,
+        );
+    }
+    let client_id = get("client_id").unwrap_or("");
+    let redirect_uri = match get("redirect_uri") {
+        Some(v) => v,
+        None => {
+            let e = "redirect_uri is required";
+            return oauth_error(http::StatusCode::BAD_REQUEST, "invalid_request", e);
+        }
+    };
+    if let Err(e) = check_client(oauth, client_id, redirect_uri) {
+        return oauth_error(http::StatusCode::BAD_REQUEST, "invalid_client", &e);
+    }
+
+    let username = get("username").unwrap_or("");
+    let (group, _) = match find_user(auth_doc, username) {
+        Some(v) => v,
+        None => return user_picker(&req, auth_doc),
+    };
+
+    let mut claims = Map::new();
+    claims.insert("sub".to_string(), json!(username));
+    claims.insert("group".to_string(), json!(group.name));
+    claims.insert("client_id".to_string(), json!(client_id));
+    claims.insert("redirect_uri".to_string(), json!(redirect_uri));
+    claims.insert("iss".to_string(), json!(get_issuer(&req, &project, oauth)));
+    claims.insert("jti".to_string(), json!(uuid::Uuid::new_v4().to_string()));
+    for key in &["scope", "nonce", "code_challenge", "code_challenge_method"] {
+        if let Some(v) = get(key) {
+            claims.insert(key.to_string(), json!(v));
+        }
+    }
+    // 授权码也是签名的token, 只需要记录用过的jti
+    let code = match jwt::sign_claims(jwt_config, &claims, Some("code"), CODE_EXPIRES_IN) {
+        Ok(code) => code,
+        Err(e) => return oauth_error(http::StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e),
+    };
+
+    let separator = if redirect_uri.contains('?') { "&" } else { "?" };
+    let mut location = format!("{}{}code={}", redirect_uri, separator, code);
+    if let Some(state) = get("state") {
+        location.push_str(&format!("&state={}", url_encode(state)));
+    }
+    HttpResponse::Found()
+        .header(http::header::LOCATION, location)
+        .finish()
+}
+
+/// 选择登录用户的页面
+fn user_picker(req: &HttpRequest, auth_doc: &db::AuthDoc) -> HttpResponse {
+    let mut items = String::new();
+    for group in &auth_doc.groups {
+        let mut usernames: Vec<&String> = group.users.keys().collect();
+        usernames.sort();
+        for username in usernames {
+            let href = format!("?{}&username={}", req.query_string(), url_encode(username));
+            items.push_str(&format!(
+                r#"<a class="user" href="{}"><div class="name">{}</div><div class="group">{}</div></a>"#,
+                html_escape(&href),
+                html_escape(username),
+                html_escape(&group.name)
+            ));
+        }
+    }
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Panda api sign in</title>
+  <style>
+    body {{ margin: 0; background: #f5f7fa; color: #303133; font-family: "Helvetica Neue", Helvetica, "PingFang SC", "Microsoft YaHei", Arial, sans-serif; }}
+    .header {{ padding: 24px 40px; background: #fff; border-bottom: 1px solid #e4e7ed; font-size: 22px; }}
+    .users {{ display: flex; flex-wrap: wrap; padding: 24px 28px; }}
+    .user {{ display: block; width: 200px; margin: 12px; padding: 16px 20px; background: #fff; border: 1px solid #ebeef5; border-radius: 4px; color: inherit; text-decoration: none; }}
+    .user:hover {{ box-shadow: 0 2px 12px 0 rgba(0, 0, 0, .1); }}
+    .user .name {{ font-size: 16px; margin-bottom: 6px; }}
+    .user .group {{ font-size: 12px; color: #909399; }}
+  </style>
+</head>
+<body>
+<div class="header">{} - choose a user to sign in</div>
+<div class="users">{}</div>
+</body>
+</html>"#,
+        html_escape(&auth_doc.name),
+        items
+    );
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(html)
+}
+
+/// 请求中的client_id和client_secret, 支持放在表单中或者Basic认证
+fn get_client_credentials(
+    req: &HttpRequest,
+    form: &HashMap<String, String>,
+) -> (String, Option<String>) {
+    let basic = req
+        .headers()
+        .get(http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|v| base64::decode(v.trim()).ok())
+        .and_then(|v| String::from_utf8(v).ok());
+    if let Some(basic) = basic {
+        let mut parts = basic.splitn(2, ':');
+        let client_id = parts.next().unwrap_or("").to_string();
+        return (client_id, parts.next().map(|v| v.to_string()));
+    }
+    (
+        form.get("client_id").cloned().unwrap_or_default(),
+        form.get("client_secret").cloned(),
+    )
+}
+
+/// PKCE验证
+fn is_code_verifier_valid(code_claims: &Value, code_verifier: Option<&String>) -> bool {
+    let challenge = match code_claims.get("code_challenge").and_then(|v| v.as_str()) {
+        Some(v) => v,
+        None => return true,
+    };
+    let verifier = match code_verifier {
+        Some(v) => v,
+        None => return false,
+    };
+    match code_claims
+        .get("code_challenge_method")
+        .and_then(|v| v.as_str())
+    {
+        Some("plain") => verifier == challenge,
+        _ => {
+            base64::encode_config(sha256(verifier.as_bytes()), base64::URL_SAFE_NO_PAD) == challenge
+        }
+    }
+}
+
+/// token接口, 用授权码或者refresh_token换取token
+pub async fn token_view(
+    req: HttpRequest,
+    form: web::Form<HashMap<String, String>>,
+    db_data: web::Data<db::DataStore>,
+    used_codes: web::Data<UsedCodes>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let (auth_doc, jwt_config, oauth) = match get_oauth_config(&data) {
+        Some(v) => v,
+        None => return not_configured(),
+    };
+    let bad_request = http::StatusCode::BAD_REQUEST;
+    let (client_id, client_secret) = get_client_credentials(&req, &form);
+    if !check_client_secret(oauth, &client_id, client_secret.as_deref()) {
+        let e = "client authentication failed";
+        return oauth_error(http::StatusCode::UNAUTHORIZED, "invalid_client", e);
+    }
+
+    let grant_type = form.get("grant_type").map(|v| v.as_str()).unwrap_or("");
+    let (token_use, token) = match grant_type {
+        "authorization_code" => ("code", form.get("code")),
+        "refresh_token" => ("refresh", form.get("refresh_token")),
+        _ => {
+            let e = format!("grant_type {} is not supported", grant_type);
+            return oauth_error(bad_request, "unsupported_grant_type", &e);
+        }
+    };
+    let claims = match jwt::decode(jwt_config, token.map(|v| v.as_str()).unwrap_or("")) {
+        Ok(claims) if claims.get("token_use") == Some(&json!(token_use)) => claims,
+        Ok(_) => return oauth_error(bad_request, "invalid_grant", "invalid grant token"),
+        Err(jwt::JwtError::Expired) => {
+            return oauth_error(bad_request, "invalid_grant", "grant token expired")
+        }
+        Err(jwt::JwtError::Invalid(e)) => return oauth_error(bad_request, "invalid_grant", &e),
+    };
+    let get = |key: &str| claims.get(key).and_then(|v| v.as_str()).unwrap_or("");
+
+    if !client_id.is_empty() && get("client_id") != client_id {
+        return oauth_error(bad_request, "invalid_grant", "client_id not match");
+    }
+    if token_use == "code" {
+        if form.get("redirect_uri").map(|v| v.as_str()) != Some(get("redirect_uri")) {
+            return oauth_error(bad_request, "invalid_grant", "redirect_uri not match");
+        }
+        if !is_code_verifier_valid(&claims, form.get("code_verifier")) {
+            return oauth_error(bad_request, "invalid_grant", "code_verifier is invalid");
+        }
+        // 授权码只能用一次
+        let exp = claims.get("exp").and_then(|v| v.as_i64()).unwrap_or(0);
+        if get("jti").is_empty() || !used_codes.insert(get("jti"), exp) {
+            return oauth_error(bad_request, "invalid_grant", "code has been used");
+        }
+    }
+
+    let (group, user) = match find_user(auth_doc, get("sub")) {
+        Some(v) => v,
+        None => return oauth_error(bad_request, "invalid_grant", "user not found"),
+    };
+    let scope = match get("scope") {
+        "" => "openid profile",
+        s => s,
+    };
+    let mut access_claims = jwt::user_claims(jwt_config, group, get("sub"), user);
+    access_claims.insert("iss".to_string(), json!(get("iss")));
+    access_claims.insert("client_id".to_string(), json!(get("client_id")));
+    access_claims.insert("scope".to_string(), json!(scope));
+    access_claims.insert("roles".to_string(), json!([group.name]));
+
+    let (access_token, refresh_token) = match jwt::issue_tokens(jwt_config, &access_claims) {
+        Ok(v) => v,
+        Err(e) => return oauth_error(http::StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e),
+    };
+    let mut response = json!({
+        "access_token": access_token,
+        "token_type": "Bearer",
+        "expires_in": jwt_config.expires_in,
+        "refresh_token": refresh_token,
+        "scope": scope
+    });
+
+    if scope.split(' ').any(|s| s == "openid") {
+        let mut id_claims = jwt::user_claims(jwt_config, group, get("sub"), user);
+        id_claims.insert("iss".to_string(), json!(get("iss")));
+        id_claims.insert("aud".to_string(), json!(get("client_id")));
+        id_claims.insert("name".to_string(), json!(get("sub")));
+        id_claims.insert("roles".to_string(), json!([group.name]));
+        if token_use == "code" && !get("nonce").is_empty() {
+            id_claims.insert("nonce".to_string(), json!(get("nonce")));
+        }
+        match jwt::sign_claims(jwt_config, &id_claims, Some("id"), jwt_config.expires_in) {
+            Ok(id_token) => response["id_token"] = json!(id_token),
+            Err(e) => {
+                return oauth_error(http::StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e)
+            }
+        }
+    }
+    HttpResponse::Ok()
+        .header(http::header::CACHE_CONTROL, "no-store")
+        .json(response)
+}
+
+/// 用access_token获取用户信息
+pub async fn userinfo_view(req: HttpRequest, db_data: web::Data<db::DataStore>) -> HttpResponse {
+    let data = db_data.load();
+    let (auth_doc, jwt_config, _) = match get_oauth_config(&data) {
+        Some(v) => v,
+        None => return not_configured(),
+    };
+    let token = api::get_token_from_request(&req, &auth_doc.token_places);
+    let mut claims = match jwt::decode(jwt_config, &token) {
+        Ok(Value::Object(claims)) if !claims.contains_key("token_use") => claims,
+        _ => {
+            return HttpResponse::Unauthorized()
+                .header(
+                    http::header::WWW_AUTHENTICATE,
+                    r#"Bearer error="invalid_token""#,
+                )
+                .json(json!({"error": "invalid_token"}))
+        }
+    };
+    for key in &["iss", "iat", "exp", "client_id", "scope"] {
+        claims.remove(*key);
+    }
+    let name = claims.get("sub").cloned().unwrap_or(Value::Null);
+    claims.entry("name").or_insert(name);
+    HttpResponse::Ok().json(claims)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{test, App};
+    use arc_swap::ArcSwap;
+    use std::fs;
+
+    const REDIRECT_URI: &str = "http://localhost:3000/callback";
+
+    /// 在临时目录中写一个oidc的_auth.json5, 用它加载的数据启动授权服务器
+    async fn oauth_app(
+        name: &str,
+    ) -> impl actix_web::dev::Service<
+        Request = actix_http::Request,
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+    > {
+        let root =
+            std::env::temp_dir().join(format!("panda-oauth-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let auth = r#"{
+            auth_type: "oidc",
+            jwt: {secret: "s3cret"},
+            oauth: {clients: [{client_id: "web", redirect_uris: ["http://localhost:3000/callback"]}]},
+            groups: [{name: "admin", users: [{username: "alice", password: "123"}], has_perms: {}}]
+        }"#;
+        fs::write(root.join("_auth.json5"), auth).unwrap();
+        let db = db::Database::load(&root);
+        let _ = fs::remove_dir_all(&root);
+
+        test::init_service(
+            App::new()
+                .data(Project::current())
+                .app_data(web::Data::new(ArcSwap::from_pointee(db)))
+                .app_data(web::Data::new(UsedCodes::default()))
+                .service(
+                    web::scope(OAUTH_PATH)
+                        .route("/authorize", web::get().to(authorize_view))
+                        .route("/token", web::post().to(token_view))
+                        .route("/userinfo", web::get().to(userinfo_view)),
+                ),
+        )
+        .await
+    }
+
+    /// 授权后跳转地址中的code
+    async fn authorize<S>(app: &mut S, redirect_uri: &str) -> (http::StatusCode, String)
+    where
+        S: actix_web::dev::Service<
+            Request = actix_http::Request,
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+        >,
+    {
+        let uri = format!(
+            "{}/authorize?response_type=code&client_id=web&redirect_uri={}&username=alice&scope=openid",
+            OAUTH_PATH,
+            url_encode(redirect_uri)
+        );
+        let resp = test::call_service(app, test::TestRequest::get().uri(&uri).to_request()).await;
+        let location = resp
+            .headers()
+            .get(http::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        let code = location.split_once("code=").map_or("", |x| x.1).to_string();
+        (resp.status(), code)
+    }
+
+    fn token_request(code: &str) -> actix_http::Request {
+        test::TestRequest::post()
+            .uri(&format!("{}/token", OAUTH_PATH))
+            .header(
+                http::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .set_payload(format!(
+                "grant_type=authorization_code&client_id=web&code={}&redirect_uri={}",
+                code,
+                url_encode(REDIRECT_URI)
+            ))
+            .to_request()
+    }
+
+    #[actix_rt::test]
+    async fn authorization_code_test() {
+        let mut app = oauth_app("flow").await;
+        let (status, code) = authorize(&mut app, REDIRECT_URI).await;
+        assert_eq!(http::StatusCode::FOUND, status);
+
+        let body: Value = test::read_response_json(&mut app, token_request(&code)).await;
+        assert!(body["id_token"].is_string());
+        let access_token = body["access_token"].as_str().unwrap();
+
+        let req = test::TestRequest::get()
+            .uri(&format!("{}/userinfo", OAUTH_PATH))
+            .header(
+                http::header::AUTHORIZATION,
+                format!("Bearer {}", access_token),
+            )
+            .to_request();
+        let userinfo: Value = test::read_response_json(&mut app, req).await;
+        assert_eq!("alice", userinfo["sub"]);
+        assert_eq!(json!(["admin"]), userinfo["roles"]);
+
+        // 同一个授权码不能再换token
+        let resp = test::call_service(&mut app, token_request(&code)).await;
+        assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        let body: Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!("invalid_grant", body["error"]);
+    }
+
+    #[actix_rt::test]
+    async fn redirect_uri_test() {
+        let mut app = oauth_app("redirect").await;
+        let (status, code) = authorize(&mut app, "http://evil.com/callback").await;
+        assert_eq!(http::StatusCode::BAD_REQUEST, status);
+        assert_eq!("", code);
+        let (status, _) = authorize(&mut app, "http://localhost:3000/callback/x").await;
+        assert_eq!(http::StatusCode::BAD_REQUEST, status);
+
+        // 没有设置clients时拒绝所有的client
+        assert!(check_client(&json!({}), "web", REDIRECT_URI).is_err());
+        let oauth = json!({"clients": [{"client_id": "web"}]});
+        assert!(check_client(&oauth, "web", REDIRECT_URI).is_err());
+    }
+
+    #[test]
+    fn pkce_test() {
+        // RFC 7636 附录B中的例子
+        let claims = json!({
+            "code_challenge": "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+            "code_challenge_method": "S256"
+        });
+        let verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".to_string();
+        assert!(is_code_verifier_valid(&claims, Some(&verifier)));
+        assert!(!is_code_verifier_valid(&claims, Some(&"x".to_string())));
+        assert!(!is_code_verifier_valid(&claims, None));
+        assert!(is_code_verifier_valid(&json!({}), None));
+    }
+}
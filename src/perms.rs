@@ -0,0 +1,356 @@
+//! 权限矩阵: 按_auth.json5解析后的has_perms和no_perms, 列出每个接口每个请求方法在每个权限组的访问权限
+//! 以及是哪一条规则决定的, 可以输出为json, csv和html表格
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::api;
+use crate::db;
+use crate::project::Project;
+
+/// 一个权限组对一个接口方法的访问权限
+#[derive(Debug, Clone, Serialize)]
+pub struct PermCell {
+    pub group: String,
+    pub allowed: bool,
+    /// 是否取决于规则的when条件
+    pub conditional: bool,
+    /// 决定权限的规则
+    pub rule: String,
+}
+
+/// 权限矩阵中的一行, 一个接口的一个请求方法
+#[derive(Debug, Clone, Serialize)]
+pub struct PermRow {
+    pub doc: String,
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    pub auth: bool,
+    pub perms: Vec<PermCell>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermMatrix {
+    pub groups: Vec<String>,
+    pub apis: Vec<PermRow>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PermMatrixRequest {
+    format: Option<String>,
+}
+
+/// 规则的显示内容, 如 has_perms: /me/ [GET,POST], $ref展开的规则带上原始的规则
+fn rule_text(
+    perm_type: &str,
+    rule_url: &str,
+    perms: &HashMap<String, HashSet<String>>,
+    source: Option<&String>,
+) -> String {
+    let mut methods: Vec<&String> = perms
+        .get(rule_url)
+        .map(|m| m.iter().collect())
+        .unwrap_or_default();
+    methods.sort();
+    let methods = if methods.is_empty() {
+        "*".to_string()
+    } else {
+        methods
+            .iter()
+            .map(|m| m.as_str())
+            .collect::<Vec<&str>>()
+            .join(",")
+    };
+    match source {
+        Some(source) if !source.is_empty() => {
+            format!("{}: {} [{}] from {}", perm_type, rule_url, methods, source)
+        }
+        _ => format!("{}: {} [{}]", perm_type, rule_url, methods),
+    }
+}
+
+/// 带条件的规则的显示内容, 如 has_perms: /users/{id}/ [PUT] when {"$url_param/id":"$claims/uid"}
+fn conditional_rule_text(
+    perm_type: &str,
+    url: &str,
+    method: &str,
+    rules: &[db::ConditionalPerm],
+) -> Option<String> {
+    rules.iter().find_map(|rule| {
+        let rule_url = api::find_perm_rule(url, method, &rule.perms)?;
+        Some(format!(
+            "{} when {}",
+            rule_text(perm_type, rule_url, &rule.perms, Some(&rule.source)),
+            json!(rule.when)
+        ))
+    })
+}
+
+/// 判断一个权限组对接口方法的权限, 和接口请求时的权限检查一致, no_perms优先
+fn group_perm(group: &db::AuthData, api: &db::ApiData, method: &str) -> PermCell {
+    let no_rule = conditional_rule_text("no_perms", &api.url, method, &group.no_perm_rules);
+    let has_rule = conditional_rule_text("has_perms", &api.url, method, &group.has_perm_rules);
+    let has_rule_url = api::find_perm_rule(&api.url, method, &group.has_perms);
+    let (allowed, conditional, rule) = if !api.auth {
+        (true, false, "auth: false".to_string())
+    } else if let Some(rule_url) = api::find_perm_rule(&api.url, method, &group.no_perms) {
+        let source = group.no_perm_sources.get(rule_url);
+        (
+            false,
+            false,
+            rule_text("no_perms", rule_url, &group.no_perms, source),
+        )
+    } else if has_rule_url.is_none() && has_rule.is_none() {
+        (false, false, "not in has_perms".to_string())
+    } else if let Some(rule) = no_rule {
+        // 条件满足时没有权限, 只有带条件的has规则时默认没有权限
+        (has_rule_url.is_some(), true, rule)
+    } else if let Some(rule_url) = has_rule_url {
+        let source = group.has_perm_sources.get(rule_url);
+        (
+            true,
+            false,
+            rule_text("has_perms", rule_url, &group.has_perms, source),
+        )
+    } else {
+        // 条件满足时才有权限
+        (false, true, has_rule.unwrap_or_default())
+    };
+    PermCell {
+        group: group.name.clone(),
+        allowed,
+        conditional,
+        rule,
+    }
+}
+
+/// 接口的请求方法, * 展开为具体的方法
+fn api_methods(api: &db::ApiData) -> Vec<&str> {
+    let mut methods: Vec<&str> = Vec::new();
+    for method in &api.method {
+        if method == "*" {
+            methods.extend(db::ALL_METHODS.iter());
+        } else {
+            methods.push(method);
+        }
+    }
+    let mut seen = HashSet::new();
+    methods.retain(|m| seen.insert(*m));
+    methods
+}
+
+/// 生成权限矩阵
+pub fn get_perm_matrix(data: &db::Database) -> PermMatrix {
+    let groups: Vec<&db::AuthData> = match &data.auth_doc {
+        Some(auth_doc) => auth_doc.groups.iter().collect(),
+        None => Vec::new(),
+    };
+
+    let mut api_docs: Vec<&db::ApiDoc> = data.api_docs.values().collect();
+    api_docs.sort_by(|a, b| a.order.cmp(&b.order).then(a.filename.cmp(&b.filename)));
+
+    let mut apis = Vec::new();
+    for api_doc in api_docs {
+        for api in &api_doc.apis {
+            for method in api_methods(api) {
+                // websocket和graphql接口按实际的请求方法检查权限
+                let req_method = match method {
+                    "WEBSOCKET" => "GET",
+                    "GRAPHQL" => "POST",
+                    m => m,
+                };
+                apis.push(PermRow {
+                    doc: api_doc.filename.clone(),
+                    name: api.name.clone(),
+                    url: api.url.clone(),
+                    method: method.to_string(),
+                    auth: api.auth,
+                    perms: groups
+                        .iter()
+                        .map(|group| group_perm(group, api, req_method))
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    PermMatrix {
+        groups: groups.iter().map(|g| g.name.clone()).collect(),
+        apis,
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// 权限矩阵的csv, 每个权限组有权限和规则两列
+pub fn to_csv(matrix: &PermMatrix) -> String {
+    let mut header = vec![
+        "doc".to_string(),
+        "name".to_string(),
+        "url".to_string(),
+        "method".to_string(),
+        "auth".to_string(),
+    ];
+    for group in &matrix.groups {
+        header.push(group.clone());
+        header.push(format!("{} rule", group));
+    }
+
+    let mut lines = vec![header];
+    for row in &matrix.apis {
+        let mut line = vec![
+            row.doc.clone(),
+            row.name.clone(),
+            row.url.clone(),
+            row.method.clone(),
+            row.auth.to_string(),
+        ];
+        for cell in &row.perms {
+            let perm = match (cell.conditional, cell.allowed) {
+                (true, _) => "conditional",
+                (false, true) => "allowed",
+                (false, false) => "denied",
+            };
+            line.push(perm.to_string());
+            line.push(cell.rule.clone());
+        }
+        lines.push(line);
+    }
+
+    let mut result = String::new();
+    for line in lines {
+        let line: Vec<String> = line.iter().map(|s| csv_field(s)).collect();
+        result.push_str(&line.join(","));
+        result.push('\n');
+    }
+    result
+}
+
+/// 权限矩阵的html页面, 把数据放到皮肤的perms.html中, 不需要请求服务器就可以打开
+pub fn to_html(matrix: &PermMatrix, root: &std::path::Path) -> Result<String, String> {
+    let theme_file = api::get_theme_file_path(root, "/perms.html");
+    let html = fs::read_to_string(&theme_file)
+        .map_err(|e| format!("read {} error: {}", theme_file.display(), e))?;
+    // 避免数据中的</script>结束脚本
+    let data = json!(matrix).to_string().replace("</", "<\\/");
+    let script = format!("<script>window.PERM_MATRIX = {};</script>\n</head>", data);
+    Ok(html.replacen("</head>", &script, 1))
+}
+
+/// 权限矩阵, format 为 json(默认), csv 或者 html
+pub async fn perm_matrix_view(
+    req: HttpRequest,
+    req_get: web::Query<PermMatrixRequest>,
+    db_data: web::Data<db::DataStore>,
+    project: web::Data<Project>,
+) -> Result<HttpResponse, Error> {
+    let format = req_get.format.as_deref().unwrap_or("json");
+    if format == "html" {
+        return api::open_theme_file(&project.root, "/perms.html")?.into_response(&req);
+    }
+
+    let data = db_data.load();
+    let matrix = get_perm_matrix(&data);
+    if format == "csv" {
+        return Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .header("Content-Disposition", "attachment; filename=\"perms.csv\"")
+            .body(to_csv(&matrix)));
+    }
+    Ok(HttpResponse::Ok().json(matrix))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn perm_cell_test() {
+        let mut has_perms = HashMap::new();
+        has_perms.insert(
+            "*".to_string(),
+            ["GET".to_string()].iter().cloned().collect(),
+        );
+        let mut no_perms = HashMap::new();
+        no_perms.insert("/admin/".to_string(), HashSet::new());
+        let group = db::AuthData {
+            name: "user".to_string(),
+            has_perms,
+            no_perms,
+            ..Default::default()
+        };
+        let api = |url: &str| db::ApiData {
+            url: url.to_string(),
+            auth: true,
+            ..Default::default()
+        };
+
+        let cell = group_perm(&group, &api("/me/"), "GET");
+        assert!(cell.allowed);
+        assert_eq!("has_perms: * [GET]", cell.rule);
+        let cell = group_perm(&group, &api("/me/"), "POST");
+        assert!(!cell.allowed);
+        assert_eq!("not in has_perms", cell.rule);
+        let cell = group_perm(&group, &api("/admin/"), "GET");
+        assert!(!cell.allowed);
+        assert_eq!("no_perms: /admin/ [*]", cell.rule);
+
+        // $ref展开的规则显示原始的规则
+        let mut group = group;
+        let source = r#"{"$ref":"$admin.json5","$exclude":["/admin/logs/"]}"#;
+        group
+            .no_perm_sources
+            .insert("/admin/".to_string(), source.to_string());
+        let cell = group_perm(&group, &api("/admin/"), "GET");
+        assert_eq!(format!("no_perms: /admin/ [*] from {}", source), cell.rule);
+    }
+
+    #[test]
+    fn conditional_perm_cell_test() {
+        let rule = |url: &str| db::ConditionalPerm {
+            perms: [(url.to_string(), HashSet::new())]
+                .iter()
+                .cloned()
+                .collect(),
+            when: json!({"$query/a": "1"}).as_object().cloned().unwrap(),
+            source: String::new(),
+        };
+        let group = db::AuthData {
+            name: "user".to_string(),
+            has_perm_rules: vec![rule("/me/")],
+            no_perm_rules: vec![rule("/me/")],
+            ..Default::default()
+        };
+        let api = db::ApiData {
+            url: "/me/".to_string(),
+            auth: true,
+            ..Default::default()
+        };
+        let cell = group_perm(&group, &api, "GET");
+        assert!(!cell.allowed);
+        assert!(cell.conditional);
+        assert!(cell.rule.starts_with("no_perms: /me/ [*] when"));
+    }
+
+    #[test]
+    fn api_methods_test() {
+        let api = |methods: &[&str]| db::ApiData {
+            method: methods.iter().map(|m| m.to_string()).collect(),
+            ..Default::default()
+        };
+        assert_eq!(vec!["GET", "POST"], api_methods(&api(&["GET", "POST"])));
+        assert_eq!(db::ALL_METHODS.to_vec(), api_methods(&api(&["*"])));
+        assert_eq!(db::ALL_METHODS.to_vec(), api_methods(&api(&["GET", "*"])));
+    }
+}
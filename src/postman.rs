@@ -0,0 +1,794 @@
+//! Postman v2.1 collection 的导入和导出
+//! 导出时菜单中的文件夹和接口文档是collection中的文件夹, 每个接口一个请求, test_data中的用例是请求保存的示例
+//! _settings.json5 中的每个服务器导出一个environment, 请求地址使用 {{base_url}}, token使用 {{token}}
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::api;
+use crate::db;
+use crate::export;
+use crate::import::{self, Exchange, ImportApi, ImportDoc};
+use crate::jwt;
+use crate::snippets;
+
+const COLLECTION_SCHEMA: &str =
+    "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+
+/// 导出collection和environment文件到out目录
+pub fn export_postman(root: &Path, out: &Path) -> Result<(), String> {
+    let data = db::Database::load(root);
+    fs::create_dir_all(out).map_err(|e| format!("create {} error: {}", out.display(), e))?;
+
+    let name = import::safe_filename(&data.basic_data.project_name);
+    let collection = get_collection(&data);
+    write_json(
+        &out.join(format!("{}.postman_collection.json", name)),
+        &collection,
+    )?;
+
+    let tokens = get_user_tokens(&data.auth_doc);
+    for (server_name, url) in get_servers(&data) {
+        let mut values = vec![json!({"key": "base_url", "value": url, "enabled": true})];
+        for (key, token) in &tokens {
+            values.push(json!({"key": key, "value": token, "type": "secret", "enabled": true}));
+        }
+        let name = format!("{} {}", data.basic_data.project_name, server_name);
+        let environment = json!({
+            "id": postman_id(&format!("environment {}", name)),
+            "name": name,
+            "values": values,
+            "_postman_variable_scope": "environment"
+        });
+        write_json(
+            &out.join(format!(
+                "{}.postman_environment.json",
+                import::safe_filename(&server_name)
+            )),
+            &environment,
+        )?;
+    }
+    println!("exported the postman collection to {}", out.display());
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value).unwrap_or_default();
+    fs::write(path, content).map_err(|e| format!("write {} error: {}", path.display(), e))
+}
+
+/// _settings.json5 中的服务器, 没有时使用本地的panda
+fn get_servers(data: &db::Database) -> Vec<(String, String)> {
+    let mut servers = Vec::new();
+    if let Some(items) = data
+        .settings
+        .as_ref()
+        .and_then(|s| s.get("servers"))
+        .and_then(|s| s.as_object())
+    {
+        for (key, server) in items {
+            if let Some(url) = server.get("url").and_then(|v| v.as_str()) {
+                let name = server.get("name").and_then(|v| v.as_str()).unwrap_or(key);
+                servers.push((name.to_string(), url.trim_end_matches('/').to_string()));
+            }
+        }
+    }
+    if servers.is_empty() {
+        servers.push(("local".to_string(), export::DEFAULT_SERVER.to_string()));
+    }
+    servers
+}
+
+/// environment中的token, token是第一个用户的, 每个权限组的第一个用户是 token_<group>
+/// jwt的用户按用户名签发token
+fn get_user_tokens(auth_doc: &Option<db::AuthDoc>) -> Vec<(String, String)> {
+    let auth_doc = match auth_doc {
+        Some(auth_doc) => auth_doc,
+        None => return Vec::new(),
+    };
+    let mut tokens: Vec<(String, String)> = Vec::new();
+    for group in &auth_doc.groups {
+        let mut users: Vec<(&String, &Value)> = group.users.iter().collect();
+        users.sort_by(|a, b| a.0.cmp(b.0));
+        let (key, user) = match users.first() {
+            Some(user) => *user,
+            None => continue,
+        };
+        let token = match &auth_doc.jwt {
+            Some(config) => {
+                let claims = jwt::user_claims(config, group, key, user);
+                match jwt::sign_claims(config, &claims, None, config.expires_in) {
+                    Ok(token) => token,
+                    Err(e) => {
+                        log::error!("sign the token of {} error: {}", key, e);
+                        continue;
+                    }
+                }
+            }
+            None => key.to_string(),
+        };
+        if tokens.is_empty() {
+            tokens.push(("token".to_string(), token.to_string()));
+        }
+        tokens.push((format!("token_{}", group.name), token));
+    }
+    tokens
+}
+
+/// 由名称生成的id, 重新导出时id不变, 导入postman会更新原来的collection和environment
+fn postman_id(name: &str) -> String {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()).to_string()
+}
+
+/// 整个collection
+fn get_collection(data: &db::Database) -> Value {
+    let mut collection = Map::new();
+    collection.insert(
+        "info".to_string(),
+        json!({
+            "_postman_id": postman_id(&format!("collection {}", data.basic_data.project_name)),
+            "name": data.basic_data.project_name,
+            "description": data.basic_data.project_desc,
+            "schema": COLLECTION_SCHEMA
+        }),
+    );
+    collection.insert(
+        "item".to_string(),
+        Value::Array(menu_items(&data.menus, data)),
+    );
+    if let Some(auth) = get_auth(&data.auth_doc) {
+        collection.insert("auth".to_string(), auth);
+    }
+    let base_url = get_servers(data)
+        .first()
+        .map(|(_, url)| url.to_string())
+        .unwrap_or_default();
+    collection.insert(
+        "variable".to_string(),
+        json!([{"key": "base_url", "value": base_url}]),
+    );
+    Value::Object(collection)
+}
+
+/// token按_auth.json5中的第一个auth_place放置, Bearer前缀的Authorization使用bearer认证
+fn get_auth(auth_doc: &Option<db::AuthDoc>) -> Option<Value> {
+    let token_place = auth_doc.as_ref()?.token_places.first()?;
+    let is_bearer = token_place.place == "header"
+        && token_place.name.to_lowercase() == "authorization"
+        && token_place.prefix.trim().to_lowercase() == "bearer";
+    if is_bearer {
+        return Some(json!({
+            "type": "bearer",
+            "bearer": [{"key": "token", "value": "{{token}}", "type": "string"}]
+        }));
+    }
+    let token = format!("{}{{{{token}}}}", token_place.prefix);
+    let (key, value, place) = match token_place.place.as_str() {
+        "query" => (token_place.name.to_string(), token, "query"),
+        "cookie" => (
+            "Cookie".to_string(),
+            format!("{}={}", token_place.name, token),
+            "header",
+        ),
+        _ => (token_place.name.to_string(), token, "header"),
+    };
+    Some(json!({
+        "type": "apikey",
+        "apikey": [
+            {"key": "key", "value": key, "type": "string"},
+            {"key": "value", "value": value, "type": "string"},
+            {"key": "in", "value": place, "type": "string"}
+        ]
+    }))
+}
+
+/// 菜单按顺序转换为文件夹, 接口文档是包含接口请求的文件夹
+fn menu_items(menus: &HashMap<String, db::Menu>, data: &db::Database) -> Vec<Value> {
+    let mut menus: Vec<&db::Menu> = menus.values().collect();
+    menus.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+
+    let mut items = Vec::new();
+    for menu in menus {
+        if let Some(doc) = data.api_docs.get(&menu.filename) {
+            let requests: Vec<Value> = doc
+                .apis
+                .iter()
+                .flat_map(|api| api_items(api, data))
+                .collect();
+            if !requests.is_empty() {
+                items.push(json!({"name": doc.name, "description": doc.desc, "item": requests}));
+            }
+        } else if !menu.children.is_empty() {
+            let children = menu_items(&menu.children, data);
+            if !children.is_empty() {
+                items.push(json!({"name": menu.name, "description": menu.desc, "item": children}));
+            }
+        }
+    }
+    items
+}
+
+/// 接口的每个请求方法一个请求
+fn api_items(api: &db::ApiData, data: &db::Database) -> Vec<Value> {
+    let methods: Vec<&str> = api
+        .method
+        .iter()
+        .filter_map(|method| match method.as_str() {
+            "WEBSOCKET" => None,
+            "GRAPHQL" => Some("POST"),
+            "*" => Some("GET"),
+            m => Some(m),
+        })
+        .collect();
+
+    let mut items = Vec::new();
+    for method in &methods {
+        let url_param = snippets::mock_value(&api.url_param);
+        let query = snippets::mock_value(&api.query);
+        let body = if *method == "GET" || *method == "HEAD" {
+            Value::Null
+        } else {
+            snippets::mock_value(&api.body)
+        };
+        let request = get_request(api, method, &url_param, None, &query, &body, data);
+
+        let mut examples = Vec::new();
+        if let Some(test_data) = api.test_data.as_array() {
+            for (i, case) in test_data.iter().enumerate() {
+                let case_method = match case.get("method") {
+                    Some(Value::String(m)) => m.to_uppercase(),
+                    Some(Value::Array(m)) => m
+                        .first()
+                        .and_then(|m| m.as_str())
+                        .unwrap_or(method)
+                        .to_uppercase(),
+                    _ => method.to_string(),
+                };
+                if case_method != *method && case_method != "*" {
+                    continue;
+                }
+                examples.push(get_example(api, method, i, case, data));
+            }
+        }
+
+        let name = if methods.len() > 1 {
+            format!("{} {}", api.name, method)
+        } else {
+            api.name.to_string()
+        };
+        items.push(json!({"name": name, "request": request, "response": examples}));
+    }
+    items
+}
+
+/// test_data中的用例, 返回按接口的response模型生成
+fn get_example(
+    api: &db::ApiData,
+    method: &str,
+    i: usize,
+    case: &Value,
+    data: &db::Database,
+) -> Value {
+    let name = ["name", "desc"]
+        .iter()
+        .find_map(|key| case.get(*key).and_then(|v| v.as_str()))
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("test case {}", i + 1));
+    let query = case.get("query").cloned().unwrap_or(Value::Null);
+    let body = case.get("body").cloned().unwrap_or(Value::Null);
+    let url = case.get("url").and_then(|v| v.as_str());
+    let url_param = snippets::mock_value(&api.url_param);
+    let request = get_request(api, method, &url_param, url, &query, &body, data);
+
+    let response = api::parse_test_case_response(
+        case.get("response").unwrap_or(&Value::Null),
+        "",
+        &api.response,
+        &body,
+        &query,
+    );
+    json!({
+        "name": name,
+        "originalRequest": request,
+        "status": "OK",
+        "code": 200,
+        "_postman_previewlanguage": "json",
+        "header": [{"key": "Content-Type", "value": "application/json"}],
+        "body": serde_json::to_string_pretty(&response).unwrap_or_default()
+    })
+}
+
+/// 请求的地址, 参数, 请求头和请求体, 用例中有url时使用用例的地址
+fn get_request(
+    api: &db::ApiData,
+    method: &str,
+    url_param: &Value,
+    case_url: Option<&str>,
+    query: &Value,
+    body: &Value,
+    data: &db::Database,
+) -> Value {
+    let mut request = Map::new();
+    request.insert("method".to_string(), json!(method));
+
+    let mut headers = Vec::new();
+    if let Value::Object(request_headers) = snippets::mock_value(&api.request_headers) {
+        for (key, value) in request_headers {
+            headers.push(json!({"key": key, "value": snippets::value_string(&value)}));
+        }
+    }
+
+    let (path, variables) = match case_url {
+        Some(url) => (url.to_string(), Vec::new()),
+        None => url_path(&api.url, url_param),
+    };
+    let pairs = snippets::value_pairs(query);
+    let mut raw = format!("{{{{base_url}}}}{}", path);
+    if !pairs.is_empty() {
+        let query_string: Vec<String> = pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", snippets::url_encode(k), snippets::url_encode(v)))
+            .collect();
+        raw = format!("{}?{}", raw, query_string.join("&"));
+    }
+    let query: Vec<Value> = pairs
+        .into_iter()
+        .map(|(key, value)| json!({"key": key, "value": value}))
+        .collect();
+    let mut url = Map::new();
+    url.insert("raw".to_string(), json!(raw));
+    url.insert("host".to_string(), json!(["{{base_url}}"]));
+    let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+    url.insert("path".to_string(), json!(segments));
+    if !query.is_empty() {
+        url.insert("query".to_string(), Value::Array(query));
+    }
+    if !variables.is_empty() {
+        url.insert("variable".to_string(), Value::Array(variables));
+    }
+    request.insert("url".to_string(), Value::Object(url));
+
+    if !body.is_null() {
+        let (mode, language) = match api.body_mode.as_str() {
+            "form-data" => ("formdata", ""),
+            "urlencoded" | "x-www-form-urlencoded" => ("urlencoded", ""),
+            "text" | "html" | "xml" | "javascript" => ("raw", api.body_mode.as_str()),
+            _ => ("raw", "json"),
+        };
+        let body_value = match mode {
+            "raw" => {
+                let raw = match body {
+                    Value::String(s) if language != "json" => s.to_string(),
+                    v => serde_json::to_string_pretty(v).unwrap_or_default(),
+                };
+                if language == "json" {
+                    headers.insert(
+                        0,
+                        json!({"key": "Content-Type", "value": "application/json"}),
+                    );
+                }
+                json!({"mode": "raw", "raw": raw, "options": {"raw": {"language": language}}})
+            }
+            _ => {
+                let items: Vec<Value> = snippets::value_pairs(body)
+                    .into_iter()
+                    .map(|(key, value)| json!({"key": key, "value": value, "type": "text"}))
+                    .collect();
+                json!({ "mode": mode, mode: items })
+            }
+        };
+        request.insert("body".to_string(), body_value);
+    }
+    request.insert("header".to_string(), Value::Array(headers));
+
+    if !api.auth && data.auth_doc.is_some() {
+        request.insert("auth".to_string(), json!({"type": "noauth"}));
+    }
+    if !api.desc.is_empty() {
+        request.insert("description".to_string(), json!(api.desc));
+    }
+    Value::Object(request)
+}
+
+/// url中的 {param} 转换为postman的 :param 和对应的变量
+fn url_path(url: &str, url_param: &Value) -> (String, Vec<Value>) {
+    let mut variables = Vec::new();
+    let mut result = String::new();
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        let name = rest[start + 1..end].split(':').next().unwrap_or("").trim();
+        result.push_str(&format!(":{}", name));
+        let value = url_param.get(name).map(snippets::value_string);
+        variables.push(json!({"key": name, "value": value.unwrap_or_default()}));
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    (result, variables)
+}
+
+/// collection中一个请求的路径和请求体格式, 路径中的变量是 :name
+#[derive(Debug, Default)]
+struct Observation {
+    segments: Vec<String>,
+    body_mode: String,
+    exchange: Exchange,
+}
+
+/// collection转换为接口文档, 每个文件夹一个文档文件, 子文件夹是子目录
+pub fn import_collection(content: &str) -> Result<Vec<ImportDoc>, String> {
+    let collection: Value =
+        serde_json::from_str(content).map_err(|e| format!("parse collection error: {}", e))?;
+    let info = collection.get("info").unwrap_or(&Value::Null);
+    let name = info
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("postman");
+    let items = collection
+        .get("item")
+        .and_then(|v| v.as_array())
+        .ok_or("the collection has no item")?;
+
+    let mut docs = Vec::new();
+    let mut filenames = HashSet::new();
+    import_folder(
+        name,
+        &description(info.get("description")),
+        "",
+        items,
+        true,
+        &mut docs,
+        &mut filenames,
+    );
+    Ok(docs)
+}
+
+fn import_folder(
+    name: &str,
+    desc: &str,
+    dir: &str,
+    items: &[Value],
+    is_root: bool,
+    docs: &mut Vec<ImportDoc>,
+    filenames: &mut HashSet<String>,
+) {
+    let mut apis = Vec::new();
+    let sub_dir = if is_root {
+        dir.to_string()
+    } else {
+        format!("{}{}/", dir, import::safe_filename(name))
+    };
+    for item in items {
+        if let Some(children) = item.get("item").and_then(|v| v.as_array()) {
+            let folder_name = item
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("folder");
+            import_folder(
+                folder_name,
+                &description(item.get("description")),
+                &sub_dir,
+                children,
+                false,
+                docs,
+                filenames,
+            );
+        } else if item.get("request").is_some() {
+            apis.push(import_request(item));
+        }
+    }
+    if apis.is_empty() {
+        return;
+    }
+
+    let mut filename = format!("{}{}.json5", dir, import::safe_filename(name));
+    let mut i = 2;
+    while filenames.contains(&filename) {
+        filename = format!("{}{} {}.json5", dir, import::safe_filename(name), i);
+        i += 1;
+    }
+    filenames.insert(filename.to_string());
+    docs.push(ImportDoc {
+        filename,
+        name: name.to_string(),
+        desc: desc.to_string(),
+        apis,
+    });
+}
+
+/// description可以是字符串或者 {content}
+fn description(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.to_string(),
+        Some(v) => v
+            .get("content")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        None => "".to_string(),
+    }
+}
+
+/// 一个请求转换为接口, 请求和保存的示例一起推断字段的类型
+fn import_request(item: &Value) -> ImportApi {
+    let request = item.get("request").unwrap_or(&Value::Null);
+    let main = parse_request(request, None);
+
+    let mut examples = Vec::new();
+    for example in item
+        .get("response")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let original = example.get("originalRequest").unwrap_or(request);
+        let mut observation = parse_request(original, example.get("body"));
+        observation.exchange.name = example
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        observation.exchange.status = example.get("code").and_then(|v| v.as_u64()).unwrap_or(200);
+        examples.push(observation.exchange);
+    }
+
+    let url = format!(
+        "/{}",
+        main.segments
+            .iter()
+            .map(|s| match s.strip_prefix(':') {
+                Some(name) => format!("{{{}}}", name),
+                None => s.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join("/")
+    );
+    let api = ImportApi {
+        name: item
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&url)
+            .to_string(),
+        desc: description(request.get("description")),
+        method: request
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("GET")
+            .to_uppercase(),
+        url,
+        body_mode: main.body_mode,
+        ..Default::default()
+    };
+    let cases: Vec<&Exchange> = examples.iter().collect();
+    let mut samples = vec![&main.exchange];
+    samples.extend(cases.iter());
+    import::infer_api(api, &samples, &cases)
+}
+
+/// 请求的地址, 变量替换为值
+fn concrete_url(segments: &[String], variables: &[(String, String)]) -> String {
+    let segments: Vec<String> = segments
+        .iter()
+        .map(|s| match s.strip_prefix(':') {
+            Some(name) => match variables.iter().find(|(k, _)| k == name) {
+                Some((_, value)) if !value.is_empty() => value.to_string(),
+                _ => format!("{{{}}}", name),
+            },
+            None => s.to_string(),
+        })
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// 解析请求的地址, 参数和请求体, 返回的内容是json时一起解析
+fn parse_request(request: &Value, response_body: Option<&Value>) -> Observation {
+    let url = match request {
+        Value::String(url) => json!(url),
+        v => v.get("url").cloned().unwrap_or(Value::Null),
+    };
+    let raw = match &url {
+        Value::String(raw) => raw.to_string(),
+        v => v
+            .get("raw")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+    };
+    let (raw_path, raw_query) = split_raw_url(&raw);
+
+    let mut segments: Vec<String> = match url.get("path").and_then(|v| v.as_array()) {
+        Some(path) => path
+            .iter()
+            .map(|s| match s {
+                Value::String(s) => s.to_string(),
+                v => v
+                    .get("value")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            })
+            .collect(),
+        None => raw_path
+            .trim_start_matches('/')
+            .split('/')
+            .map(|s| s.to_string())
+            .collect(),
+    };
+    // {{id}} 形式的路径变量也作为url参数
+    for segment in segments.iter_mut() {
+        if segment.starts_with("{{") && segment.ends_with("}}") {
+            *segment = format!(":{}", &segment[2..segment.len() - 2]);
+        }
+    }
+    let mut url_param = match url.get("variable").and_then(|v| v.as_array()) {
+        Some(variables) => key_values(variables),
+        None => Vec::new(),
+    };
+    for segment in &segments {
+        if let Some(name) = segment.strip_prefix(':') {
+            if !url_param.iter().any(|(k, _)| k == name) {
+                url_param.push((name.to_string(), "".to_string()));
+            }
+        }
+    }
+    let mut exchange = Exchange {
+        url: concrete_url(&segments, &url_param),
+        url_param,
+        query: match url.get("query").and_then(|v| v.as_array()) {
+            Some(query) => key_values(query),
+            None => raw_query,
+        },
+        ..Default::default()
+    };
+
+    let body = request.get("body").unwrap_or(&Value::Null);
+    let mut body_mode = "json".to_string();
+    match body.get("mode").and_then(|v| v.as_str()) {
+        Some("raw") => {
+            let raw = body.get("raw").and_then(|v| v.as_str()).unwrap_or("");
+            match import::parse_json(raw) {
+                Some(v) => exchange.body = Some(v),
+                None => {
+                    let language = body
+                        .pointer("/options/raw/language")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("text");
+                    if language != "json" {
+                        body_mode = language.to_string();
+                    }
+                }
+            }
+        }
+        Some("urlencoded") => {
+            body_mode = "urlencoded".to_string();
+            exchange.form = key_values(body["urlencoded"].as_array().unwrap_or(&Vec::new()));
+        }
+        Some("formdata") => {
+            body_mode = "form-data".to_string();
+            exchange.form = key_values(body["formdata"].as_array().unwrap_or(&Vec::new()));
+        }
+        _ => {}
+    }
+
+    exchange.response = response_body
+        .and_then(|v| v.as_str())
+        .and_then(import::parse_json);
+    Observation {
+        segments,
+        body_mode,
+        exchange,
+    }
+}
+
+/// 去掉地址中的协议, 域名或者 {{base_url}}, 分开路径和参数
+fn split_raw_url(raw: &str) -> (String, Vec<(String, String)>) {
+    let mut rest = raw.split('#').next().unwrap_or("").trim();
+    if rest.starts_with("{{") {
+        rest = rest.find("}}").map(|i| &rest[i + 2..]).unwrap_or("");
+    } else if let Some(i) = rest.find("://") {
+        rest = &rest[i + 3..];
+        rest = rest.find('/').map(|i| &rest[i..]).unwrap_or("");
+    }
+    let mut parts = rest.splitn(2, '?');
+    let path = parts.next().unwrap_or("").to_string();
+    let query = parts
+        .next()
+        .unwrap_or("")
+        .split('&')
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let mut kv = pair.splitn(2, '=');
+            let key = kv.next().unwrap_or("").to_string();
+            (key, kv.next().unwrap_or("").to_string())
+        })
+        .collect();
+    (path, query)
+}
+
+/// postman中的 [{key, value, disabled}]
+fn key_values(items: &[Value]) -> Vec<(String, String)> {
+    items
+        .iter()
+        .filter(|item| item.get("disabled").and_then(|v| v.as_bool()) != Some(true))
+        .filter_map(|item| {
+            let key = item.get("key")?.as_str()?;
+            let value = match item.get("value") {
+                Some(v) => snippets::value_string(v),
+                None => "".to_string(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn postman_id_test() {
+        let id = postman_id("collection Shop");
+        assert_eq!(id, postman_id("collection Shop"));
+        assert_ne!(id, postman_id("environment Shop test"));
+        assert_eq!(
+            Some(uuid::Version::Sha1),
+            Uuid::parse_str(&id).unwrap().get_version()
+        );
+    }
+
+    #[test]
+    fn import_collection_test() {
+        let collection = json!({
+            "info": {"name": "Shop", "schema": COLLECTION_SCHEMA},
+            "item": [{
+                "name": "Users",
+                "item": [{
+                    "name": "user detail",
+                    "request": {
+                        "method": "GET",
+                        "url": {
+                            "raw": "{{base_url}}/users/:id/?fields=name",
+                            "host": ["{{base_url}}"],
+                            "path": ["users", ":id", ""],
+                            "query": [{"key": "fields", "value": "name"}],
+                            "variable": [{"key": "id", "value": "12"}]
+                        }
+                    },
+                    "response": [{
+                        "name": "user 12",
+                        "code": 200,
+                        "originalRequest": {"method": "GET", "url": "https://shop.example.com/users/12/?fields=name"},
+                        "body": "{\"id\": 12, \"email\": \"a@b.com\"}"
+                    }]
+                }]
+            }]
+        });
+        let docs = import_collection(&collection.to_string()).unwrap();
+        assert_eq!(1, docs.len());
+        assert_eq!("Users.json5", docs[0].filename);
+        let api = &docs[0].apis[0];
+        assert_eq!("/users/{id}/", api.url);
+        assert_eq!(json!({"id": {"type": "int"}}), api.url_param);
+        assert_eq!(
+            json!({"id": {"type": "int"}, "email": {"type": "email"}}),
+            api.response
+        );
+        assert_eq!(
+            vec![json!({
+                "name": "user 12",
+                "url": "/users/12/",
+                "query": {"fields": "name"},
+                "response": {"id": 12, "email": "a@b.com"}
+            })],
+            api.test_data
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! 一个panda进程同时提供多个接口文档项目
+//! 每个项目有自己的根目录、数据、配置和文件监控，通过url前缀或者域名区分
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// 一个接口文档项目
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// 项目名称, 按前缀挂载时就是url前缀
+    pub name: String,
+    /// 接口文档根目录, 绝对路径
+    pub root: PathBuf,
+    /// url前缀, 如 /svc-order, 按域名挂载或者单项目时为空
+    pub prefix: String,
+    /// 按域名挂载时的域名
+    pub host: Option<String>,
+}
+
+impl Project {
+    /// 当前目录作为唯一的项目，挂载在根路径
+    pub fn current() -> Project {
+        let root = env::current_dir().expect("Failed to determine current directory");
+        Project {
+            name: String::new(),
+            root,
+            prefix: String::new(),
+            host: None,
+        }
+    }
+
+    /// 解析命令行参数 name=path, 按前缀挂载
+    pub fn from_prefix_arg(arg: &str) -> Result<Project, String> {
+        let (name, root) = parse_project_arg(arg)?;
+        if name.contains('/') {
+            return Err(format!("project name {} can not contain /", name));
+        }
+        Ok(Project {
+            prefix: format!("/{}", name),
+            name,
+            root,
+            host: None,
+        })
+    }
+
+    /// 解析命令行参数 host=path, 按域名挂载
+    pub fn from_host_arg(arg: &str) -> Result<Project, String> {
+        let (name, root) = parse_project_arg(arg)?;
+        Ok(Project {
+            host: Some(name.clone()),
+            name,
+            root,
+            prefix: String::new(),
+        })
+    }
+
+    /// 去掉项目url前缀后的请求地址
+    pub fn request_path<'a>(&self, req_path: &'a str) -> &'a str {
+        if self.prefix.is_empty() {
+            return req_path;
+        }
+        match req_path.strip_prefix(&self.prefix) {
+            Some("") => "/",
+            Some(p) => p,
+            None => req_path,
+        }
+    }
+
+    /// 项目在线文档的访问地址
+    pub fn doc_url(&self, port: u16) -> String {
+        match &self.host {
+            Some(host) => format!("//{}:{}/", host, port),
+            None => format!("{}/?server_url={}", self.prefix, self.prefix),
+        }
+    }
+}
+
+fn parse_project_arg(arg: &str) -> Result<(String, PathBuf), String> {
+    let mut parts = arg.splitn(2, '=');
+    let name = parts.next().unwrap_or("").trim();
+    let path = parts.next().unwrap_or("").trim();
+    if name.is_empty() || path.is_empty() {
+        return Err(format!("project {} should be name=path", arg));
+    }
+    let root = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("project {} path {} error: {}", name, path, e))?;
+    if !root.is_dir() {
+        return Err(format!("project {} path {} is not a folder", name, path));
+    }
+    Ok((name.to_string(), root))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn project_arg_test() {
+        let project = Project::from_prefix_arg("svc-order=./src").unwrap();
+        assert_eq!("/svc-order", project.prefix);
+        assert!(project.root.is_absolute());
+        assert_eq!("/user/", project.request_path("/svc-order/user/"));
+        assert_eq!("/", project.request_path("/svc-order"));
+
+        assert!(Project::from_prefix_arg("svc-order").is_err());
+        assert!(Project::from_prefix_arg("a/b=./src").is_err());
+        assert!(Project::from_host_arg("order.local=./not-exists").is_err());
+    }
+}
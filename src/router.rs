@@ -0,0 +1,323 @@
+//! 接口url的路由表
+//! 在加载接口文档时把带参数的url编译好，并按照优先级排序，请求时不需要再逐个生成ResourceDef
+//! 优先级规则, 从前往后按路径段比较:
+//! 静态路径段 > 带文字的参数段(如 file-{id}.json) > 带正则的参数段 > 普通参数段 > 尾部通配,
+//! 前面的路径段都相同的时候，路径段多的优先
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use regex::{escape, Regex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SegmentRank {
+    Static,
+    Mixed,
+    Regex,
+    Param,
+    Tail,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    url: String,
+    regex: Regex,
+    ranks: Vec<SegmentRank>,
+    // 参数名统一替换后的url，用于判断两个url定义是否完全重叠
+    shape: String,
+    // 第一个路径段是静态的时候，用它来快速筛选
+    first_segment: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    by_first_segment: HashMap<String, Vec<usize>>,
+    any_first_segment: Vec<usize>,
+    /// 有歧义的url定义, 同一个请求地址可以匹配多个url
+    pub conflicts: Vec<String>,
+    /// 无法解析的url定义
+    pub errors: Vec<String>,
+}
+
+impl Router {
+    /// 根据所有的接口url生成路由表
+    /// 如果提供了旧的路由表，已经编译过的url会直接复用
+    pub fn build<'a, I>(urls: I, old_router: Option<&Router>) -> Router
+    where
+        I: Iterator<Item = &'a String>,
+    {
+        let mut compiled: HashMap<&str, &Route> = HashMap::new();
+        if let Some(old_router) = old_router {
+            for route in &old_router.routes {
+                compiled.insert(&route.url, route);
+            }
+        }
+
+        let mut routes = Vec::new();
+        let mut errors = Vec::new();
+        for url in urls {
+            if !is_dynamic_url(url) {
+                // 静态url直接通过url查找，不需要进入路由表
+                continue;
+            }
+            if let Some(route) = compiled.get(url.as_str()) {
+                routes.push((*route).clone());
+                continue;
+            }
+            match compile_route(url) {
+                Ok(route) => routes.push(route),
+                Err(e) => {
+                    log::error!("api url {} is invalid: {}", url, e);
+                    errors.push(format!("{}: {}", url, e));
+                }
+            }
+        }
+        routes.sort_by(compare_route);
+
+        let mut conflicts = Vec::new();
+        for (i, route) in routes.iter().enumerate() {
+            for other in &routes[i + 1..] {
+                if other.ranks != route.ranks {
+                    break;
+                }
+                if other.shape == route.shape {
+                    let conflict = format!(
+                        "{} and {} match the same request urls, {} is used",
+                        route.url, other.url, route.url
+                    );
+                    log::warn!("{}", conflict);
+                    conflicts.push(conflict);
+                }
+            }
+        }
+
+        let mut by_first_segment: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut any_first_segment = Vec::new();
+        for (i, route) in routes.iter().enumerate() {
+            match &route.first_segment {
+                Some(s) => by_first_segment.entry(s.to_string()).or_default().push(i),
+                None => any_first_segment.push(i),
+            }
+        }
+
+        Router {
+            routes,
+            by_first_segment,
+            any_first_segment,
+            conflicts,
+            errors,
+        }
+    }
+
+    /// 找到请求地址匹配的接口url，按优先级返回第一个
+    pub fn find(&self, path: &str) -> Option<&str> {
+        let first_segment = path.trim_start_matches('/').split('/').next().unwrap_or("");
+        let empty = Vec::new();
+        let a = self.by_first_segment.get(first_segment).unwrap_or(&empty);
+        let b = &self.any_first_segment;
+
+        // 两个列表都是按优先级排好序的，合并着依次匹配
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            let index = if j >= b.len() || (i < a.len() && a[i] < b[j]) {
+                i += 1;
+                a[i - 1]
+            } else {
+                j += 1;
+                b[j - 1]
+            };
+            let route = &self.routes[index];
+            if route.regex.is_match(path) {
+                return Some(&route.url);
+            }
+        }
+        None
+    }
+}
+
+/// 是否是需要正则匹配的url, 包含参数或者以*结尾
+pub fn is_dynamic_url(url: &str) -> bool {
+    url.contains('{') || url.ends_with('*')
+}
+
+fn compare_route(a: &Route, b: &Route) -> Ordering {
+    for (x, y) in a.ranks.iter().zip(b.ranks.iter()) {
+        if x != y {
+            return x.cmp(y);
+        }
+    }
+    // 前面都一样，路径段多的优先
+    b.ranks
+        .len()
+        .cmp(&a.ranks.len())
+        .then_with(|| a.url.cmp(&b.url))
+}
+
+/// 按照actix ResourceDef相同的规则把url转为正则
+fn compile_route(url: &str) -> Result<Route, String> {
+    let mut re = String::from("^");
+    let mut shape = String::new();
+    let mut is_tail = false;
+    let mut rest = url;
+
+    if !url.contains('{') {
+        // 以*结尾的静态url, 表示前缀匹配
+        let prefix = &url[..url.len() - 1];
+        re.push_str(&escape(prefix));
+        re.push_str("(.*)");
+        shape.push_str(url);
+        is_tail = true;
+        rest = "";
+    }
+
+    while let Some(idx) = rest.find('{') {
+        let (prefix, rem) = rest.split_at(idx);
+        re.push_str(&escape(prefix));
+        shape.push_str(prefix);
+
+        let mut nesting = 0usize;
+        let close_idx = rem
+            .find(|c| match c {
+                '{' => {
+                    nesting += 1;
+                    false
+                }
+                '}' => {
+                    nesting -= 1;
+                    nesting == 0
+                }
+                _ => false,
+            })
+            .ok_or_else(|| "malformed dynamic segment".to_string())?;
+        let param = &rem[1..close_idx];
+        rest = &rem[close_idx + 1..];
+        let tail = rest.starts_with('*');
+
+        match param.find(':') {
+            Some(i) => {
+                if tail {
+                    return Err("custom regex is not supported for remainder match".to_string());
+                }
+                re.push_str(&format!("(?P<{}>{})", &param[..i], &param[i + 1..]));
+                shape.push_str(&format!("{{:{}}}", &param[i + 1..]));
+            }
+            None => {
+                if tail {
+                    rest = &rest[1..];
+                    is_tail = true;
+                    re.push_str(&format!("(?P<{}>.*)", param));
+                    shape.push_str("{}*");
+                } else {
+                    re.push_str(&format!("(?P<{}>[^/]+)", param));
+                    shape.push_str("{}");
+                }
+            }
+        }
+    }
+    re.push_str(&escape(rest));
+    shape.push_str(rest);
+    if !is_tail {
+        re.push('$');
+    }
+
+    let regex = Regex::new(&re).map_err(|e| format!("{}", e))?;
+
+    let segments = split_segments(url);
+    let mut ranks = Vec::new();
+    for segment in &segments {
+        let rank = if segment.ends_with('*') {
+            SegmentRank::Tail
+        } else if !segment.contains('{') {
+            SegmentRank::Static
+        } else if !segment.starts_with('{') || !segment.ends_with('}') {
+            SegmentRank::Mixed
+        } else if segment.contains(':') {
+            SegmentRank::Regex
+        } else {
+            SegmentRank::Param
+        };
+        ranks.push(rank);
+        if rank == SegmentRank::Tail {
+            break;
+        }
+    }
+
+    let first_segment = match (segments.first(), ranks.first()) {
+        (Some(s), Some(SegmentRank::Static)) if segments.len() > 1 || !is_tail => {
+            Some(s.to_string())
+        }
+        _ => None,
+    };
+
+    Ok(Route {
+        url: url.to_string(),
+        regex,
+        ranks,
+        shape,
+        first_segment,
+    })
+}
+
+/// 按/分割url的路径段，参数正则里面的/不分割
+fn split_segments(url: &str) -> Vec<&str> {
+    let url = url.trim_start_matches('/');
+    let mut segments = Vec::new();
+    let mut nesting = 0usize;
+    let mut start = 0;
+    for (i, c) in url.char_indices() {
+        match c {
+            '{' => nesting += 1,
+            '}' => nesting = nesting.saturating_sub(1),
+            '/' if nesting == 0 => {
+                segments.push(&url[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    segments.push(&url[start..]);
+    segments
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn build(urls: &[&str]) -> Router {
+        let urls: Vec<String> = urls.iter().map(|x| x.to_string()).collect();
+        Router::build(urls.iter(), None)
+    }
+
+    #[test]
+    fn router_precedence_test() {
+        let router = build(&[
+            "/user/{id}",
+            "/user/{id:\\d+}",
+            "/user/{id}/profile",
+            "/files/{path}*",
+            "/files/{name}.json",
+            "/{category}/list",
+            "/api/*",
+        ]);
+        assert_eq!(Some("/user/{id:\\d+}"), router.find("/user/12"));
+        assert_eq!(Some("/user/{id}"), router.find("/user/me"));
+        assert_eq!(Some("/user/{id}/profile"), router.find("/user/me/profile"));
+        assert_eq!(Some("/files/{name}.json"), router.find("/files/a.json"));
+        assert_eq!(Some("/files/{path}*"), router.find("/files/a/b.json"));
+        assert_eq!(Some("/user/{id}"), router.find("/user/list"));
+        assert_eq!(Some("/{category}/list"), router.find("/order/list"));
+        assert_eq!(Some("/api/*"), router.find("/api/v1/order"));
+        assert_eq!(None, router.find("/order/"));
+        assert!(router.conflicts.is_empty());
+    }
+
+    #[test]
+    fn router_conflict_test() {
+        let router = build(&["/user/{id}", "/user/{name}", "/user/{id:\\d+", "/user/me"]);
+        assert_eq!(1, router.conflicts.len());
+        assert_eq!(1, router.errors.len());
+        // 歧义的定义按url排序，结果是确定的
+        assert_eq!(Some("/user/{id}"), router.find("/user/12"));
+    }
+}
@@ -0,0 +1,703 @@
+//! 把接口的url_param, query, body和response模型转换成JSON Schema(draft 2020-12), 方便在测试中校验数据
+//! 模型已经处理过$ref, $include, $exclude, $del和a/b这样的嵌套修改, rec递归转换成$ref和$defs
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::Path;
+
+use actix_web::{web, HttpResponse};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::codegen::model::{
+    field_desc, is_empty_model, pascal_case, pointer_type_name, unique_name, url_words,
+};
+use crate::db;
+use crate::version;
+
+pub const SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// 接口中可以生成schema的部分
+pub const PARTS: [&str; 4] = ["url_param", "query", "body", "response"];
+
+#[derive(Deserialize, Debug)]
+pub struct SchemaRequest {
+    url: String,
+    method: Option<String>,
+    /// 只返回这一部分的schema: url_param, query, body 或者 response
+    part: Option<String>,
+    version: Option<String>,
+}
+
+fn get_part_model<'a>(api: &'a db::ApiData, part: &str) -> Option<&'a Value> {
+    match part {
+        "url_param" => Some(&api.url_param),
+        "query" => Some(&api.query),
+        "body" => Some(&api.body),
+        "response" => Some(&api.response),
+        _ => None,
+    }
+}
+
+/// 接口一个请求方法的所有schema, 没有定义字段的部分是Null
+pub fn get_api_schemas(api: &db::ApiData, method: &str) -> Map<String, Value> {
+    let mut schemas = Map::new();
+    for part in PARTS.iter() {
+        let model = get_part_model(api, part).unwrap_or(&Value::Null);
+        let title = format!("{} {} {}", method, api.url, part);
+        schemas.insert(part.to_string(), model_schema(model, &title));
+    }
+    schemas
+}
+
+/// 模型转换成一个完整的schema, 没有定义字段时返回Null
+pub fn model_schema(model: &Value, title: &str) -> Value {
+    if is_empty_model(model) {
+        return Value::Null;
+    }
+    let mut converter = Converter {
+        targets: BTreeSet::new(),
+        defs: Map::new(),
+    };
+    collect_rec_targets(model, &mut converter.targets);
+
+    let mut schema = match converter.convert(model, "") {
+        Value::Object(schema) => schema,
+        _ => Map::new(),
+    };
+    // 递归引用的节点没有在转换中遇到时单独生成
+    let targets: Vec<String> = converter.targets.iter().cloned().collect();
+    for target in targets {
+        let name = def_name(&target);
+        if !converter.defs.contains_key(&name) {
+            if let Some(node) = model.pointer(&target) {
+                let node_schema = converter.convert_node(node, &target);
+                converter.defs.insert(name, node_schema);
+            }
+        }
+    }
+
+    schema.insert("$schema".to_string(), json!(SCHEMA_DRAFT));
+    schema.insert("title".to_string(), json!(title));
+    if !converter.defs.is_empty() {
+        schema.insert("$defs".to_string(), Value::Object(converter.defs));
+    }
+    Value::Object(schema)
+}
+
+/// 按模型检查数据, 返回第一个不符合的字段, 没有定义字段的模型不检查
+pub fn validate_model(model: &Value, value: &Value) -> Result<(), String> {
+    let schema = model_schema(model, "");
+    if schema.is_null() {
+        return Ok(());
+    }
+    validate(&schema, &schema, value, "")
+}
+
+/// 按model_schema生成的schema检查数据, 只支持生成时用到的关键字
+fn validate(root: &Value, schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    let name = if path.is_empty() { "/" } else { path };
+    if let Some(reference) = schema.get("$ref").and_then(|v| v.as_str()) {
+        return match root.pointer(reference.trim_start_matches('#')) {
+            Some(target) => validate(root, target, value, path),
+            None => Ok(()),
+        };
+    }
+    if let Some(Value::Array(values)) = schema.get("enum") {
+        if !values.contains(value) {
+            return Err(format!("{} should be one of {}", name, json!(values)));
+        }
+    }
+    if let Some(Value::Array(schemas)) = schema.pointer("/items/anyOf") {
+        if let Value::Array(items) = value {
+            for (i, item) in items.iter().enumerate() {
+                let item_path = format!("{}/{}", path, i);
+                if !schemas
+                    .iter()
+                    .any(|s| validate(root, s, item, &item_path).is_ok())
+                {
+                    return Err(format!("{} matches none of the item models", item_path));
+                }
+            }
+        }
+    }
+
+    let schema_type = schema.get("type").and_then(|v| v.as_str()).unwrap_or("");
+    let is_type = match schema_type {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        _ => true,
+    };
+    if !is_type {
+        return Err(format!("{} should be {}", name, schema_type));
+    }
+
+    let get_u64 = |key: &str| schema.get(key).and_then(|v| v.as_u64());
+    let check_len = |len: usize, min: &str, max: &str, unit: &str| -> Result<(), String> {
+        if let Some(min) = get_u64(min) {
+            if (len as u64) < min {
+                return Err(format!("{} should have at least {} {}", name, min, unit));
+            }
+        }
+        if let Some(max) = get_u64(max) {
+            if len as u64 > max {
+                return Err(format!("{} should have at most {} {}", name, max, unit));
+            }
+        }
+        Ok(())
+    };
+    match value {
+        Value::Object(obj) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if !obj.contains_key(key) {
+                        return Err(format!("{}/{} is required", path, key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(|v| v.as_object());
+            let additional = schema.get("additionalProperties");
+            for (key, v) in obj {
+                let field_path = format!("{}/{}", path, key);
+                if let Some(field_schema) = properties.and_then(|p| p.get(key)).or(additional) {
+                    validate(root, field_schema, v, &field_path)?;
+                }
+            }
+            check_len(obj.len(), "minProperties", "maxProperties", "fields")?;
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items").filter(|s| s.get("anyOf").is_none()) {
+                for (i, item) in items.iter().enumerate() {
+                    validate(root, item_schema, item, &format!("{}/{}", path, i))?;
+                }
+            }
+            check_len(items.len(), "minItems", "maxItems", "items")?;
+        }
+        Value::String(text) => {
+            check_len(text.chars().count(), "minLength", "maxLength", "chars")?;
+            if let Some(pattern) = schema.get("pattern").and_then(|v| v.as_str()) {
+                if let Ok(re) = Regex::new(pattern) {
+                    if !re.is_match(text) {
+                        return Err(format!("{} should match {}", name, pattern));
+                    }
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(0.0);
+            if let Some(min) = schema.get("minimum").and_then(|v| v.as_f64()) {
+                if n < min {
+                    return Err(format!("{} should be >= {}", name, min));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(|v| v.as_f64()) {
+                if n > max {
+                    return Err(format!("{} should be <= {}", name, max));
+                }
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+struct Converter {
+    /// rec引用的节点路径, 不包括根节点
+    targets: BTreeSet<String>,
+    defs: Map<String, Value>,
+}
+
+impl Converter {
+    /// 被递归引用的节点放到$defs中, 原来的位置改为引用
+    fn convert(&mut self, attr: &Value, path: &str) -> Value {
+        if !path.is_empty() && self.targets.contains(path) {
+            let name = def_name(path);
+            if !self.defs.contains_key(&name) {
+                // 先占位, 节点内部的递归引用不会再次生成
+                self.defs.insert(name.to_string(), json!({}));
+                let schema = self.convert_node(attr, path);
+                self.defs.insert(name.to_string(), schema);
+            }
+            return json!({ "$ref": format!("#/$defs/{}", name) });
+        }
+        self.convert_node(attr, path)
+    }
+
+    fn convert_node(&mut self, attr: &Value, path: &str) -> Value {
+        if let Value::Array(items) = attr {
+            return self.array_schema(items, path);
+        }
+        if !attr.is_object() {
+            return json!({});
+        }
+
+        let mut schema = Map::new();
+        let field_type = db::get_field_type(attr);
+        if let Some(values) = enum_values(attr) {
+            schema.insert("enum".to_string(), Value::Array(values));
+        } else {
+            match field_type.as_str() {
+                "object" => self.object_schema(attr, path, &mut schema),
+                "map" => {
+                    schema.insert("type".to_string(), json!("object"));
+                    if let Some(value) = attr.get("$value") {
+                        let value_path = format!("{}/$value", path);
+                        schema.insert(
+                            "additionalProperties".to_string(),
+                            self.convert(value, &value_path),
+                        );
+                    }
+                    insert_u64(
+                        &mut schema,
+                        "minProperties",
+                        attr,
+                        &["$min_length", "$length"],
+                    );
+                    insert_u64(
+                        &mut schema,
+                        "maxProperties",
+                        attr,
+                        &["$max_length", "$length"],
+                    );
+                }
+                "rec" => {
+                    let pointer = rec_pointer(attr);
+                    let reference = if pointer.is_empty() {
+                        "#".to_string()
+                    } else {
+                        format!("#/$defs/{}", def_name(&pointer))
+                    };
+                    schema.insert("$ref".to_string(), json!(reference));
+                }
+                field_type => scalar_schema(field_type, attr, &mut schema),
+            }
+        }
+
+        let desc = field_desc(attr);
+        if !desc.is_empty() {
+            schema.insert("description".to_string(), json!(desc));
+        }
+        let deprecated = attr
+            .get("deprecated")
+            .filter(|v| version::is_lifecycle_deprecated(v))
+            .or_else(|| attr.get("$deprecated"));
+        if !version::parse_deprecated(deprecated).is_null() {
+            schema.insert("deprecated".to_string(), json!(true));
+        }
+        Value::Object(schema)
+    }
+
+    fn object_schema(&mut self, attr: &Value, path: &str, schema: &mut Map<String, Value>) {
+        schema.insert("type".to_string(), json!("object"));
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        if let Some(obj) = attr.as_object() {
+            for (key, field_attr) in obj {
+                // a/b 这样的嵌套修改已经合并到字段中
+                if key.starts_with('$') || key.contains('/') {
+                    continue;
+                }
+                // 字段的废弃信息不是子字段, 叫deprecated的子字段还是子字段
+                if key == "deprecated" && version::is_lifecycle_deprecated(field_attr) {
+                    continue;
+                }
+                if !field_attr.is_object() && !field_attr.is_array() {
+                    continue;
+                }
+                if field_attr.get("$del").and_then(|v| v.as_bool()) == Some(true) {
+                    continue;
+                }
+                let is_required = field_attr
+                    .get("required")
+                    .or_else(|| field_attr.get("$required"))
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true);
+                if is_required {
+                    required.push(json!(key));
+                }
+                let field_path = format!("{}/{}", path, key);
+                properties.insert(key.to_string(), self.convert(field_attr, &field_path));
+            }
+        }
+        schema.insert("properties".to_string(), Value::Object(properties));
+        if !required.is_empty() {
+            schema.insert("required".to_string(), Value::Array(required));
+        }
+    }
+
+    fn array_schema(&mut self, items: &[Value], path: &str) -> Value {
+        let mut schema = Map::new();
+        schema.insert("type".to_string(), json!("array"));
+        let mut item_schemas = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            if item.is_null() || item.get("$__continue_array_value").is_some() {
+                continue;
+            }
+            item_schemas.push(self.convert(item, &format!("{}/{}", path, i)));
+        }
+        match item_schemas.len() {
+            0 => {}
+            1 => {
+                schema.insert("items".to_string(), item_schemas.remove(0));
+            }
+            _ => {
+                schema.insert("items".to_string(), json!({ "anyOf": item_schemas }));
+            }
+        }
+        // 数组的长度设置在元素上
+        if let Some(item) = items.first() {
+            insert_u64(&mut schema, "minItems", item, &["$min_length", "$length"]);
+            insert_u64(&mut schema, "maxItems", item, &["$max_length", "$length"]);
+        }
+        Value::Object(schema)
+    }
+}
+
+/// 字段类型对应的schema
+fn scalar_schema(field_type: &str, attr: &Value, schema: &mut Map<String, Value>) {
+    let (schema_type, format) = match field_type {
+        "number" | "int" | "posint" | "negint" | "timestamp" => ("integer", None),
+        "float" | "posfloat" | "negfloat" => ("number", None),
+        "bool" => ("boolean", None),
+        "date" => ("string", Some("date")),
+        "uuid" => ("string", Some("uuid")),
+        "email" => ("string", Some("email")),
+        "url" => ("string", Some("uri")),
+        "ip" => ("string", Some("ipv4")),
+        "domain" => ("string", Some("hostname")),
+        _ => ("string", None),
+    };
+    schema.insert("type".to_string(), json!(schema_type));
+    if let Some(format) = format {
+        schema.insert("format".to_string(), json!(format));
+    }
+
+    match field_type {
+        "number" | "int" | "posint" | "negint" | "float" | "posfloat" | "negfloat" => {
+            number_range(field_type, attr, schema);
+        }
+        "datetime" => {
+            // mock数据的时间格式是 2020-02-02 14:16:18
+            schema.insert(
+                "pattern".to_string(),
+                json!(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}$"),
+            );
+        }
+        "regex" => {
+            let regex = attr.get("regex").and_then(|v| v.as_str()).unwrap_or("");
+            if !regex.trim().is_empty() {
+                schema.insert(
+                    "pattern".to_string(),
+                    json!(format!("^(?:{})$", regex.trim())),
+                );
+            }
+        }
+        "string" | "name" | "cname" | "word" | "title" | "sentence" | "summary" | "paragraph"
+        | "cw" | "ct" | "cs" | "cm" | "cp" => {
+            insert_u64(schema, "minLength", attr, &["min_length", "length"]);
+            insert_u64(schema, "maxLength", attr, &["max_length", "length"]);
+        }
+        _ => {}
+    }
+}
+
+/// 数字的取值范围, pos和neg开头的类型分别是非负数和非正数
+fn number_range(field_type: &str, attr: &Value, schema: &mut Map<String, Value>) {
+    let get = |key: &str| attr.get(key).filter(|v| v.is_number()).cloned();
+    let minimum = get("min_value").or_else(|| {
+        if field_type.starts_with("pos") {
+            Some(json!(0))
+        } else {
+            None
+        }
+    });
+    let maximum = get("max_value").or_else(|| {
+        if field_type.starts_with("neg") {
+            Some(json!(0))
+        } else {
+            None
+        }
+    });
+    if let Some(minimum) = minimum {
+        schema.insert("minimum".to_string(), minimum);
+    }
+    if let Some(maximum) = maximum {
+        schema.insert("maximum".to_string(), maximum);
+    }
+}
+
+/// 按顺序取第一个设置的数字
+fn insert_u64(schema: &mut Map<String, Value>, key: &str, attr: &Value, attr_keys: &[&str]) {
+    if let Some(v) = attr_keys
+        .iter()
+        .find_map(|k| attr.get(k).and_then(|v| v.as_u64()))
+    {
+        schema.insert(key.to_string(), json!(v));
+    }
+}
+
+/// enum是值的列表, $enum是 [值, 说明] 的列表
+fn enum_values(attr: &Value) -> Option<Vec<Value>> {
+    if let Some(Value::Array(values)) = attr.get("enum") {
+        if !values.is_empty() {
+            return Some(values.clone());
+        }
+    }
+    if let Some(Value::Array(values)) = attr.get("$enum") {
+        let values: Vec<Value> = values.iter().filter_map(|v| v.get(0).cloned()).collect();
+        if !values.is_empty() {
+            return Some(values);
+        }
+    }
+    None
+}
+
+/// rec引用的节点路径, 根节点是空字符串
+fn rec_pointer(attr: &Value) -> String {
+    let pointer = attr.get("$ref").and_then(|v| v.as_str()).unwrap_or("/");
+    pointer.trim_end_matches('/').to_string()
+}
+
+fn collect_rec_targets(model: &Value, targets: &mut BTreeSet<String>) {
+    match model {
+        Value::Array(items) => {
+            for item in items {
+                collect_rec_targets(item, targets);
+            }
+        }
+        Value::Object(obj) => {
+            if db::get_field_type(model) == "rec" {
+                let pointer = rec_pointer(model);
+                if !pointer.is_empty() {
+                    targets.insert(pointer);
+                }
+                return;
+            }
+            for value in obj.values() {
+                collect_rec_targets(value, targets);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// $defs中的名称, /data/children/0 => DataChildrenItem
+fn def_name(pointer: &str) -> String {
+    pointer_type_name("", pointer)
+}
+
+/// 按url和请求方法找到接口, url可以是接口文档中的url, 也可以是实际的请求地址
+fn find_api(
+    data: &db::Database,
+    url: &str,
+    method: Option<&str>,
+    version: Option<&str>,
+) -> Option<std::sync::Arc<db::ApiData>> {
+    let apis = version::select_api_versions(data.find_api_data(url)?, version);
+    match method {
+        Some(method) => apis
+            .into_iter()
+            .find(|api| api.method.iter().any(|m| m.eq_ignore_ascii_case(method))),
+        None => apis.into_iter().next(),
+    }
+}
+
+/// 接口的JSON Schema, 带上part参数时只返回这一部分的schema
+pub async fn schema_view(
+    req_get: web::Query<SchemaRequest>,
+    db_data: web::Data<db::DataStore>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let method = req_get.method.as_deref().filter(|m| !m.is_empty());
+    let req_version = req_get.version.as_deref().filter(|v| !v.is_empty());
+    let api = match find_api(&data, &req_get.url, method, req_version) {
+        Some(api) => api,
+        None => return HttpResponse::Ok().json(json!({
+            "code": -1,
+            "msg": format!("no api match url {} and method {}", req_get.url, method.unwrap_or("*"))
+        })),
+    };
+    let method = match method {
+        Some(method) => method.to_uppercase(),
+        None => api.method.first().cloned().unwrap_or_default(),
+    };
+    let mut schemas = get_api_schemas(&api, &method);
+
+    if let Some(part) = req_get.part.as_deref().filter(|p| !p.is_empty()) {
+        return match schemas.remove(part) {
+            Some(Value::Null) => HttpResponse::Ok().json(json!({
+                "code": -1,
+                "msg": format!("{} {} has no {} fields", method, api.url, part)
+            })),
+            Some(schema) => HttpResponse::Ok().json(schema),
+            None => HttpResponse::Ok().json(json!({
+                "code": -1,
+                "msg": format!("unknown part {}, available: {}", part, PARTS.join(", "))
+            })),
+        };
+    }
+
+    HttpResponse::Ok().json(json!({
+        "code": 0,
+        "url": api.url,
+        "method": method,
+        "schemas": schemas,
+    }))
+}
+
+/// 把所有接口的schema导出到out目录, 每个接口的每个部分一个文件, index.json是接口和文件的对应关系
+pub fn export_schema(root: &Path, out: &Path) -> Result<(), String> {
+    let data = db::Database::load(root);
+    fs::create_dir_all(out).map_err(|e| format!("create {} error: {}", out.display(), e))?;
+
+    let mut api_docs: Vec<&db::ApiDoc> = data.api_docs.values().collect();
+    api_docs.sort_by(|a, b| a.order.cmp(&b.order).then(a.filename.cmp(&b.filename)));
+
+    let mut names = HashSet::new();
+    let mut index = Vec::new();
+    for api_doc in api_docs {
+        for api in &api_doc.apis {
+            for method in &api.method {
+                let name = pascal_case(&format!(
+                    "{} {}",
+                    method.to_lowercase(),
+                    url_words(&api.url)
+                ));
+                let name = unique_name(&mut names, &name);
+                let mut files = Map::new();
+                for (part, schema) in get_api_schemas(api, method) {
+                    if schema.is_null() {
+                        continue;
+                    }
+                    let filename = format!("{}{}.schema.json", name, pascal_case(&part));
+                    let content = serde_json::to_string_pretty(&schema).unwrap_or_default();
+                    fs::write(out.join(&filename), content)
+                        .map_err(|e| format!("write {} error: {}", filename, e))?;
+                    files.insert(part, json!(filename));
+                }
+                if files.is_empty() {
+                    continue;
+                }
+                index.push(json!({
+                    "doc": api_doc.filename,
+                    "name": api.name,
+                    "method": method,
+                    "url": api.url,
+                    "version": api.version,
+                    "schemas": files,
+                }));
+            }
+        }
+    }
+
+    let content = serde_json::to_string_pretty(&index).unwrap_or_default();
+    fs::write(out.join("index.json"), content)
+        .map_err(|e| format!("write index.json error: {}", e))?;
+    println!(
+        "exported the schemas of {} apis to {}",
+        index.len(),
+        out.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn model_schema_test() {
+        let model = json!({
+            "id": {"name": "ID", "type": "posint"},
+            "status": {"enum": ["on", "off"], "required": false},
+            "tree": {
+                "$type": "object",
+                "title": {"max_length": 20},
+                "children": [{"$type": "rec", "$ref": "/tree"}],
+            },
+            "parent": {"$type": "rec", "$ref": "/"},
+        });
+        let schema = model_schema(&model, "GET /nodes/ response");
+        assert_eq!(
+            json!({
+                "$schema": SCHEMA_DRAFT,
+                "title": "GET /nodes/ response",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer", "minimum": 0, "description": "ID"},
+                    "status": {"enum": ["on", "off"]},
+                    "tree": {"$ref": "#/$defs/Tree"},
+                    "parent": {"$ref": "#"},
+                },
+                "required": ["id", "parent", "tree"],
+                "$defs": {
+                    "Tree": {
+                        "type": "object",
+                        "properties": {
+                            "title": {"type": "string", "maxLength": 20},
+                            "children": {"type": "array", "items": {"$ref": "#/$defs/Tree"}},
+                        },
+                        "required": ["children", "title"],
+                    }
+                }
+            }),
+            schema
+        );
+        assert!(model_schema(&json!({}), "").is_null());
+    }
+
+    #[test]
+    fn deprecated_field_schema_test() {
+        let model = json!({
+            "nick": {"name": "nick", "deprecated": {"replacement": "name"}},
+            "deprecated": {"type": "bool"},
+            "flags": {"deprecated": {"type": "bool"}},
+        });
+        let schema = model_schema(&model, "");
+        assert_eq!(json!(true), schema["properties"]["nick"]["deprecated"]);
+        assert_eq!(json!("boolean"), schema["properties"]["deprecated"]["type"]);
+        assert_eq!(
+            json!(["deprecated"]),
+            schema["properties"]["flags"]["required"]
+        );
+        assert!(schema["properties"]["flags"].get("deprecated").is_none());
+        assert_eq!(json!(["deprecated", "flags", "nick"]), schema["required"]);
+    }
+
+    #[test]
+    fn validate_model_test() {
+        let model = json!({
+            "action": {"enum": ["ping", "query"]},
+            "id": {"type": "posint", "required": false},
+            "tags": [{"name": "tag", "max_length": 3}],
+        });
+        let message = json!({"action": "query", "id": 3, "tags": ["a"]});
+        assert_eq!(Ok(()), validate_model(&model, &message));
+        assert_eq!(
+            Err("/action is required".to_string()),
+            validate_model(&model, &json!({"tags": []}))
+        );
+        assert_eq!(
+            Err("/id should be integer".to_string()),
+            validate_model(&model, &json!({"action": "ping", "id": "3", "tags": []}))
+        );
+        assert_eq!(
+            Err("/id should be >= 0".to_string()),
+            validate_model(&model, &json!({"action": "ping", "id": -1, "tags": []}))
+        );
+        assert_eq!(
+            Err("/tags/0 should have at most 3 chars".to_string()),
+            validate_model(&model, &json!({"action": "ping", "tags": ["abcd"]}))
+        );
+        assert_eq!(
+            Err("/ should be object".to_string()),
+            validate_model(&model, &json!("hello"))
+        );
+        assert_eq!(Ok(()), validate_model(&Value::Null, &json!("hello")));
+    }
+}
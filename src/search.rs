@@ -0,0 +1,447 @@
+//! 文档的全文搜索
+//! 索引在加载文档时生成, 按文件保存, 文件变化后只更新这个文件的索引
+//! 搜索范围: 文档名称和说明, 接口名称、url、说明, body/query/response的字段名和字段说明, markdown内容
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::codegen::model::field_desc;
+use crate::db;
+
+/// 默认返回的结果数
+const DEFAULT_LIMIT: usize = 20;
+/// 匹配内容前后保留的字符数
+const SNIPPET_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchField {
+    /// body, query 或者 response
+    pub part: String,
+    /// 字段路径, 如 data/refund_amount
+    pub path: String,
+    pub desc: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchEntry {
+    /// doc: 接口文档, api: 接口, md: markdown文档
+    pub kind: String,
+    pub doc: String,
+    pub doc_name: String,
+    pub name: String,
+    pub url: String,
+    pub method: Vec<String>,
+    pub desc: String,
+    pub fields: Vec<SearchField>,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    // {filename: [entry]}
+    files: BTreeMap<String, Vec<SearchEntry>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchedField {
+    pub part: String,
+    pub path: String,
+    pub desc: String,
+    pub highlight: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub kind: String,
+    pub doc: String,
+    pub doc_name: String,
+    pub name: String,
+    pub url: String,
+    pub method: Vec<String>,
+    pub score: u32,
+    /// 名称和url中匹配的部分用 <em> 标记
+    pub name_highlight: String,
+    pub url_highlight: String,
+    /// 说明或者markdown内容中匹配的片段
+    pub snippet: String,
+    pub fields: Vec<MatchedField>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SearchRequest {
+    q: String,
+    method: Option<String>,
+    doc: Option<String>,
+    limit: Option<usize>,
+}
+
+impl SearchIndex {
+    /// 生成所有接口文档和markdown文档的索引
+    pub fn build(data: &db::Database, root: &Path) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        let mut files: Vec<String> = data.api_docs.keys().cloned().collect();
+        collect_md_files(&data.menus, &mut files);
+        for file in files {
+            index.update(data, root, &file);
+        }
+        index
+    }
+
+    /// 文件变化后更新它的索引, 文件删除后移除
+    pub fn update(&mut self, data: &db::Database, root: &Path, doc_file: &str) {
+        let entries = index_file(data, root, doc_file);
+        if entries.is_empty() {
+            self.files.remove(doc_file);
+        } else {
+            self.files.insert(doc_file.to_string(), entries);
+        }
+    }
+
+    /// 按关键词搜索, 所有的词都要匹配, 可以按请求方法和文档文件过滤
+    pub fn search(
+        &self,
+        q: &str,
+        method: Option<&str>,
+        doc: Option<&str>,
+        limit: usize,
+    ) -> (usize, Vec<SearchResult>) {
+        let terms: Vec<String> = q.split_whitespace().map(|t| t.to_lowercase()).collect();
+        if terms.is_empty() {
+            return (0, Vec::new());
+        }
+        let method = method.map(|m| m.to_uppercase());
+
+        let mut results = Vec::new();
+        for (filename, entries) in &self.files {
+            if doc.is_some() && doc != Some(filename.as_str()) {
+                continue;
+            }
+            for entry in entries {
+                if let Some(m) = &method {
+                    if !entry.method.iter().any(|em| em == m || em == "*") {
+                        continue;
+                    }
+                }
+                if let Some(result) = match_entry(entry, &terms) {
+                    results.push(result);
+                }
+            }
+        }
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.doc.cmp(&b.doc))
+                .then(a.name.cmp(&b.name))
+        });
+        let total = results.len();
+        results.truncate(limit);
+        (total, results)
+    }
+}
+
+fn collect_md_files(menus: &std::collections::HashMap<String, db::Menu>, files: &mut Vec<String>) {
+    for menu in menus.values() {
+        if menu.filename.ends_with(".md") {
+            files.push(menu.filename.to_string());
+        }
+        collect_md_files(&menu.children, files);
+    }
+}
+
+/// 一个文件的索引内容
+fn index_file(data: &db::Database, root: &Path, doc_file: &str) -> Vec<SearchEntry> {
+    let mut entries = Vec::new();
+    if doc_file.ends_with(".md") {
+        if !root.join(doc_file).exists() {
+            return entries;
+        }
+        let (mut order, mut name) = db::get_order_and_title_from_filename(doc_file, "md");
+        let mut desc = "".to_string();
+        let mut content = "".to_string();
+        let mut filename = "".to_string();
+        db::load_md_doc_config(
+            root,
+            doc_file,
+            &mut order,
+            &mut name,
+            &mut desc,
+            &mut content,
+            &mut filename,
+        );
+        entries.push(SearchEntry {
+            kind: "md".to_string(),
+            doc: doc_file.to_string(),
+            doc_name: name.to_string(),
+            name,
+            url: "".to_string(),
+            method: Vec::new(),
+            desc,
+            fields: Vec::new(),
+            content,
+        });
+        return entries;
+    }
+
+    let api_doc = match data.api_docs.get(doc_file) {
+        Some(api_doc) => api_doc,
+        None => return entries,
+    };
+    entries.push(SearchEntry {
+        kind: "doc".to_string(),
+        doc: doc_file.to_string(),
+        doc_name: api_doc.name.to_string(),
+        name: api_doc.name.to_string(),
+        url: "".to_string(),
+        method: Vec::new(),
+        desc: api_doc.desc.to_string(),
+        fields: Vec::new(),
+        content: "".to_string(),
+    });
+    for api in &api_doc.apis {
+        let mut fields = Vec::new();
+        for (part, model) in &[
+            ("body", &api.body),
+            ("query", &api.query),
+            ("response", &api.response),
+        ] {
+            db::walk_fields(model, &mut |path, attr| {
+                fields.push(SearchField {
+                    part: part.to_string(),
+                    path: path.to_string(),
+                    // 数组字段的说明写在元素上
+                    desc: match attr {
+                        Value::Array(items) => items.first().map(field_desc).unwrap_or_default(),
+                        _ => field_desc(attr),
+                    },
+                });
+            });
+        }
+        entries.push(SearchEntry {
+            kind: "api".to_string(),
+            doc: doc_file.to_string(),
+            doc_name: api_doc.name.to_string(),
+            name: api.name.to_string(),
+            url: api.url.to_string(),
+            method: api.method.clone(),
+            desc: api.desc.to_string(),
+            fields,
+            content: "".to_string(),
+        });
+    }
+    entries
+}
+
+fn match_entry(entry: &SearchEntry, terms: &[String]) -> Option<SearchResult> {
+    let name = entry.name.to_lowercase();
+    let url = entry.url.to_lowercase();
+    let desc = entry.desc.to_lowercase();
+    let content = entry.content.to_lowercase();
+
+    let mut score = 0;
+    let mut matched_fields: Vec<&SearchField> = Vec::new();
+    for term in terms {
+        let mut term_score = 0;
+        if name == *term {
+            term_score += 10;
+        } else if name.contains(term.as_str()) {
+            term_score += 6;
+        }
+        if url.contains(term.as_str()) {
+            term_score += 5;
+        }
+        if entry.doc_name.to_lowercase().contains(term.as_str()) {
+            term_score += 1;
+        }
+        for field in &entry.fields {
+            let field_name = field.path.rsplit('/').next().unwrap_or("").to_lowercase();
+            let field_score = if field_name == *term {
+                5
+            } else if field.path.to_lowercase().contains(term.as_str()) {
+                3
+            } else if field.desc.to_lowercase().contains(term.as_str()) {
+                2
+            } else {
+                0
+            };
+            if field_score > 0 {
+                term_score += field_score;
+                if !matched_fields.iter().any(|f| std::ptr::eq(*f, field)) {
+                    matched_fields.push(field);
+                }
+            }
+        }
+        if desc.contains(term.as_str()) {
+            term_score += 2;
+        }
+        if content.contains(term.as_str()) {
+            term_score += 1;
+        }
+        if term_score == 0 {
+            return None;
+        }
+        score += term_score;
+    }
+
+    let snippet_source = if !desc.is_empty() && terms.iter().any(|t| desc.contains(t.as_str())) {
+        &entry.desc
+    } else {
+        &entry.content
+    };
+    Some(SearchResult {
+        kind: entry.kind.to_string(),
+        doc: entry.doc.to_string(),
+        doc_name: entry.doc_name.to_string(),
+        name: entry.name.to_string(),
+        url: entry.url.to_string(),
+        method: entry.method.clone(),
+        score,
+        name_highlight: highlight(&entry.name, terms),
+        url_highlight: highlight(&entry.url, terms),
+        snippet: highlight(&get_snippet(snippet_source, terms), terms),
+        fields: matched_fields
+            .into_iter()
+            .map(|f| MatchedField {
+                part: f.part.to_string(),
+                path: f.path.to_string(),
+                desc: f.desc.to_string(),
+                highlight: highlight(&f.path, terms),
+            })
+            .collect(),
+    })
+}
+
+/// 第一个匹配位置前后的一段文字
+fn get_snippet(text: &str, terms: &[String]) -> String {
+    let lower = text.to_lowercase();
+    // 转小写后长度可能变化, 按字符位置截取
+    let position = terms
+        .iter()
+        .filter_map(|t| lower.find(t.as_str()))
+        .min()
+        .map(|i| lower[..i].chars().count());
+    let position = match position {
+        Some(p) => p,
+        None => return "".to_string(),
+    };
+    let chars: Vec<char> = text.chars().collect();
+    let start = position.saturating_sub(SNIPPET_CHARS);
+    let end = (position + SNIPPET_CHARS).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    snippet = snippet.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if start > 0 {
+        snippet = format!("...{}", snippet);
+    }
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// html转义后把匹配的词用 <em> 标记, 不区分大小写
+fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > lower.len() {
+            continue;
+        }
+        for i in 0..=lower.len() - term.len() {
+            if lower[i..i + term.len()] == term[..] {
+                for m in &mut marked[i..i + term.len()] {
+                    *m = true;
+                }
+            }
+        }
+    }
+
+    let mut result = String::new();
+    let mut in_mark = false;
+    for (c, m) in chars.iter().zip(marked) {
+        if m && !in_mark {
+            result.push_str("<em>");
+        } else if !m && in_mark {
+            result.push_str("</em>");
+        }
+        in_mark = m;
+        result.push_str(&escape_html(&c.to_string()));
+    }
+    if in_mark {
+        result.push_str("</em>");
+    }
+    result
+}
+
+/// 搜索接口 /__api_docs/search?q=refund&method=GET&doc=order.json5
+pub async fn search_view(
+    req_get: web::Query<SearchRequest>,
+    db_data: web::Data<db::DataStore>,
+) -> HttpResponse {
+    let data = db_data.load();
+    let (total, results) = data.search_index.search(
+        &req_get.q,
+        req_get.method.as_deref().filter(|m| !m.is_empty()),
+        req_get.doc.as_deref().filter(|d| !d.is_empty()),
+        req_get.limit.unwrap_or(DEFAULT_LIMIT),
+    );
+    HttpResponse::Ok().json(json!({
+        "code": 0,
+        "q": req_get.q,
+        "total": total,
+        "results": results,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn search_test() {
+        let entry = SearchEntry {
+            kind: "api".to_string(),
+            doc: "order.json5".to_string(),
+            doc_name: "Order".to_string(),
+            name: "refund order".to_string(),
+            url: "/orders/{id}/refund/".to_string(),
+            method: vec!["POST".to_string()],
+            desc: "".to_string(),
+            fields: vec![SearchField {
+                part: "response".to_string(),
+                path: "data/refund_amount".to_string(),
+                desc: "refund <amount>".to_string(),
+            }],
+            content: "".to_string(),
+        };
+        let mut index = SearchIndex::default();
+        index.files.insert("order.json5".to_string(), vec![entry]);
+
+        let (total, results) = index.search("refund_amount", None, None, 10);
+        assert_eq!(1, total);
+        assert_eq!(
+            "data/<em>refund_amount</em>",
+            results[0].fields[0].highlight
+        );
+        assert_eq!(0, index.search("refund_amount", Some("get"), None, 10).0);
+        assert_eq!(0, index.search("refund missing", None, None, 10).0);
+        assert_eq!(
+            "<em>Refund</em> &lt;a&gt;",
+            highlight("Refund <a>", &["refund".to_string()])
+        );
+    }
+}
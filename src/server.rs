@@ -1,6 +1,5 @@
 //! `ChatServer` is an actor. It maintains list of connection client session.
-//! And manages available rooms. Peers send messages to other peers in same
-//! room through `ChatServer`.
+//! And manages available rooms, every websocket api url is a room.
 
 use actix::prelude::*;
 use rand::{self, rngs::ThreadRng, Rng};
@@ -27,16 +26,13 @@ pub struct Disconnect {
     pub id: usize,
 }
 
-/// Send message to specific room
+/// Send message to all sessions in the room, return the number of sessions
 #[derive(Message)]
-#[rtype(result = "()")]
-pub struct ClientMessage {
-    /// Id of the client session
-    pub id: usize,
-    /// Peer message
-    pub msg: String,
+#[rtype(usize)]
+pub struct Broadcast {
     /// Room name
     pub room: String,
+    pub msg: String,
 }
 
 /// List of available rooms
@@ -56,8 +52,7 @@ pub struct Join {
     pub name: String,
 }
 
-/// `ChatServer` manages chat rooms and responsible for coordinating chat
-/// session. implementation is super primitive
+/// `ChatServer` manages websocket sessions of the websocket apis
 pub struct ChatServer {
     sessions: HashMap<usize, Recipient<Message>>,
     rooms: HashMap<String, HashSet<usize>>,
@@ -66,33 +61,14 @@ pub struct ChatServer {
 
 impl Default for ChatServer {
     fn default() -> ChatServer {
-        // default room
-        let mut rooms = HashMap::new();
-        rooms.insert("Main".to_owned(), HashSet::new());
-
         ChatServer {
             sessions: HashMap::new(),
-            rooms,
+            rooms: HashMap::new(),
             rng: rand::thread_rng(),
         }
     }
 }
 
-impl ChatServer {
-    /// Send message to all users in the room
-    fn send_message(&self, room: &str, message: &str, skip_id: usize) {
-        if let Some(sessions) = self.rooms.get(room) {
-            for id in sessions {
-                if *id != skip_id {
-                    if let Some(addr) = self.sessions.get(id) {
-                        let _ = addr.do_send(Message(message.to_owned()));
-                    }
-                }
-            }
-        }
-    }
-}
-
 /// Make actor from `ChatServer`
 impl Actor for ChatServer {
     /// We are going to use simple Context, we just need ability to communicate
@@ -107,18 +83,10 @@ impl Handler<Connect> for ChatServer {
     type Result = usize;
 
     fn handle(&mut self, msg: Connect, _: &mut Context<Self>) -> Self::Result {
-        println!("Someone joined");
-
-        // notify all users in same room
-        self.send_message(&"Main".to_owned(), "Someone joined", 0);
-
         // register session with random id
         let id = self.rng.gen::<usize>();
         self.sessions.insert(id, msg.addr);
 
-        // auto join session to Main room
-        self.rooms.get_mut(&"Main".to_owned()).unwrap().insert(id);
-
         // send id back
         id
     }
@@ -129,32 +97,33 @@ impl Handler<Disconnect> for ChatServer {
     type Result = ();
 
     fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
-        println!("Someone disconnected");
-
-        let mut rooms: Vec<String> = Vec::new();
-
         // remove address
         if self.sessions.remove(&msg.id).is_some() {
-            // remove session from all rooms
-            for (name, sessions) in &mut self.rooms {
-                if sessions.remove(&msg.id) {
-                    rooms.push(name.to_owned());
-                }
+            // remove session from all rooms, empty rooms are removed too
+            for sessions in self.rooms.values_mut() {
+                sessions.remove(&msg.id);
             }
-        }
-        // send message to other users
-        for room in rooms {
-            self.send_message(&room, "Someone disconnected", 0);
+            self.rooms.retain(|_, sessions| !sessions.is_empty());
         }
     }
 }
 
-/// Handler for Message message.
-impl Handler<ClientMessage> for ChatServer {
-    type Result = ();
+/// Handler for Broadcast message.
+impl Handler<Broadcast> for ChatServer {
+    type Result = usize;
 
-    fn handle(&mut self, msg: ClientMessage, _: &mut Context<Self>) {
-        self.send_message(&msg.room, msg.msg.as_str(), msg.id);
+    fn handle(&mut self, msg: Broadcast, _: &mut Context<Self>) -> Self::Result {
+        let mut count = 0;
+        if let Some(sessions) = self.rooms.get(&msg.room) {
+            for id in sessions {
+                if let Some(addr) = self.sessions.get(id) {
+                    if addr.do_send(Message(msg.msg.clone())).is_ok() {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
     }
 }
 
@@ -163,40 +132,21 @@ impl Handler<ListRooms> for ChatServer {
     type Result = MessageResult<ListRooms>;
 
     fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
-        let mut rooms = Vec::new();
-
-        for key in self.rooms.keys() {
-            rooms.push(key.to_owned())
-        }
-
-        MessageResult(rooms)
+        MessageResult(self.rooms.keys().cloned().collect())
     }
 }
 
-/// Join room, send disconnect message to old room
-/// send join message to new room
+/// Join room, if room does not exists create new one.
 impl Handler<Join> for ChatServer {
     type Result = ();
 
     fn handle(&mut self, msg: Join, _: &mut Context<Self>) {
         let Join { id, name } = msg;
-        let mut rooms = Vec::new();
 
         // remove session from all rooms
-        for (n, sessions) in &mut self.rooms {
-            if sessions.remove(&id) {
-                rooms.push(n.to_owned());
-            }
-        }
-        // send message to other users
-        for room in rooms {
-            self.send_message(&room, "Someone disconnected", 0);
-        }
-
-        if self.rooms.get_mut(&name).is_none() {
-            self.rooms.insert(name.clone(), HashSet::new());
+        for sessions in self.rooms.values_mut() {
+            sessions.remove(&id);
         }
-        self.send_message(&name, "Someone connected", id);
-        self.rooms.get_mut(&name).unwrap().insert(id);
+        self.rooms.entry(name).or_default().insert(id);
     }
 }
@@ -0,0 +1,612 @@
+//! 接口的请求示例代码: curl, HTTPie, fetch, axios, Python requests 和 Rust reqwest
+//! 接口本身按文档生成mock的请求数据, test_data中的每个用例使用用例里面的数据
+
+use serde_json::{json, Map, Value};
+
+use crate::api;
+use crate::db;
+
+/// 没有真实token时示例中使用的token
+const TOKEN_PLACEHOLDER: &str = "<token>";
+
+#[derive(Debug, Clone, PartialEq)]
+enum BodyMode {
+    None,
+    Json,
+    FormData,
+    Urlencoded,
+    // 其它的请求体按文本发送, 带上对应的Content-Type
+    Raw(String),
+}
+
+/// 一个请求示例需要的数据
+#[derive(Debug, Clone)]
+struct SnippetRequest {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body_mode: BodyMode,
+    body: Value,
+}
+
+/// 接口的示例代码, base_url是生成示例使用的服务器地址, 页面上切换服务器时替换它
+pub fn get_api_snippets(
+    api: &db::ApiData,
+    base_url: &str,
+    auth_doc: &Option<db::AuthDoc>,
+) -> Value {
+    let mut items = Vec::new();
+    for method in &api.method {
+        let method = match method.as_str() {
+            "WEBSOCKET" => continue,
+            "GRAPHQL" => "POST",
+            "*" => "GET",
+            m => m,
+        };
+        let url_param = mock_value(&api.url_param);
+        let query = mock_value(&api.query);
+        let body = if method == "GET" || method == "HEAD" {
+            Value::Null
+        } else {
+            mock_value(&api.body)
+        };
+        let url = format!("{}{}", base_url, fill_url_params(&api.url, &url_param));
+        let request = build_request(api, method, &url, &query, &body, auth_doc);
+        items.push(snippet_item(method, "", &request));
+    }
+
+    if let Some(test_data) = api.test_data.as_array() {
+        let default_method = match api.method.first().map(|m| m.as_str()) {
+            Some("GRAPHQL") => "POST",
+            Some("WEBSOCKET") | None => return json!({"base_url": base_url, "items": items}),
+            Some(m) => m,
+        };
+        for (i, case) in test_data.iter().enumerate() {
+            let method = match case.get("method") {
+                Some(Value::String(m)) => m.as_str(),
+                Some(Value::Array(m)) => {
+                    m.first().and_then(|m| m.as_str()).unwrap_or(default_method)
+                }
+                _ => default_method,
+            };
+            let name = ["name", "desc"]
+                .iter()
+                .find_map(|key| case.get(*key).and_then(|v| v.as_str()))
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("test case {}", i + 1));
+            // 用例中的url是完整的请求地址, 没有的时候用接口的mock参数
+            let url = match case.get("url").and_then(|v| v.as_str()) {
+                Some(url) => url.to_string(),
+                None => fill_url_params(&api.url, &mock_value(&api.url_param)),
+            };
+            let url = format!("{}{}", base_url, url);
+            let query = case.get("query").cloned().unwrap_or(Value::Null);
+            let body = case.get("body").cloned().unwrap_or(Value::Null);
+            let request = build_request(api, method, &url, &query, &body, auth_doc);
+            items.push(snippet_item(method, &name, &request));
+        }
+    }
+
+    json!({"base_url": base_url, "items": items})
+}
+
+fn snippet_item(method: &str, name: &str, request: &SnippetRequest) -> Value {
+    let mut item = Map::new();
+    item.insert("method".to_string(), json!(method));
+    item.insert("name".to_string(), json!(name));
+    item.insert("curl".to_string(), json!(curl_snippet(request)));
+    item.insert("httpie".to_string(), json!(httpie_snippet(request)));
+    item.insert("fetch".to_string(), json!(fetch_snippet(request)));
+    item.insert("axios".to_string(), json!(axios_snippet(request)));
+    item.insert("python".to_string(), json!(python_snippet(request)));
+    item.insert("reqwest".to_string(), json!(reqwest_snippet(request)));
+    Value::Object(item)
+}
+
+pub fn mock_value(model: &Value) -> Value {
+    if model.is_null() {
+        return Value::Null;
+    }
+    api::create_mock_value(model, "", model, &Value::Null, &Value::Null).unwrap_or(Value::Null)
+}
+
+fn build_request(
+    api: &db::ApiData,
+    method: &str,
+    url: &str,
+    query: &Value,
+    body: &Value,
+    auth_doc: &Option<db::AuthDoc>,
+) -> SnippetRequest {
+    let mut query_pairs = value_pairs(query);
+    let mut headers = Vec::new();
+    if let Value::Object(request_headers) = mock_value(&api.request_headers) {
+        for (key, value) in request_headers {
+            headers.push((key, value_string(&value)));
+        }
+    }
+
+    // token按_auth.json5中的第一个auth_place放置
+    if api.auth {
+        if let Some(token_place) = auth_doc.as_ref().and_then(|a| a.token_places.first()) {
+            let token = format!("{}{}", token_place.prefix, TOKEN_PLACEHOLDER);
+            match token_place.place.as_str() {
+                "query" => query_pairs.push((token_place.name.to_string(), token)),
+                "cookie" => headers.push((
+                    "Cookie".to_string(),
+                    format!("{}={}", token_place.name, token),
+                )),
+                _ => headers.push((token_place.name.to_string(), token)),
+            }
+        }
+    }
+
+    let body_mode = if body.is_null() {
+        BodyMode::None
+    } else {
+        match api.body_mode.as_str() {
+            "form-data" => BodyMode::FormData,
+            "urlencoded" | "x-www-form-urlencoded" => BodyMode::Urlencoded,
+            "text" => BodyMode::Raw("text/plain".to_string()),
+            "html" => BodyMode::Raw("text/html".to_string()),
+            "xml" => BodyMode::Raw("application/xml".to_string()),
+            "javascript" => BodyMode::Raw("application/javascript".to_string()),
+            _ => BodyMode::Json,
+        }
+    };
+    let content_type = match &body_mode {
+        BodyMode::Json => Some("application/json"),
+        BodyMode::Urlencoded => Some("application/x-www-form-urlencoded"),
+        BodyMode::Raw(content_type) => Some(content_type.as_str()),
+        // multipart的boundary由客户端生成
+        BodyMode::None | BodyMode::FormData => None,
+    };
+    if let Some(content_type) = content_type {
+        if !headers
+            .iter()
+            .any(|(k, _)| k.to_lowercase() == "content-type")
+        {
+            headers.insert(0, ("Content-Type".to_string(), content_type.to_string()));
+        }
+    }
+
+    let mut url = url.to_string();
+    if !query_pairs.is_empty() {
+        let query_string: Vec<String> = query_pairs
+            .iter()
+            .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+            .collect();
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(&query_string.join("&"));
+    }
+
+    SnippetRequest {
+        method: method.to_uppercase(),
+        url,
+        headers,
+        body_mode,
+        body: body.clone(),
+    }
+}
+
+/// url中的 {param} 替换为参数值
+fn fill_url_params(url: &str, url_param: &Value) -> String {
+    let mut result = String::new();
+    let mut rest = url;
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        let name = rest[start + 1..end].split(':').next().unwrap_or("").trim();
+        match url_param.get(name) {
+            Some(value) => result.push_str(&url_encode(&value_string(value))),
+            None => result.push_str(&rest[start..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 对象转为 key=value 的列表, 数组的值重复key
+pub fn value_pairs(value: &Value) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    if let Value::Object(obj) = value {
+        for (key, value) in obj {
+            match value {
+                Value::Array(items) => {
+                    for item in items {
+                        pairs.push((key.to_string(), value_string(item)));
+                    }
+                }
+                Value::Null => {}
+                _ => pairs.push((key.to_string(), value_string(value))),
+            }
+        }
+    }
+    pairs
+}
+
+pub fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        Value::Null => "".to_string(),
+        v => v.to_string(),
+    }
+}
+
+pub fn url_encode(s: &str) -> String {
+    let mut result = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                result.push(b as char)
+            }
+            _ => result.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    result
+}
+
+/// 文本请求体的内容, 其它请求体是格式化的json
+fn body_text(request: &SnippetRequest) -> String {
+    match &request.body {
+        Value::String(s) => s.to_string(),
+        v => serde_json::to_string_pretty(v).unwrap_or_default(),
+    }
+}
+
+fn indent(s: &str, prefix: &str) -> String {
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+fn js_string(s: &str) -> String {
+    Value::String(s.to_string()).to_string()
+}
+
+fn curl_snippet(request: &SnippetRequest) -> String {
+    let mut lines = vec![format!(
+        "curl -X {} {}",
+        request.method,
+        shell_quote(&request.url)
+    )];
+    for (key, value) in &request.headers {
+        lines.push(format!(
+            "  -H {}",
+            shell_quote(&format!("{}: {}", key, value))
+        ));
+    }
+    match &request.body_mode {
+        BodyMode::None => {}
+        BodyMode::FormData => {
+            for (key, value) in value_pairs(&request.body) {
+                lines.push(format!(
+                    "  -F {}",
+                    shell_quote(&format!("{}={}", key, value))
+                ));
+            }
+        }
+        BodyMode::Urlencoded => {
+            for (key, value) in value_pairs(&request.body) {
+                lines.push(format!(
+                    "  --data-urlencode {}",
+                    shell_quote(&format!("{}={}", key, value))
+                ));
+            }
+        }
+        BodyMode::Json => lines.push(format!("  -d {}", shell_quote(&request.body.to_string()))),
+        BodyMode::Raw(_) => {
+            lines.push(format!("  --data-raw {}", shell_quote(&body_text(request))))
+        }
+    }
+    lines.join(" \\\n")
+}
+
+fn httpie_snippet(request: &SnippetRequest) -> String {
+    let mut command = vec!["http".to_string()];
+    match &request.body_mode {
+        BodyMode::FormData => command.push("--multipart".to_string()),
+        BodyMode::Urlencoded => command.push("--form".to_string()),
+        _ => {}
+    }
+    command.push(request.method.to_string());
+    command.push(shell_quote(&request.url));
+    let mut lines = vec![command.join(" ")];
+    for (key, value) in &request.headers {
+        lines.push(format!("  {}", shell_quote(&format!("{}:{}", key, value))));
+    }
+    match &request.body_mode {
+        BodyMode::None => {}
+        BodyMode::Json => match &request.body {
+            Value::Object(obj) => {
+                // 字符串用 key=value, 其它类型用 key:=json
+                for (key, value) in obj {
+                    let item = match value {
+                        Value::String(s) => format!("{}={}", key, s),
+                        v => format!("{}:={}", key, v),
+                    };
+                    lines.push(format!("  {}", shell_quote(&item)));
+                }
+            }
+            v => lines.push(format!("  --raw {}", shell_quote(&v.to_string()))),
+        },
+        BodyMode::FormData | BodyMode::Urlencoded => {
+            for (key, value) in value_pairs(&request.body) {
+                lines.push(format!("  {}", shell_quote(&format!("{}={}", key, value))));
+            }
+        }
+        BodyMode::Raw(_) => lines.push(format!("  --raw {}", shell_quote(&body_text(request)))),
+    }
+    lines.join(" \\\n")
+}
+
+/// js对象形式的请求头
+fn js_headers(request: &SnippetRequest, indent_str: &str) -> String {
+    let items: Vec<String> = request
+        .headers
+        .iter()
+        .map(|(k, v)| format!("{}  {}: {},", indent_str, js_string(k), js_string(v)))
+        .collect();
+    format!("{{\n{}\n{}}}", items.join("\n"), indent_str)
+}
+
+/// FormData和URLSearchParams的构造代码, 返回构造语句和请求体变量
+fn js_form_body(request: &SnippetRequest) -> (String, String) {
+    match &request.body_mode {
+        BodyMode::FormData => {
+            let mut code = "const form = new FormData();\n".to_string();
+            for (key, value) in value_pairs(&request.body) {
+                code.push_str(&format!(
+                    "form.append({}, {});\n",
+                    js_string(&key),
+                    js_string(&value)
+                ));
+            }
+            (code + "\n", "form".to_string())
+        }
+        BodyMode::Urlencoded => {
+            let mut code = "const params = new URLSearchParams();\n".to_string();
+            for (key, value) in value_pairs(&request.body) {
+                code.push_str(&format!(
+                    "params.append({}, {});\n",
+                    js_string(&key),
+                    js_string(&value)
+                ));
+            }
+            (code + "\n", "params".to_string())
+        }
+        BodyMode::Json => (
+            "".to_string(),
+            format!("JSON.stringify({})", indent(&body_text(request), "  ")),
+        ),
+        BodyMode::Raw(_) => ("".to_string(), js_string(&body_text(request))),
+        BodyMode::None => ("".to_string(), "".to_string()),
+    }
+}
+
+fn fetch_snippet(request: &SnippetRequest) -> String {
+    let (prepare, body) = js_form_body(request);
+    let mut options = vec![format!("  method: {},", js_string(&request.method))];
+    if !request.headers.is_empty() {
+        options.push(format!("  headers: {},", js_headers(request, "  ")));
+    }
+    if !body.is_empty() {
+        options.push(format!("  body: {},", body));
+    }
+    format!(
+        "{}const response = await fetch({}, {{\n{}\n}});\nconst data = await response.json();",
+        prepare,
+        js_string(&request.url),
+        options.join("\n")
+    )
+}
+
+fn axios_snippet(request: &SnippetRequest) -> String {
+    let (prepare, body) = js_form_body(request);
+    let mut options = vec![
+        format!("  method: {},", js_string(&request.method.to_lowercase())),
+        format!("  url: {},", js_string(&request.url)),
+    ];
+    if !request.headers.is_empty() {
+        options.push(format!("  headers: {},", js_headers(request, "  ")));
+    }
+    if request.body_mode == BodyMode::Json {
+        // axios会自己序列化json
+        options.push(format!("  data: {},", indent(&body_text(request), "  ")));
+    } else if !body.is_empty() {
+        options.push(format!("  data: {},", body));
+    }
+    format!(
+        "{}const {{ data }} = await axios({{\n{}\n}});",
+        prepare,
+        options.join("\n")
+    )
+}
+
+/// json值转为python的字面量
+fn python_value(value: &Value, indent_str: &str) -> String {
+    let next = format!("{}    ", indent_str);
+    match value {
+        Value::Null => "None".to_string(),
+        Value::Bool(true) => "True".to_string(),
+        Value::Bool(false) => "False".to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => Value::String(s.to_string()).to_string(),
+        Value::Array(items) if items.is_empty() => "[]".to_string(),
+        Value::Array(items) => {
+            let items: Vec<String> = items
+                .iter()
+                .map(|v| format!("{}{},", next, python_value(v, &next)))
+                .collect();
+            format!("[\n{}\n{}]", items.join("\n"), indent_str)
+        }
+        Value::Object(obj) if obj.is_empty() => "{}".to_string(),
+        Value::Object(obj) => {
+            let items: Vec<String> = obj
+                .iter()
+                .map(|(k, v)| format!("{}{}: {},", next, js_string(k), python_value(v, &next)))
+                .collect();
+            format!("{{\n{}\n{}}}", items.join("\n"), indent_str)
+        }
+    }
+}
+
+fn python_snippet(request: &SnippetRequest) -> String {
+    let method = request.method.to_lowercase();
+    let standard =
+        ["get", "post", "put", "patch", "delete", "head", "options"].contains(&method.as_str());
+    let mut args = Vec::new();
+    if !standard {
+        args.push(format!("    {},", js_string(&request.method)));
+    }
+    args.push(format!("    {},", js_string(&request.url)));
+    if !request.headers.is_empty() {
+        let headers: Map<String, Value> = request
+            .headers
+            .iter()
+            .map(|(k, v)| (k.to_string(), json!(v)))
+            .collect();
+        args.push(format!(
+            "    headers={},",
+            python_value(&Value::Object(headers), "    ")
+        ));
+    }
+    match &request.body_mode {
+        BodyMode::None => {}
+        BodyMode::Json => args.push(format!("    json={},", python_value(&request.body, "    "))),
+        BodyMode::FormData => {
+            // files参数才会使用multipart, 文本字段的文件名为None
+            let fields: Vec<String> = value_pairs(&request.body)
+                .iter()
+                .map(|(k, v)| format!("        ({}, (None, {})),", js_string(k), js_string(v)))
+                .collect();
+            args.push(format!("    files=[\n{}\n    ],", fields.join("\n")));
+        }
+        BodyMode::Urlencoded => {
+            let fields: Vec<String> = value_pairs(&request.body)
+                .iter()
+                .map(|(k, v)| format!("        ({}, {}),", js_string(k), js_string(v)))
+                .collect();
+            args.push(format!("    data=[\n{}\n    ],", fields.join("\n")));
+        }
+        BodyMode::Raw(_) => args.push(format!("    data={},", js_string(&body_text(request)))),
+    }
+    format!(
+        "import requests\n\nresponse = requests.{}(\n{}\n)\nprint(response.json())",
+        if standard { method.as_str() } else { "request" },
+        args.join("\n")
+    )
+}
+
+fn reqwest_snippet(request: &SnippetRequest) -> String {
+    let method = request.method.to_lowercase();
+    let mut lines = vec!["let client = reqwest::Client::new();".to_string()];
+    if request.body_mode == BodyMode::FormData {
+        let mut form = "let form = reqwest::multipart::Form::new()".to_string();
+        for (key, value) in value_pairs(&request.body) {
+            form.push_str(&format!(
+                "\n    .text({}, {})",
+                js_string(&key),
+                js_string(&value)
+            ));
+        }
+        lines.push(form + ";");
+    }
+    lines.push("let response = client".to_string());
+    if ["get", "post", "put", "patch", "delete", "head"].contains(&method.as_str()) {
+        lines.push(format!("    .{}({})", method, js_string(&request.url)));
+    } else {
+        lines.push(format!(
+            "    .request(reqwest::Method::from_bytes(b{}).unwrap(), {})",
+            js_string(&request.method),
+            js_string(&request.url)
+        ));
+    }
+    for (key, value) in &request.headers {
+        // json, form和multipart会自己设置Content-Type
+        let is_raw = matches!(request.body_mode, BodyMode::Raw(_));
+        if key.to_lowercase() == "content-type" && !is_raw {
+            continue;
+        }
+        lines.push(format!(
+            "    .header({}, {})",
+            js_string(key),
+            js_string(value)
+        ));
+    }
+    match &request.body_mode {
+        BodyMode::None => {}
+        BodyMode::Json => lines.push(format!(
+            "    .json(&serde_json::json!({}))",
+            indent(&body_text(request), "    ")
+        )),
+        BodyMode::FormData => lines.push("    .multipart(form)".to_string()),
+        BodyMode::Urlencoded => {
+            let fields: Vec<String> = value_pairs(&request.body)
+                .iter()
+                .map(|(k, v)| format!("({}, {})", js_string(k), js_string(v)))
+                .collect();
+            lines.push(format!("    .form(&[{}])", fields.join(", ")));
+        }
+        BodyMode::Raw(_) => lines.push(format!("    .body({})", js_string(&body_text(request)))),
+    }
+    lines.push("    .send()\n    .await?;".to_string());
+    lines.push("let data: serde_json::Value = response.json().await?;".to_string());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snippet_request_test() {
+        let api = db::ApiData {
+            url: "/users/{user_id}/".to_string(),
+            method: vec!["PUT".to_string()],
+            auth: true,
+            ..Default::default()
+        };
+        let auth_doc = Some(db::AuthDoc {
+            token_places: vec![db::TokenPlace {
+                place: "query".to_string(),
+                name: "access_token".to_string(),
+                prefix: "".to_string(),
+            }],
+            ..Default::default()
+        });
+        let url = fill_url_params(&api.url, &json!({"user_id": 3}));
+        let request = build_request(
+            &api,
+            "PUT",
+            &format!("http://127.0.0.1:9000{}", url),
+            &json!({"tags": ["a b", "c"]}),
+            &json!({"name": "it's"}),
+            &auth_doc,
+        );
+        assert_eq!(
+            "http://127.0.0.1:9000/users/3/?tags=a%20b&tags=c&access_token=%3Ctoken%3E",
+            request.url
+        );
+        assert_eq!(
+            "curl -X PUT 'http://127.0.0.1:9000/users/3/?tags=a%20b&tags=c&access_token=%3Ctoken%3E' \\\n  -H 'Content-Type: application/json' \\\n  -d '{\"name\":\"it'\\''s\"}'",
+            curl_snippet(&request)
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! 流式返回的接口: Server-Sent Events 和 NDJSON
+//! 按照接口文档中的stream设置，每隔interval毫秒发送一条事件
+
+use std::time::Duration;
+
+use actix_rt::time::delay_for;
+use actix_web::{http, web, Error, HttpResponse};
+use futures::stream;
+use serde_json::{Map, Value};
+
+use crate::api;
+
+/// 没有定义events的时候，按照response生成多少条mock事件
+const DEFAULT_COUNT: u64 = 10;
+/// 循环发送又没有设置间隔的时候，使用的默认间隔
+const DEFAULT_LOOP_INTERVAL: u64 = 1000;
+
+/// 接口文档中的stream设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamConfig {
+    /// sse 或者 ndjson
+    pub mode: String,
+    /// 每条事件之间的间隔, 毫秒
+    pub interval: u64,
+    /// 发送的事件, 为空时按照response生成mock事件
+    pub events: Vec<Value>,
+    /// 没有定义events时mock事件的条数
+    pub count: u64,
+    /// 发送完之后是否从头开始继续发送
+    pub looping: bool,
+}
+
+impl StreamConfig {
+    /// 解析stream设置, stream:true 表示全部使用默认设置
+    pub fn from_value(value: &Value) -> Option<StreamConfig> {
+        let empty = Map::new();
+        let obj = match value {
+            Value::Bool(true) => &empty,
+            Value::Object(obj) => obj,
+            _ => return None,
+        };
+
+        let mode = match obj.get("mode").and_then(|v| v.as_str()) {
+            Some("ndjson") => "ndjson",
+            _ => "sse",
+        };
+        let events = match obj.get("events").and_then(|v| v.as_array()) {
+            Some(v) => v.clone(),
+            None => Vec::new(),
+        };
+        let looping = obj.get("loop").and_then(|v| v.as_bool()).unwrap_or(false);
+        let mut interval = obj.get("interval").and_then(|v| v.as_u64()).unwrap_or(0);
+        if looping && interval == 0 {
+            interval = DEFAULT_LOOP_INTERVAL;
+        }
+
+        Some(StreamConfig {
+            mode: mode.to_string(),
+            interval,
+            events,
+            count: obj
+                .get("count")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_COUNT),
+            looping,
+        })
+    }
+
+    /// 一轮发送多少条事件
+    fn cycle_len(&self) -> u64 {
+        if self.events.is_empty() {
+            self.count
+        } else {
+            self.events.len() as u64
+        }
+    }
+
+    /// 第n条事件的id, 没有定义id就使用序号
+    fn event_id(&self, n: u64) -> String {
+        if !self.events.is_empty() {
+            let event = &self.events[(n % self.cycle_len()) as usize];
+            match event.get("id") {
+                Some(Value::String(id)) => return id.to_string(),
+                Some(Value::Number(id)) => return id.to_string(),
+                _ => (),
+            }
+        }
+        (n + 1).to_string()
+    }
+
+    /// 客户端带着Last-Event-ID重连时，从这个事件之后继续发送
+    pub fn resume_position(&self, last_event_id: Option<&str>) -> u64 {
+        let last_event_id = match last_event_id {
+            Some(v) if !v.is_empty() => v,
+            _ => return 0,
+        };
+        for n in 0..self.cycle_len() {
+            if self.event_id(n) == last_event_id {
+                return n + 1;
+            }
+        }
+        last_event_id.parse::<u64>().unwrap_or(0)
+    }
+}
+
+/// 流式返回的发送状态
+struct StreamState {
+    config: StreamConfig,
+    /// 事件没有定义data时使用的数据, 为空就按response生成mock数据
+    case_response: Value,
+    response_model: Value,
+    request_body: Value,
+    request_query: Value,
+    /// 下一条要发送的事件序号
+    next: u64,
+    /// 已经发送了多少条
+    sent: u64,
+}
+
+impl StreamState {
+    /// 下一条事件要等待的时间和发送的内容，发送完了返回None
+    fn next_chunk(&mut self) -> Option<(u64, String)> {
+        let cycle_len = self.config.cycle_len();
+        if cycle_len == 0 || (!self.config.looping && self.next >= cycle_len) {
+            return None;
+        }
+
+        let n = self.next;
+        let event = if self.config.events.is_empty() {
+            &Value::Null
+        } else {
+            &self.config.events[(n % cycle_len) as usize]
+        };
+
+        // 第一条立即发送，之后按事件的delay或者interval等待
+        let delay = if self.sent == 0 {
+            0
+        } else {
+            event
+                .get("delay")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(self.config.interval)
+        };
+
+        let data = event.get("data").unwrap_or(&self.case_response);
+        let data = if data.is_null() {
+            api::create_mock_value(
+                &self.response_model,
+                "",
+                &self.response_model,
+                &self.request_body,
+                &self.request_query,
+            )
+            .unwrap_or(Value::Null)
+        } else {
+            api::parse_test_case_response(
+                data,
+                "",
+                &self.response_model,
+                &self.request_body,
+                &self.request_query,
+            )
+        };
+
+        let chunk = if self.config.mode == "ndjson" {
+            format!("{}\n", data)
+        } else {
+            let event_name = event.get("event").and_then(|v| v.as_str());
+            sse_event(&self.config.event_id(n), event_name, &data)
+        };
+
+        self.next += 1;
+        self.sent += 1;
+        Some((delay, chunk))
+    }
+}
+
+/// 生成一条SSE事件，多行的数据拆分为多个data行
+pub fn sse_event(id: &str, event_name: Option<&str>, data: &Value) -> String {
+    let mut result = format!("id: {}\n", id);
+    if let Some(event_name) = event_name {
+        result.push_str(&format!("event: {}\n", event_name));
+    }
+    let data = match data {
+        Value::String(s) => s.to_string(),
+        v => v.to_string(),
+    };
+    for line in data.split('\n') {
+        result.push_str(&format!("data: {}\n", line));
+    }
+    result.push('\n');
+    result
+}
+
+/// 按stream设置返回流式的response
+pub fn stream_response(
+    config: StreamConfig,
+    status_code: http::StatusCode,
+    last_event_id: Option<&str>,
+    case_response: &Value,
+    response_model: &Value,
+    request_body: &Value,
+    request_query: &Value,
+) -> HttpResponse {
+    let content_type = if config.mode == "ndjson" {
+        "application/x-ndjson"
+    } else {
+        "text/event-stream"
+    };
+    let state = StreamState {
+        next: config.resume_position(last_event_id),
+        config,
+        case_response: case_response.clone(),
+        response_model: response_model.clone(),
+        request_body: request_body.clone(),
+        request_query: request_query.clone(),
+        sent: 0,
+    };
+
+    let body = stream::unfold(state, |mut state| async move {
+        let (delay, chunk) = state.next_chunk()?;
+        if delay > 0 {
+            // 异步等待，不阻塞worker处理其它请求
+            delay_for(Duration::from_millis(delay)).await;
+        }
+        Some((Ok::<_, Error>(web::Bytes::from(chunk)), state))
+    });
+
+    HttpResponse::build(status_code)
+        .content_type(content_type)
+        .header("Cache-Control", "no-cache")
+        .streaming(body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stream_resume_test() {
+        let config = StreamConfig::from_value(&json!({
+            "events": [
+                {"id": "a", "event": "created", "data": {"id": 1}},
+                {"id": "b", "data": "hello\nworld"},
+                {"data": 3}
+            ]
+        }))
+        .unwrap();
+        assert_eq!("sse", config.mode);
+        assert_eq!(0, config.resume_position(None));
+        assert_eq!(2, config.resume_position(Some("b")));
+        assert_eq!(3, config.resume_position(Some("3")));
+
+        let mut state = StreamState {
+            next: config.resume_position(Some("a")),
+            config,
+            case_response: Value::Null,
+            response_model: Value::Null,
+            request_body: Value::Null,
+            request_query: Value::Null,
+            sent: 0,
+        };
+        assert_eq!(
+            Some((0, "id: b\ndata: hello\ndata: world\n\n".to_string())),
+            state.next_chunk()
+        );
+        assert_eq!(
+            Some((0, "id: 3\ndata: 3\n\n".to_string())),
+            state.next_chunk()
+        );
+        assert_eq!(None, state.next_chunk());
+    }
+}
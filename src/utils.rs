@@ -1,22 +1,27 @@
 use std::collections::{HashMap, HashSet};
-use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
+use actix::Addr;
 use actix_web::web;
 use chrono::Local;
 use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 
 use crate::db;
-
-/// 建立异步线程，监控文件改动，当改动的时候，就重新生成文件
-pub fn watch_api_docs_change(data: web::Data<Mutex<db::Database>>) {
-    let current_dir = env::current_dir().expect("Failed to determine current directory");
-    let ignore_file_path = current_dir.join(".gitignore");
-    let current_dir = current_dir.to_str().unwrap().to_string();
+use crate::live_reload::{DocEvent, LiveReloadServer};
+
+/// 建立异步线程，监控项目目录的文件改动，当改动的时候，就重新生成文件
+/// 并把改动通知给打开的文档页面
+pub fn watch_api_docs_change(
+    root: PathBuf,
+    data: web::Data<db::DataStore>,
+    live_reload: Addr<LiveReloadServer>,
+) {
+    let ignore_file_path = root.join(".gitignore");
+    let current_dir = root.to_str().unwrap().to_string();
 
     thread::spawn(move || {
         let (tx, rx) = channel();
@@ -27,17 +32,48 @@ pub fn watch_api_docs_change(data: web::Data<Mutex<db::Database>>) {
         loop {
             match rx.recv() {
                 Ok(event) => match event {
-                    DebouncedEvent::NoticeWrite(f) => {
-                        update_api_data(f, &current_dir, &ignore_file_path, data.clone());
+                    DebouncedEvent::Write(f) => {
+                        update_api_data(
+                            f,
+                            &current_dir,
+                            &ignore_file_path,
+                            data.clone(),
+                            &live_reload,
+                        );
                     }
                     DebouncedEvent::Create(f) => {
-                        update_api_data(f, &current_dir, &ignore_file_path, data.clone());
+                        update_api_data(
+                            f,
+                            &current_dir,
+                            &ignore_file_path,
+                            data.clone(),
+                            &live_reload,
+                        );
                     }
-                    DebouncedEvent::NoticeRemove(f) => {
-                        update_api_data(f, &current_dir, &ignore_file_path, data.clone());
+                    DebouncedEvent::Remove(f) => {
+                        update_api_data(
+                            f,
+                            &current_dir,
+                            &ignore_file_path,
+                            data.clone(),
+                            &live_reload,
+                        );
                     }
-                    DebouncedEvent::Rename(_f1, f2) => {
-                        update_api_data(f2, &current_dir, &ignore_file_path, data.clone());
+                    DebouncedEvent::Rename(f1, f2) => {
+                        update_api_data(
+                            f1,
+                            &current_dir,
+                            &ignore_file_path,
+                            data.clone(),
+                            &live_reload,
+                        );
+                        update_api_data(
+                            f2,
+                            &current_dir,
+                            &ignore_file_path,
+                            data.clone(),
+                            &live_reload,
+                        );
                     }
                     _ => {}
                 },
@@ -47,147 +83,164 @@ pub fn watch_api_docs_change(data: web::Data<Mutex<db::Database>>) {
     });
 }
 
-/// 发生文件改动/新增时，更新接口文档数据
-/// README.md, json数据
+/// 发生文件改动/新增/删除时，更新接口文档数据
+/// 只重新加载改动的文件，以及通过fileindex_data找到的引用了这个文件的接口文档
 fn update_api_data(
     filepath: PathBuf,
     current_dir: &str,
     ignore_file_path: &PathBuf,
-    data: web::Data<Mutex<db::Database>>,
+    data: web::Data<db::DataStore>,
+    live_reload: &Addr<LiveReloadServer>,
 ) {
     if let Ok(ignore) = gitignore::File::new(ignore_file_path) {
-        let is_ignore = ignore.is_excluded(&filepath).unwrap();
-        if is_ignore {
+        if let Ok(true) = ignore.is_excluded(&filepath) {
             return;
         }
     }
 
     let filepath = filepath.to_str().unwrap();
-
-    let mut api_docs: HashMap<String, db::ApiDoc> = HashMap::new();
-    let mut api_data: HashMap<String, Vec<Arc<Mutex<db::ApiData>>>> = HashMap::new();
-    let mut fileindex_data: HashMap<String, HashSet<String>> = HashMap::new();
-    let websocket_api = Arc::new(Mutex::new(db::ApiData::default()));
-
-    let mut data = data.lock().unwrap();
     let filename = filepath.trim_start_matches(&format!("{}/", current_dir));
+    if filename.starts_with(".") || filename.contains("/.") {
+        return;
+    }
 
-    // 暂时全部使用全局重新加载: 这里需要改进
-    *data = db::Database::load();
+    let root = Path::new(current_dir);
+    let event = if filename == "README.md" {
+        let (basic_data, settings_value) = db::load_basic_data(root);
+        update_snapshot(&data, |data| {
+            data.basic_data = basic_data;
+            data.settings = settings_value;
+        });
+        DocEvent::Update(filename.to_string())
+    } else if filename == "_settings.json5" || filename == "_settings.json" {
+        // 全局配置会影响所有接口的数据，需要全局重新加载，加载完成后再替换
+        data.store(Arc::new(db::Database::load(root)));
+        get_json_file_event(root, filename)
+    } else if filename == "_auth.json5" || filename == "_auth.json" {
+        update_snapshot(&data, |data| {
+            data.auth_doc = db::load_auth_data(root, &data.api_docs);
+        });
+        get_json_file_event(root, filename)
+    } else if filename.ends_with("$_folder.md") {
+        update_snapshot(&data, |data| {
+            data.reload_folder_menu(root, filename);
+            data.refresh_search_index(root, filename);
+        });
+        DocEvent::Update(filename.to_string())
+    } else if filename.ends_with(".md") {
+        update_snapshot(&data, |data| {
+            db::remove_menu(root, &mut data.menus, filename);
+            if root.join(filename).exists() {
+                db::Database::load_a_md_doc(root, filename, &mut data.menus);
+            }
+            data.refresh_search_index(root, filename);
+        });
+        if root.join(filename).exists() {
+            DocEvent::Update(filename.to_string())
+        } else {
+            DocEvent::Delete(filename.to_string())
+        }
+    } else {
+        for event in update_api_doc_files(root, filename, &data) {
+            live_reload.do_send(event);
+        }
+        return;
+    };
     println!("{} data update done. {}", filename, Local::now());
-    return;
+    live_reload.do_send(event);
+}
 
-    let mut delete_files: Vec<String> = Vec::new();
-    let mut parse_error_code = 0;
-    let mut menus: HashMap<String, db::Menu> = HashMap::new();
+/// 在当前数据快照的副本上进行修改，然后原子替换为新的快照
+/// 只有文件监控线程会修改数据，所以不会有修改丢失的问题
+fn update_snapshot<F: FnOnce(&mut db::Database)>(data: &db::DataStore, f: F) {
+    let mut new_data = db::Database::clone(&data.load());
+    f(&mut new_data);
+    data.store(Arc::new(new_data));
+}
 
-    if filename == "README.md" {
-        let (basic_data, settings_value) = db::load_basic_data();
-        data.basic_data = basic_data;
-        data.settings = settings_value;
-    } else if filepath.ends_with(".md") {
-        // 全局重新加载
-        *data = db::Database::load();
-        println!("{} data update done. {}", filename, Local::now());
-        return;
-    } else if !filepath.ends_with(".json5") {
-        return;
-    } else if filename == "_settings.json5" {
-        // 全局重新加载
-        *data = db::Database::load();
-        println!("{} data update done. {}", filename, Local::now());
-        return;
-    } else if filename == "_auth.json5" {
-        // 加载auth
-        let auth_data = db::load_auth_data(&data.api_docs);
-        data.auth_doc = auth_data;
-        println!("{} data update done. {}", filename, Local::now());
-        return;
-    } else if filename.contains("_data/") {
-        // 如果修改的是_data里面的文件，需要通过fileindex_datal来找到对应文件更新
-        if let Some(ref_files) = data.fileindex_data.get(filename) {
-            // 把找到的文件全部重新load一遍
-            for ref_file in ref_files {
-                parse_error_code = db::Database::load_a_api_json_file(
-                    ref_file,
-                    &data.basic_data,
-                    &mut api_data,
-                    &mut api_docs,
-                    websocket_api.clone(),
-                    &mut fileindex_data,
-                    &mut menus,
-                );
-                if parse_error_code == -2 {
-                    delete_files.push(ref_file.to_string());
-                }
+/// 根据json文件是否能正常解析，生成对应的通知事件
+fn get_json_file_event(root: &Path, filename: &str) -> DocEvent {
+    if !root.join(filename).exists() {
+        return DocEvent::Delete(filename.to_string());
+    }
+    match db::get_json_file_error(root, filename) {
+        Some(e) => DocEvent::Error(filename.to_string(), e),
+        None => DocEvent::Update(filename.to_string()),
+    }
+}
+
+/// 重新加载接口文档文件
+/// 如果修改的是_data里面被引用的文件，就重新加载所有引用了它的接口文档
+/// 在当前快照的副本上更新，完成后再替换，重新加载过程中不影响接口请求
+/// 返回每个文件的更新结果，用于通知文档页面
+fn update_api_doc_files(root: &Path, filename: &str, data: &db::DataStore) -> Vec<DocEvent> {
+    let mut events = Vec::new();
+    let mut new_data = db::Database::clone(&data.load());
+    let doc_files: Vec<String> = match new_data.fileindex_data.get(filename) {
+        Some(ref_files) => ref_files.iter().cloned().collect(),
+        None => {
+            if !filename.ends_with(".json5") || filename.contains("_data/") {
+                return events;
             }
+            vec![filename.to_string()]
         }
-    } else {
-        parse_error_code = db::Database::load_a_api_json_file(
-            filename,
-            &data.basic_data,
+    };
+
+    if !doc_files.contains(&filename.to_string()) && !filename.ends_with(".graphql") {
+        // 被引用的数据文件本身出错，也要通知出来
+        match get_json_file_event(root, filename) {
+            DocEvent::Update(_) => (),
+            event => events.push(event),
+        }
+    }
+
+    for doc_file in &doc_files {
+        let doc_file = doc_file.to_string();
+        let mut api_docs: HashMap<String, db::ApiDoc> = HashMap::new();
+        let mut api_data: HashMap<String, Vec<Arc<db::ApiData>>> = HashMap::new();
+        let mut fileindex_data: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut menus: HashMap<String, db::Menu> = HashMap::new();
+
+        let parse_code = db::Database::load_a_api_json_file(
+            root,
+            &doc_file,
+            &new_data.basic_data,
             &mut api_data,
             &mut api_docs,
-            websocket_api.clone(),
             &mut fileindex_data,
             &mut menus,
         );
-        if parse_error_code == -2 {
-            delete_files.push(filename.to_string());
-        }
-    }
-
-    if parse_error_code < 0 {
-        // 没有解析错误，才会打印解析完成
-        for delete_file in &delete_files {
-            // 发生删除文件，要分别删除api_docs和api_data中的数据
-            let mut urls: Vec<String> = Vec::new();
-            if let Some(api_doc) = &data.api_docs.get(delete_file) {
-                // 删除 api_data中 api_doc包含的url
-                for api in api_doc.apis.iter() {
-                    let url = &api.lock().unwrap().url;
-                    urls.push(url.to_string());
-                }
+        match parse_code {
+            1 => {
+                new_data.remove_api_doc_file(root, &doc_file);
+                new_data.merge_api_doc_data(api_docs, api_data, fileindex_data, menus);
+                println!("{} data update done. {}", doc_file, Local::now());
+                events.push(DocEvent::Update(doc_file));
             }
-
-            // 删除api_doc
-            data.api_docs.remove(delete_file);
-            // 删除api_data中的url
-            for url in &urls {
-                data.api_data.remove(url);
+            -2 => {
+                // 文件被删除
+                new_data.remove_api_doc_file(root, &doc_file);
+                println!("deleted file {} {}", doc_file, Local::now());
+                events.push(DocEvent::Delete(doc_file));
             }
-            if parse_error_code == -2 {
-                println!("deleted file {} {}", filename, Local::now());
+            -4 => {
+                let e = format!("file {} json5 data is not a object", doc_file);
+                events.push(DocEvent::Error(doc_file, e));
             }
-        }
-    }
-
-    for (k, v) in api_data {
-        data.api_data.insert(k, v);
-    }
-
-    for (k, v) in api_docs {
-        data.api_docs.insert(k, v);
-    }
-
-    data.websocket_api = websocket_api;
-
-    for (ref_file, doc_files) in fileindex_data {
-        if &ref_file != "" {
-            match data.fileindex_data.get_mut(&ref_file) {
-                Some(x) => {
-                    for f in doc_files {
-                        x.insert(f);
-                    }
-                }
-                None => {
-                    data.fileindex_data.insert(ref_file, doc_files);
+            _ => {
+                // 解析出错的时候，保留原来的数据
+                if let DocEvent::Error(f, e) = get_json_file_event(root, &doc_file) {
+                    events.push(DocEvent::Error(f, e));
                 }
             }
         }
     }
-
-    if parse_error_code == 1 {
-        println!("{} data update done. {}", filename, Local::now());
+    new_data.refresh_router();
+    for doc_file in &doc_files {
+        new_data.refresh_search_index(root, doc_file);
     }
+    // auth中的权限可能是按接口文档加载的，接口文档更新后需要重新解析
+    new_data.auth_doc = db::load_auth_data(root, &new_data.api_docs);
+    data.store(Arc::new(new_data));
+    events
 }
@@ -0,0 +1,314 @@
+//! 接口的版本和废弃信息
+//! 文档, 接口和字段都可以设置 version, since 和 deprecated, 接口没有设置的时候使用文档的设置
+//! deprecated 可以是 true, 一段说明, 或者 {desc, replacement, date, sunset}
+//! 同一个url可以有多个版本的接口, 请求时通过 Accept-Version 请求头或者url前面的版本号选择
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::HttpResponse;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde_json::{json, Map, Value};
+
+use crate::db;
+
+/// 选择接口版本的请求头
+pub const VERSION_HEADER: &str = "Accept-Version";
+
+/// 接口上的设置优先, 然后是$ref的接口, 文档, 最后是全局的apis设置
+pub fn get_lifecycle_value<'a>(
+    key: &str,
+    api: &'a Value,
+    ref_data: &'a Value,
+    doc: &'a Map<String, Value>,
+    global_data: &'a Value,
+) -> Option<&'a Value> {
+    api.get(key)
+        .or_else(|| ref_data.get(key))
+        .or_else(|| doc.get(key))
+        .or_else(|| global_data.pointer(&format!("/apis/{}", key)))
+}
+
+/// 版本号可以写成字符串或者数字
+pub fn get_version_string(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(v)) => v.trim().to_string(),
+        Some(Value::Number(v)) => v.to_string(),
+        Some(Value::Null) | None => "".to_string(),
+        Some(v) => {
+            log::error!("version {} is not a string", v);
+            "".to_string()
+        }
+    }
+}
+
+/// 统一成 {desc, replacement, date, sunset} 的格式, 没有废弃时是Null
+pub fn parse_deprecated(value: Option<&Value>) -> Value {
+    match value {
+        Some(Value::Bool(true)) => json!({}),
+        Some(Value::String(desc)) => json!({ "desc": desc }),
+        Some(Value::Object(v)) => Value::Object(v.clone()),
+        Some(Value::Bool(false)) | Some(Value::Null) | None => Value::Null,
+        Some(v) => {
+            log::error!("deprecated {} need a bool, string or object", v);
+            Value::Null
+        }
+    }
+}
+
+/// deprecated 是不是废弃信息, 而不是一个叫deprecated的字段
+/// 废弃信息是 bool, 一段说明, 或者只有 desc, replacement, date, sunset 的对象
+pub fn is_lifecycle_deprecated(value: &Value) -> bool {
+    match value {
+        Value::Bool(_) | Value::String(_) => true,
+        Value::Object(v) => {
+            !v.is_empty()
+                && v.keys()
+                    .all(|k| ["desc", "replacement", "date", "sunset"].contains(&k.as_str()))
+        }
+        _ => false,
+    }
+}
+
+/// 废弃了但是没有设置date时不会返回Deprecation响应头, 加载的时候提醒一下
+pub fn check_deprecated_date(name: &str, deprecated: &Value) {
+    if !deprecated.is_null() && deprecated.get("date").and_then(|v| v.as_str()).is_none() {
+        log::warn!(
+            "{} is deprecated without a date, Deprecation header will not be sent",
+            name
+        );
+    }
+}
+
+/// 废弃接口返回的响应头, Deprecation(RFC 9745), Sunset(RFC 8594) 和替代接口的Link
+/// RFC 9745的Deprecation是 @时间戳, 没有设置date时不返回Deprecation
+pub fn deprecation_headers(deprecated: &Value) -> Vec<(&'static str, String)> {
+    let mut headers = Vec::new();
+    if deprecated.is_null() {
+        return headers;
+    }
+    let get = |key: &str| deprecated.get(key).and_then(|v| v.as_str()).unwrap_or("");
+
+    if let Some(date) = parse_date(get("date")) {
+        headers.push(("deprecation", format!("@{}", date.timestamp())));
+    }
+    if let Some(sunset) = parse_date(get("sunset")) {
+        headers.push((
+            "sunset",
+            sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ));
+    }
+    let replacement = get("replacement");
+    if !replacement.is_empty() {
+        headers.push((
+            "link",
+            format!("<{}>; rel=\"successor-version\"", replacement),
+        ));
+    }
+    headers
+}
+
+/// 给废弃接口的返回加上废弃信息的响应头
+pub fn add_deprecation_headers(mut response: HttpResponse, api: &db::ApiData) -> HttpResponse {
+    for (name, value) in deprecation_headers(&api.deprecated) {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response
+                .headers_mut()
+                .insert(HeaderName::from_static(name), value);
+        }
+    }
+    response
+}
+
+/// 支持 2026-12-31 和 RFC 3339 格式的时间
+fn parse_date(s: &str) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Some(date.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(Utc.from_utc_datetime(&date.and_hms(0, 0, 0)))
+}
+
+/// 版本号中的数字, v1.10 => [1, 10]
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+/// 按版本号中的数字比较, v2 < v10
+pub fn compare_version(a: &str, b: &str) -> Ordering {
+    version_numbers(a)
+        .cmp(&version_numbers(b))
+        .then_with(|| a.cmp(b))
+}
+
+/// 是否是同一个版本, v2 和 2 是同一个版本
+pub fn is_same_version(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let numbers = version_numbers(a);
+    !numbers.is_empty()
+        && numbers == version_numbers(b)
+        && a.trim_start_matches(|c: char| !c.is_ascii_digit())
+            == b.trim_start_matches(|c: char| !c.is_ascii_digit())
+}
+
+/// 接口是否属于这个版本, 没有设置版本的接口属于所有版本
+pub fn is_api_in_version(api: &db::ApiData, version: &str) -> bool {
+    api.version.is_empty() || is_same_version(&api.version, version)
+}
+
+/// 按请求的版本排列同一个url的接口, 请求的版本在前, 其次是没有设置版本的接口
+/// 没有指定版本时最新的版本在前, 请求的版本和没有设置版本的接口都没有时返回空
+pub fn select_api_versions(
+    apis: &[Arc<db::ApiData>],
+    version: Option<&str>,
+) -> Vec<Arc<db::ApiData>> {
+    let mut apis: Vec<Arc<db::ApiData>> = match version {
+        Some(version) => apis
+            .iter()
+            .filter(|api| is_api_in_version(api, version))
+            .cloned()
+            .collect(),
+        None => apis.to_vec(),
+    };
+    apis.sort_by(|a, b| match (a.version.is_empty(), b.version.is_empty()) {
+        (false, true) => Ordering::Less,
+        (true, false) => Ordering::Greater,
+        _ => compare_version(&b.version, &a.version),
+    });
+    apis
+}
+
+/// 请求的接口版本和去掉版本前缀后的地址
+/// Accept-Version 请求头优先, 请求地址找不到接口的时候, 再看第一个路径段是不是接口的版本号, 如 /v2/users/
+pub fn get_request_version(
+    data: &db::Database,
+    header: Option<&str>,
+    req_path: &str,
+) -> (Option<String>, String) {
+    let header = header.map(|v| v.trim()).filter(|v| !v.is_empty());
+    if let Some(version) = header {
+        return (Some(version.to_string()), req_path.to_string());
+    }
+    if data.find_api_data(req_path).is_some() {
+        return (None, req_path.to_string());
+    }
+
+    let path = req_path.trim_start_matches('/');
+    let (segment, rest) = match path.find('/') {
+        Some(i) => (&path[..i], &path[i..]),
+        None => return (None, req_path.to_string()),
+    };
+    if let Some(apis) = data.find_api_data(rest) {
+        if apis
+            .iter()
+            .any(|api| !api.version.is_empty() && is_same_version(&api.version, segment))
+        {
+            return (Some(segment.to_string()), rest.to_string());
+        }
+    }
+    (None, req_path.to_string())
+}
+
+/// 文档中所有的版本号, 从旧到新
+pub fn get_versions(api_docs: &HashMap<String, db::ApiDoc>) -> Vec<String> {
+    let mut versions = BTreeSet::new();
+    for doc in api_docs.values() {
+        if !doc.version.is_empty() {
+            versions.insert(doc.version.to_string());
+        }
+        for api in &doc.apis {
+            if !api.version.is_empty() {
+                versions.insert(api.version.to_string());
+            }
+        }
+    }
+    let mut versions: Vec<String> = versions.into_iter().collect();
+    versions.sort_by(|a, b| compare_version(a, b));
+    versions
+}
+
+/// 只保留这个版本的接口文档菜单, 文件夹中的文档都被去掉时文件夹也去掉
+pub fn filter_menus(
+    menus: &HashMap<String, db::Menu>,
+    api_docs: &HashMap<String, db::ApiDoc>,
+    version: &str,
+) -> HashMap<String, db::Menu> {
+    let mut result = HashMap::new();
+    for (key, menu) in menus {
+        if let Some(doc) = api_docs.get(&menu.filename) {
+            if !doc.apis.is_empty() && !doc.apis.iter().any(|api| is_api_in_version(api, version)) {
+                continue;
+            }
+        }
+        let children = filter_menus(&menu.children, api_docs, version);
+        if !menu.children.is_empty() && children.is_empty() && menu.filename.is_empty() {
+            continue;
+        }
+        result.insert(
+            key.to_string(),
+            db::Menu {
+                children,
+                ..menu.clone()
+            },
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn api(version: &str) -> Arc<db::ApiData> {
+        Arc::new(db::ApiData {
+            url: "/users/".to_string(),
+            version: version.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn select_api_versions_test() {
+        let apis = vec![api(""), api("v2"), api("v10"), api("v1")];
+        let versions = |version| -> Vec<String> {
+            select_api_versions(&apis, version)
+                .iter()
+                .map(|api| api.version.to_string())
+                .collect()
+        };
+        assert_eq!(vec!["v10", "v2", "v1", ""], versions(None));
+        assert_eq!(vec!["v2", ""], versions(Some("2")));
+        assert_eq!(vec![""], versions(Some("v3")));
+    }
+
+    #[test]
+    fn deprecation_headers_test() {
+        let deprecated = parse_deprecated(Some(&json!({
+            "date": "2026-01-01",
+            "sunset": "2026-12-31",
+            "replacement": "/v2/users/"
+        })));
+        assert_eq!(
+            vec![
+                ("deprecation", "@1767225600".to_string()),
+                ("sunset", "Thu, 31 Dec 2026 00:00:00 GMT".to_string()),
+                (
+                    "link",
+                    "</v2/users/>; rel=\"successor-version\"".to_string()
+                ),
+            ],
+            deprecation_headers(&deprecated)
+        );
+        // 没有date时不返回Deprecation
+        assert!(deprecation_headers(&parse_deprecated(Some(&json!(true)))).is_empty());
+        assert!(deprecation_headers(&parse_deprecated(Some(&json!(false)))).is_empty());
+    }
+}
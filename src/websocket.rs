@@ -1,9 +1,12 @@
 use std::time::{Duration, Instant};
 
 use actix::*;
-use actix_web::{web, App, Error, HttpRequest, HttpResponse, HttpServer};
+use actix_web::web;
 use actix_web_actors::ws;
+use serde_json::{json, Value};
 
+use crate::api;
+use crate::db;
 use crate::server;
 
 /// How often heartbeat pings are sent
@@ -11,21 +14,22 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
-pub struct WsChatSession {
+/// 一个websocket接口的连接，按照接口文档回复客户端发来的消息
+pub struct WsMockSession {
     /// unique session id
     pub id: usize,
     /// Client must send ping at least once per 10 seconds (CLIENT_TIMEOUT),
     /// otherwise we drop connection.
     pub hb: Instant,
-    /// joined room
-    pub room: String,
-    /// peer name
-    pub name: Option<String>,
+    /// 连接的请求地址，同一个地址的连接在同一个room
+    pub path: String,
+    /// 接口文档数据，每条消息都按最新的接口文档回复
+    pub data: web::Data<db::DataStore>,
     /// Chat server
     pub addr: Addr<server::ChatServer>,
 }
 
-impl Actor for WsChatSession {
+impl Actor for WsMockSession {
     type Context = ws::WebsocketContext<Self>;
 
     /// Method is called on actor start.
@@ -37,8 +41,6 @@ impl Actor for WsChatSession {
         // register self in chat server. `AsyncContext::wait` register
         // future within context, but context waits until this future resolves
         // before processing any other events.
-        // HttpContext::state() is instance of WsChatSessionState, state is shared
-        // across all routes within application
         let addr = ctx.address();
         self.addr
             .send(server::Connect {
@@ -47,7 +49,14 @@ impl Actor for WsChatSession {
             .into_actor(self)
             .then(|res, act, ctx| {
                 match res {
-                    Ok(res) => act.id = res,
+                    Ok(res) => {
+                        act.id = res;
+                        act.addr.do_send(server::Join {
+                            id: act.id,
+                            name: act.path.clone(),
+                        });
+                        act.start_push(ctx);
+                    }
                     // something is wrong with chat server
                     _ => ctx.stop(),
                 }
@@ -64,7 +73,7 @@ impl Actor for WsChatSession {
 }
 
 /// Handle messages from chat server, we simply send it to peer websocket
-impl Handler<server::Message> for WsChatSession {
+impl Handler<server::Message> for WsMockSession {
     type Result = ();
 
     fn handle(&mut self, msg: server::Message, ctx: &mut Self::Context) {
@@ -73,7 +82,7 @@ impl Handler<server::Message> for WsChatSession {
 }
 
 /// WebSocket message handler
-impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsMockSession {
     fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
         let msg = match msg {
             Err(_) => {
@@ -83,7 +92,6 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
             Ok(msg) => msg,
         };
 
-        println!("WEBSOCKET MESSAGE: {:?}", msg);
         match msg {
             ws::Message::Ping(msg) => {
                 self.hb = Instant::now();
@@ -92,73 +100,7 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
             ws::Message::Pong(_) => {
                 self.hb = Instant::now();
             }
-            ws::Message::Text(text) => {
-                let m = text.trim();
-                // we check for /sss type of messages
-                if m.starts_with('/') {
-                    let v: Vec<&str> = m.splitn(2, ' ').collect();
-                    match v[0] {
-                        "/list" => {
-                            // Send ListRooms message to chat server and wait for
-                            // response
-                            println!("List rooms");
-                            self.addr
-                                .send(server::ListRooms)
-                                .into_actor(self)
-                                .then(|res, _, ctx| {
-                                    match res {
-                                        Ok(rooms) => {
-                                            for room in rooms {
-                                                ctx.text(room);
-                                            }
-                                        }
-                                        _ => println!("Something is wrong"),
-                                    }
-                                    fut::ready(())
-                                })
-                                .wait(ctx)
-                            // .wait(ctx) pauses all events in context,
-                            // so actor wont receive any new messages until it get list
-                            // of rooms back
-                        }
-                        "/join" => {
-                            if v.len() == 2 {
-                                self.room = v[1].to_owned();
-                                self.addr.do_send(server::Join {
-                                    id: self.id,
-                                    name: self.room.clone(),
-                                });
-
-                                ctx.text("joined");
-                            } else {
-                                ctx.text("!!! room name is required");
-                            }
-                        }
-                        "/name" => {
-                            if v.len() == 2 {
-                                self.name = Some(v[1].to_owned());
-                                ctx.text(self.name.as_ref().unwrap());
-                            } else {
-                                ctx.text("!!! name is required");
-                            }
-
-                        }
-                        _ => ctx.text(format!("!!! unknown command: {:?}", m)),
-                    }
-                } else {
-                    let msg = if let Some(ref name) = self.name {
-                        format!("{}: {}", name, m)
-                    } else {
-                        m.to_owned()
-                    };
-                    // send message to chat server
-                    self.addr.do_send(server::ClientMessage {
-                        id: self.id,
-                        msg,
-                        room: self.room.clone(),
-                    })
-                }
-            }
+            ws::Message::Text(text) => self.reply(text.trim(), ctx),
             ws::Message::Binary(_) => println!("Unexpected binary"),
             ws::Message::Close(_) => {
                 ctx.stop();
@@ -171,7 +113,79 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsChatSession {
     }
 }
 
-impl WsChatSession {
+/// 连接后自动推送的push和它们的 (延迟, 间隔) 毫秒数, manual的push只能手动触发
+fn scheduled_pushes(push: &Value) -> Vec<(Value, u64, u64)> {
+    let push_list = match push.as_array() {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+    push_list
+        .iter()
+        .filter(|push| push.get("manual").and_then(|v| v.as_bool()) != Some(true))
+        .map(|push| {
+            let delay = push.get("delay").and_then(|v| v.as_u64()).unwrap_or(0);
+            let interval = push.get("interval").and_then(|v| v.as_u64()).unwrap_or(0);
+            (push.clone(), delay, interval)
+        })
+        .collect()
+}
+
+impl WsMockSession {
+    /// 按接口文档回复客户端的消息
+    /// 消息是json的时候按json匹配test_data，否则按字符串匹配
+    fn reply(&self, text: &str, ctx: &mut ws::WebsocketContext<Self>) {
+        let message = match serde_json::from_str::<Value>(text) {
+            Ok(v) => v,
+            Err(_) => Value::String(text.to_string()),
+        };
+
+        let data = self.data.load();
+        let api = match api::find_websocket_api(&data, &self.path) {
+            Some(api) => api,
+            None => {
+                // 接口文档已经删除了这个websocket接口
+                ctx.text(
+                    json!({
+                        "code": -1,
+                        "msg": format!("this api address {} no websocket api match", self.path)
+                    })
+                    .to_string(),
+                );
+                return;
+            }
+        };
+
+        let (response, delay) = api::get_websocket_response(&api, &message);
+        let response = message_text(response);
+        if delay > 0 {
+            ctx.run_later(Duration::from_millis(delay), move |_, ctx| {
+                ctx.text(response);
+            });
+        } else {
+            ctx.text(response);
+        }
+    }
+
+    /// 按接口文档中的push定义，连接后主动推送消息
+    /// delay是连接后多久推送, 设置了interval就按间隔一直推送, manual的只通过推送接口手动触发
+    fn start_push(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        let data = self.data.load();
+        let api = match api::find_websocket_api(&data, &self.path) {
+            Some(api) => api,
+            None => return,
+        };
+        for (push, delay, interval) in scheduled_pushes(&api.push) {
+            ctx.run_later(Duration::from_millis(delay), move |_, ctx| {
+                ctx.text(message_text(api::get_websocket_push_message(&push)));
+                if interval > 0 {
+                    ctx.run_interval(Duration::from_millis(interval), move |_, ctx| {
+                        ctx.text(message_text(api::get_websocket_push_message(&push)));
+                    });
+                }
+            });
+        }
+    }
+
     /// helper method that sends ping to client every second.
     ///
     /// also this method checks heartbeats from client
@@ -196,3 +210,37 @@ impl WsChatSession {
         });
     }
 }
+
+/// 发送给客户端的文本，字符串直接发送，其它的转为json
+pub fn message_text(message: Value) -> String {
+    match message {
+        Value::String(s) => s,
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scheduled_pushes_test() {
+        let push = json!([
+            {"name": "status changed", "delay": 1000, "interval": 5000, "response": {}},
+            {"name": "order closed", "manual": true, "response": {}},
+            {"name": "hello", "response": "hi"}
+        ]);
+        let pushes: Vec<(String, u64, u64)> = scheduled_pushes(&push)
+            .into_iter()
+            .map(|(p, delay, interval)| (p["name"].as_str().unwrap().to_string(), delay, interval))
+            .collect();
+        assert_eq!(
+            vec![
+                ("status changed".to_string(), 1000, 5000),
+                ("hello".to_string(), 0, 0)
+            ],
+            pushes
+        );
+        assert!(scheduled_pushes(&Value::Null).is_empty());
+    }
+}